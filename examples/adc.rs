@@ -0,0 +1,39 @@
+//! Demonstrates the ADCs
+//!
+//! Connect a potentiometer's wiper to A0 (pin 14). The
+//! example reads A0 with ADC1, and logs the 12 bit readings
+//! over USB. It also reads A12 (pin 26), which is only
+//! connected to ADC2.
+//!
+//! Success criteria: turning the potentiometer changes the
+//! logged A0 reading between 0 and 4095.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::adc::{Averaging, Resolution};
+use bsp::rt::entry;
+use embedded_hal::adc::OneShot;
+use teensy4_bsp as bsp;
+
+#[entry]
+fn main() -> ! {
+    let mut p = bsp::Peripherals::take().unwrap();
+    p.usb.init(Default::default());
+    p.systick.delay(5_000);
+
+    let mut adc1 = p.adc1.clock(&mut p.ccm.handle);
+    adc1.set_resolution(Resolution::Bits12).unwrap();
+    adc1.set_averaging(Averaging::Avg16).unwrap();
+    let mut adc2 = p.adc2.clock(&mut p.ccm.handle);
+
+    let mut analog = bsp::analog_pins!(p.pins);
+    loop {
+        let a0: u16 = nb::block!(adc1.read(&mut analog.a0)).unwrap();
+        let a12: u16 = nb::block!(adc2.read(&mut analog.a12)).unwrap();
+        log::info!("A0: {}, A12: {}", a0, a12);
+        p.systick.delay(250);
+    }
+}
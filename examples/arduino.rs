@@ -0,0 +1,50 @@
+//! Demonstrates Arduino-style, pin-level I/O
+//!
+//! Connect a potentiometer's wiper to pin 14 (A0), and an LED
+//! to pin 2. The potentiometer sets the LED's brightness. The
+//! board's LED blinks, and the analog reading is logged over USB.
+//!
+//! NOTE: This example requires the `arduino` feature to be
+//! enabled.
+//!
+//! Success criteria: turning the potentiometer dims and brightens
+//! the LED on pin 2.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::arduino::{Arduino, PinMode};
+use bsp::rt::entry;
+use teensy4_bsp as bsp;
+
+const LED: u8 = 13;
+const PWM: u8 = 2;
+const ANALOG: u8 = 14;
+
+#[entry]
+fn main() -> ! {
+    let mut p = bsp::Peripherals::take().unwrap();
+    p.usb.init(Default::default());
+    p.systick.delay(5000);
+    let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+    let mut arduino = Arduino::new(
+        p.pins,
+        (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+        (p.adc1, p.adc2),
+        &mut p.ccm.handle,
+        clocks.ipg_hz,
+    );
+
+    arduino.pin_mode(LED, PinMode::Output).unwrap();
+    arduino.analog_read_resolution(12).unwrap();
+    arduino.analog_write_resolution(12).unwrap();
+    loop {
+        let value = arduino.analog_read(ANALOG).unwrap();
+        arduino.analog_write(PWM, value as u32).unwrap();
+        arduino.digital_toggle(LED).unwrap();
+        log::info!("A0: {}", value);
+        p.systick.delay(250);
+    }
+}
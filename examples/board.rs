@@ -0,0 +1,41 @@
+//! Demonstrates single-call board initialization
+//!
+//! The example initializes the board, then builds UART2 on pins
+//! 14 and 15. It echoes each received byte, and toggles the LED.
+//!
+//! Pinout:
+//! - Pin 14: UART2_TX
+//! - Pin 15: UART2_RX
+//!
+//! Success criteria: bytes sent to the Teensy are echoed back, and
+//! each byte toggles the LED. Received bytes are logged over USB.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::rt::entry;
+use embedded_hal::serial::{Read, Write};
+use teensy4_bsp as bsp;
+
+const BAUD: u32 = 115_200;
+
+#[entry]
+fn main() -> ! {
+    let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+    board.delay.delay(5_000);
+    log::info!("ARM clock: {}Hz", board.clocks.arm_hz);
+
+    let mut uart = board
+        .uart
+        .p14_p15(&mut board.ccm.handle, board.pins.p14, board.pins.p15, BAUD)
+        .unwrap();
+    loop {
+        if let Ok(byte) = uart.read() {
+            log::info!("Received {:#X}", byte);
+            nb::block!(uart.write(byte)).unwrap();
+            board.led.toggle();
+        }
+    }
+}
@@ -24,6 +24,8 @@
 //! pinout.
 
 #![no_main]
+// Without its interrupt handler, the example has unused items.
+#![cfg_attr(feature = "async-interrupts", allow(dead_code, unused_imports))]
 #![no_std]
 
 extern crate panic_halt;
@@ -45,6 +47,7 @@ const SPI_BAUD_RATE_HZ: u32 = 1_000_000;
 ///
 /// It clears the interrupt, and completes the transfer.
 /// We'll WFI, then check the flag to see if we triggered.
+#[cfg(not(feature = "async-interrupts"))]
 #[interrupt]
 unsafe fn DMA9_DMA25() {
     let spi = SPI_DMA.as_mut().unwrap();
@@ -161,6 +164,15 @@ fn rx_buffer_mut<F: FnOnce(&mut RxBuffer) -> R, R>(act: F) -> Option<R> {
 type HardwareFlag = bsp::hal::gpio::GPIO1IO26<bsp::hal::gpio::GPIO1, bsp::hal::gpio::Output>;
 static mut HARDWARE_FLAG: Option<HardwareFlag> = None;
 
+/// The `"async-interrupts"` feature registers the `DMA9_DMA25` handler that this example
+/// defines. Build the example without that feature.
+#[cfg(feature = "async-interrupts")]
+#[entry]
+fn main() -> ! {
+    panic!("the dma_spi example defines its own `DMA9_DMA25` handler");
+}
+
+#[cfg(not(feature = "async-interrupts"))]
 #[entry]
 fn main() -> ! {
     let mut peripherals = bsp::Peripherals::take().unwrap();
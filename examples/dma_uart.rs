@@ -11,6 +11,8 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handler, the example has unused items.
+#![cfg_attr(feature = "async-interrupts", allow(dead_code, unused_imports))]
 
 extern crate panic_halt;
 
@@ -32,12 +34,6 @@ const BAUD: u32 = 115_200;
 type TxBuffer = bsp::hal::dma::Linear<u8>;
 type RxBuffer = bsp::hal::dma::Circular<u8>;
 
-#[repr(align(64))]
-struct Align64(bsp::hal::dma::Buffer<[u8; 64]>);
-
-static TX_MEM: bsp::hal::dma::Buffer<[u8; 64]> = bsp::hal::dma::Buffer::new([0; 64]);
-static RX_MEM: Align64 = Align64(bsp::hal::dma::Buffer::new([0; 64]));
-
 static TX_BUFFER: Mutex<RefCell<Option<TxBuffer>>> = Mutex::new(RefCell::new(None));
 static RX_BUFFER: Mutex<RefCell<Option<RxBuffer>>> = Mutex::new(RefCell::new(None));
 
@@ -56,6 +52,7 @@ static TX_READY: AtomicBool = AtomicBool::new(false);
 static RX_READY: AtomicBool = AtomicBool::new(false);
 
 /// The DMA interrupt matches the selected DMA channels in the demo's setup.
+#[cfg(not(feature = "async-interrupts"))]
 #[bsp::rt::interrupt]
 unsafe fn DMA7_DMA23() {
     let uart = DMA_PERIPHERAL.as_mut().unwrap();
@@ -78,6 +75,15 @@ unsafe fn DMA7_DMA23() {
     }
 }
 
+/// The `"async-interrupts"` feature registers the `DMA7_DMA23` handler that this example
+/// defines. Build the example without that feature.
+#[cfg(feature = "async-interrupts")]
+#[entry]
+fn main() -> ! {
+    panic!("the dma_uart example defines its own `DMA7_DMA23` handler");
+}
+
+#[cfg(not(feature = "async-interrupts"))]
 #[entry]
 fn main() -> ! {
     let mut peripherals = bsp::Peripherals::take().unwrap();
@@ -116,7 +122,11 @@ fn main() -> ! {
     };
     let mut led = bsp::configure_led(&mut peripherals.gpr, peripherals.pins.p13);
 
-    let rx_buffer = match bsp::hal::dma::Circular::new(&RX_MEM.0) {
+    // Buffers from the DMA pool are aligned for circular transfers, and aren't cached
+    let tx_mem = bsp::dma::linear([0; 64]).unwrap();
+    let rx_mem = bsp::dma::circular([0; 64]).unwrap();
+
+    let rx_buffer = match bsp::hal::dma::Circular::new(rx_mem) {
         Ok(circular) => circular,
         Err(error) => {
             log::error!("Unable to create circular RX buffer: {:?}", error);
@@ -126,7 +136,7 @@ fn main() -> ! {
 
     free(|cs| {
         *RX_BUFFER.borrow(cs).borrow_mut() = Some(rx_buffer);
-        *TX_BUFFER.borrow(cs).borrow_mut() = bsp::hal::dma::Linear::new(&TX_MEM);
+        *TX_BUFFER.borrow(cs).borrow_mut() = bsp::hal::dma::Linear::new(tx_mem);
     });
 
     'start: loop {
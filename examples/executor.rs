@@ -0,0 +1,68 @@
+//! Demonstrates async tasks on the BSP's executor
+//!
+//! Connect a button between Teensy pin 2 and ground. One task
+//! blinks the LED. Another task waits for button presses, and
+//! changes the blink period.
+//!
+//! NOTE: This example requires the `pin-interrupts` feature
+//! to be enabled.
+//!
+//! Success criteria: the LED blinks. Each button press changes
+//! the blink rate, and the period is logged over USB.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::executor;
+use bsp::gpio::{DynPin, Pull};
+use bsp::pin_interrupt::{self, Edge};
+use bsp::rt::entry;
+use teensy4_bsp as bsp;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Blink half-period, in milliseconds
+static PERIOD_MS: AtomicU32 = AtomicU32::new(500);
+
+async fn blink(mut led: DynPin) {
+    loop {
+        led.toggle();
+        executor::sleep(PERIOD_MS.load(Ordering::Relaxed)).await;
+    }
+}
+
+async fn button(button: DynPin) {
+    let mut presses = pin_interrupt::edges(button, Edge::Falling);
+    loop {
+        presses.next().await;
+        let period = match PERIOD_MS.load(Ordering::Relaxed) {
+            period if period <= 50 => 500,
+            period => period / 2,
+        };
+        PERIOD_MS.store(period, Ordering::Relaxed);
+        log::info!("Blinking every {}ms", period);
+        // Debounce
+        executor::sleep(20).await;
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let mut peripherals = bsp::Peripherals::take().unwrap();
+    peripherals.usb.init(Default::default());
+    peripherals.systick.delay(5_000);
+
+    let mut pins = peripherals.pins.erase();
+    let mut led = pins.take(13).unwrap();
+    led.set_output();
+
+    let mut input = pins.take(2).unwrap();
+    input.set_pull(Pull::Up47k);
+    input.set_hysteresis(true);
+
+    executor::spawn(blink(led)).unwrap();
+    executor::spawn(button(input)).unwrap();
+    executor::run();
+}
@@ -5,6 +5,8 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handler, the example has unused items.
+#![cfg_attr(feature = "async-interrupts", allow(dead_code, unused_imports))]
 
 extern crate panic_halt;
 
@@ -21,25 +23,31 @@ static mut TIMER: Option<gpt::GPT> = None;
 /// GPT output compare register selection
 const OCR: gpt::OutputCompareRegister = gpt::OutputCompareRegister::Three;
 
+#[cfg(not(feature = "async-interrupts"))]
 #[interrupt]
 unsafe fn GPT1() {
     TIMER.as_mut().unwrap().output_compare_status(OCR).clear();
 }
 
+/// The `"async-interrupts"` feature registers the `GPT1` handler that this example
+/// defines. Build the example without that feature.
+#[cfg(feature = "async-interrupts")]
+#[entry]
+fn main() -> ! {
+    panic!("the gpt example defines its own `GPT1` handler");
+}
+
+#[cfg(not(feature = "async-interrupts"))]
 #[entry]
 fn main() -> ! {
     let mut periphs = bsp::Peripherals::take().unwrap();
 
-    let (_, ipg_hz) = periphs.ccm.pll1.set_arm_clock(
-        bsp::hal::ccm::PLL1::ARM_HZ,
-        &mut periphs.ccm.handle,
-        &mut periphs.dcdc,
-    );
+    let clocks = bsp::clocks::Profile::default().configure(&mut periphs.ccm, &mut periphs.dcdc);
 
     let mut cfg = periphs.ccm.perclk.configure(
         &mut periphs.ccm.handle,
         bsp::hal::ccm::perclk::PODF::DIVIDE_3,
-        bsp::hal::ccm::perclk::CLKSEL::IPG(ipg_hz),
+        bsp::hal::ccm::perclk::CLKSEL::IPG(clocks.ipg),
     );
 
     let mut gpt1 = periphs.gpt1.clock(&mut cfg);
@@ -0,0 +1,58 @@
+//! Demonstrates GPIO pin-change interrupts
+//!
+//! Connect a button between Teensy pin 2 and ground. The
+//! example enables pin 2's pull-up, and attaches a handler
+//! for the falling edge. The handler toggles the LED.
+//!
+//! NOTE: This example requires the `pin-interrupts` feature
+//! to be enabled.
+//!
+//! Success criteria: each button press toggles the LED. The
+//! press count is logged over USB.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::gpio::{DynPin, Pull};
+use bsp::pin_interrupt::{self, Edge};
+use bsp::rt::entry;
+use teensy4_bsp as bsp;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Number of button presses, incremented by the pin handler
+static PRESSES: AtomicU32 = AtomicU32::new(0);
+
+/// Runs in the GPIO interrupt when pin 2 falls
+fn on_press(_: &mut DynPin) {
+    PRESSES.fetch_add(1, Ordering::Relaxed);
+}
+
+#[entry]
+fn main() -> ! {
+    let mut peripherals = bsp::Peripherals::take().unwrap();
+    peripherals.usb.init(Default::default());
+    peripherals.systick.delay(5_000);
+
+    let mut pins = peripherals.pins.erase();
+    let mut led = pins.take(13).unwrap();
+    led.set_output();
+
+    let mut button = pins.take(2).unwrap();
+    button.set_pull(Pull::Up47k);
+    button.set_hysteresis(true);
+    pin_interrupt::attach(button, Edge::Falling, on_press);
+
+    let mut last = 0;
+    loop {
+        cortex_m::asm::wfi();
+        let presses = PRESSES.load(Ordering::Relaxed);
+        if presses != last {
+            last = presses;
+            led.toggle();
+            log::info!("{} presses", presses);
+        }
+    }
+}
@@ -7,6 +7,8 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handler, the example has unused items.
+#![cfg_attr(feature = "async-interrupts", allow(dead_code, unused_imports))]
 
 extern crate panic_halt;
 
@@ -18,6 +20,7 @@ use teensy4_bsp as bsp;
 
 static mut TIMER: Option<pit::PIT<pit::channel::_3>> = None;
 
+#[cfg(not(feature = "async-interrupts"))]
 #[interrupt]
 unsafe fn PIT() {
     // If this timer expired, `wait()` returns `Ok(())`
@@ -27,6 +30,15 @@ unsafe fn PIT() {
     let _ = TIMER.as_mut().unwrap().wait();
 }
 
+/// The `"async-interrupts"` feature registers the `PIT` handler that this example
+/// defines. Build the example without that feature.
+#[cfg(feature = "async-interrupts")]
+#[entry]
+fn main() -> ! {
+    panic!("the pit example defines its own `PIT` handler");
+}
+
+#[cfg(not(feature = "async-interrupts"))]
 #[entry]
 fn main() -> ! {
     let mut periphs = bsp::Peripherals::take().unwrap();
@@ -37,11 +49,7 @@ fn main() -> ! {
     // busy-loop on an atomic U32, I don't crash in debug
     // builds.
     periphs.systick.delay(25);
-    let (_, ipg_hz) = periphs.ccm.pll1.set_arm_clock(
-        bsp::hal::ccm::PLL1::ARM_HZ,
-        &mut periphs.ccm.handle,
-        &mut periphs.dcdc,
-    );
+    let clocks = bsp::clocks::Profile::default().configure(&mut periphs.ccm, &mut periphs.dcdc);
     periphs.ccm.pll2.set(
         &mut periphs.ccm.handle,
         [
@@ -64,7 +72,7 @@ fn main() -> ! {
     let mut cfg = periphs.ccm.perclk.configure(
         &mut periphs.ccm.handle,
         bsp::hal::ccm::perclk::PODF::DIVIDE_3,
-        bsp::hal::ccm::perclk::CLKSEL::IPG(ipg_hz),
+        bsp::hal::ccm::perclk::CLKSEL::IPG(clocks.ipg),
     );
 
     let (_, _, _, mut timer) = periphs.pit.clock(&mut cfg);
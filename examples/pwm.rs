@@ -1,6 +1,7 @@
-//! The example shows how to allocate PWM controllers and pins,
+//! The example shows how to drive PWM outputs by Teensy pin,
 //! set duty cycles, and enable / disable PWM pins. We use
-//! pin 6 and pin 9 of the Teensy board.
+//! pin 6 and pin 9 of the Teensy board. The two pins share a
+//! FlexPWM submodule, so they share a switching frequency.
 //!
 //! Success criteria: pin 6 starts at 25% duty cycle, and pin 9
 //! starts at 50% duty cycle. They stay that way for 200ms. Pin 9
@@ -15,16 +16,11 @@
 
 extern crate panic_halt;
 
-use bsp::hal::pwm::Channel;
+use bsp::pwm::{self, Pwm};
 use bsp::rt;
-use embedded_hal::Pwm;
+use embedded_hal::PwmPin;
 use teensy4_bsp as bsp;
 
-/// Helper function to represent a duty cycle as a percent
-fn percent(duty: u16) -> f32 {
-    ((duty as f32) * 100.0f32) / (core::u16::MAX as f32)
-}
-
 #[rt::entry]
 fn main() -> ! {
     // Prepare all the BSP peripherals
@@ -34,51 +30,40 @@ fn main() -> ! {
     // Delay is only to let a user set-up their USB serial connection...
     p.systick.delay(5000);
     // Set the core and IPG clock. The IPG clock frequency drives the PWM (sub)modules
-    let (_, ipg_hz) =
-        p.ccm
-            .pll1
-            .set_arm_clock(bsp::hal::ccm::PLL1::ARM_HZ, &mut p.ccm.handle, &mut p.dcdc);
+    let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
     p.systick.delay(100);
-    // Enable the clocks for the PWM2 module
-    let mut pwm2 = p.pwm2.clock(&mut p.ccm.handle);
-    // Get the outputs from the PWM2 module, submodule 2.
-    // Set a 1KHz switching frequency, using a prescalar of 32.
-    let mut sm2 = pwm2
-        .sm2
-        .outputs(
-            &mut pwm2.handle,
-            p.pins.p6.alt2(),
-            p.pins.p9.alt2(),
-            bsp::hal::pwm::Timing {
-                clock_select: bsp::hal::ccm::pwm::ClockSelect::IPG(ipg_hz),
-                prescalar: bsp::hal::ccm::pwm::Prescalar::PRSC_5,
-                switching_period: core::time::Duration::from_micros(1000),
-            },
-        )
-        .unwrap();
+    // Start the PWM modules
+    pwm::init(
+        (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+        &mut p.ccm.handle,
+        clocks.ipg_hz,
+    );
+    // Both pins need the same 1KHz switching frequency, since they
+    // share a submodule.
+    let mut pin6 = Pwm::on_pin(p.pins.p6, 1_000).unwrap();
+    let mut pin9 = Pwm::on_pin(p.pins.p9, 1_000).unwrap();
 
     // Two different duty cycles that will be swapped to show
     // different duty cycles on the same PWM pins
-    let (mut duty1, mut duty2) = (core::u16::MAX / 4, core::u16::MAX / 2);
-    let mut ctrl = sm2.control(&mut pwm2.handle);
+    let (mut duty1, mut duty2) = (0.25, 0.5);
     loop {
         log::info!(
-            "Setting duty cycles {} and {}...",
-            percent(duty1),
-            percent(duty2)
+            "Setting duty cycles {}% and {}%...",
+            duty1 * 100.0,
+            duty2 * 100.0
         );
-        ctrl.enable(Channel::A);
-        ctrl.enable(Channel::B);
-        ctrl.set_duty(Channel::A, duty1);
-        ctrl.set_duty(Channel::B, duty2);
+        pin6.enable();
+        pin9.enable();
+        pin6.set_duty(duty1);
+        pin9.set_duty(duty2);
         p.systick.delay(200);
 
-        log::info!("Disabling 'B' PWM...");
-        ctrl.disable(Channel::B);
+        log::info!("Disabling pin 9 PWM...");
+        pin9.disable();
         p.systick.delay(200);
 
-        log::info!("Disabling 'A' PWM...");
-        ctrl.disable(Channel::A);
+        log::info!("Disabling pin 6 PWM...");
+        pin6.disable();
         p.systick.delay(400);
 
         core::mem::swap(&mut duty1, &mut duty2);
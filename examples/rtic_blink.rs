@@ -1,7 +1,7 @@
 //! An adaptation of the `rtic_led.rs` example that demonstrates:
 //!
 //! 1. how to share late resources and
-//! 2. how to use the systick interrupt to cause the LED to blink.
+//! 2. how to schedule the LED blinks with the BSP's GPT2 monotonic timer.
 //!
 //! Please refer to the [RTIC book](https://rtic.rs) for more information on RTIC.
 //!
@@ -9,16 +9,27 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handlers, the example has unused items.
+#![cfg_attr(feature = "serial-interrupts", allow(dead_code, unused_imports))]
 
+use bsp::monotonic::{Duration, Gpt2};
 use embedded_hal::digital::v2::{OutputPin, ToggleableOutputPin};
 use panic_halt as _;
-use rtic::cyccnt::U32Ext;
 use teensy4_bsp as bsp;
 
-// The CYCCNT counts in clock cycles. Using the clock hz should give us a ~1 second period.
-const PERIOD: u32 = bsp::hal::ccm::PLL1::ARM_HZ;
+// The monotonic timer counts in real time, independent of the core clock.
+const PERIOD: Duration = Duration::from_secs(1);
 
-#[rtic::app(device = teensy4_bsp, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+/// The `"serial-interrupts"` feature registers the `LPUART8` handler, which this example
+/// uses to dispatch tasks. Build the example without that feature.
+#[cfg(feature = "serial-interrupts")]
+#[bsp::rt::entry]
+fn main() -> ! {
+    panic!("the rtic_blink example dispatches tasks on `LPUART8`");
+}
+
+#[cfg(not(feature = "serial-interrupts"))]
+#[rtic::app(device = teensy4_bsp, monotonic = teensy4_bsp::monotonic::Gpt2, peripherals = true)]
 const APP: () = {
     struct Resources {
         led: bsp::LED,
@@ -40,19 +51,19 @@ const APP: () = {
         // this aspect of the processor.
         cx.device.ccm.set_mode(bsp::hal::ccm::ClockMode::Run);
 
-        // Initialise the monotonic CYCCNT timer.
-        cx.core.DWT.enable_cycle_counter();
-
-        // Ensure the ARM clock is configured for the default speed seeing as we use this speed to
-        // determine a 1 second `PERIOD`.
-        cx.device.ccm.pll1.set_arm_clock(
+        // Ensure the ARM clock is configured for the default speed. RTIC's timer queue
+        // uses SYSTICK, which runs at the ARM clock speed.
+        let (arm, _) = cx.device.ccm.pll1.set_arm_clock(
             bsp::hal::ccm::PLL1::ARM_HZ,
             &mut cx.device.ccm.handle,
             &mut cx.device.dcdc,
         );
 
+        // The BSP started the monotonic GPT2 timer. Tell it the new SYSTICK speed.
+        Gpt2::set_core_clock_hz((arm.0).0);
+
         // Schedule the first blink.
-        cx.schedule.blink(cx.start + PERIOD.cycles()).unwrap();
+        cx.schedule.blink(cx.start + PERIOD).unwrap();
 
         let mut led = bsp::configure_led(&mut cx.device.gpr, cx.device.pins.p13);
         led.set_high().unwrap();
@@ -64,7 +75,7 @@ const APP: () = {
     fn blink(cx: blink::Context) {
         cx.resources.led.toggle().unwrap();
         // Schedule the following blink.
-        cx.schedule.blink(cx.scheduled + PERIOD.cycles()).unwrap();
+        cx.schedule.blink(cx.scheduled + PERIOD).unwrap();
     }
 
     // RTIC requires that unused interrupts are declared in an extern block when
@@ -21,6 +21,11 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handlers, the example has unused items.
+#![cfg_attr(
+    any(feature = "async-interrupts", feature = "serial-interrupts"),
+    allow(dead_code, unused_imports)
+)]
 
 use embedded_hal::digital::v2::{OutputPin, ToggleableOutputPin};
 use embedded_hal::serial::Read;
@@ -43,6 +48,16 @@ type Consumer = heapless::spsc::Consumer<'static, Ty, Cap>;
 // The UART receiver.
 type UartRx = bsp::hal::uart::Rx<bsp::hal::iomuxc::uart::module::_2>;
 
+/// The `"async-interrupts"` and `"serial-interrupts"` features register the `DMA7_DMA23`,
+/// `LPUART2`, and `LPUART8` handlers, which this example binds and uses to dispatch tasks.
+/// Build the example without those features.
+#[cfg(any(feature = "async-interrupts", feature = "serial-interrupts"))]
+#[bsp::rt::entry]
+fn main() -> ! {
+    panic!("the rtic_dma_uart_log example binds `DMA7_DMA23` and `LPUART2`");
+}
+
+#[cfg(not(any(feature = "async-interrupts", feature = "serial-interrupts")))]
 #[rtic::app(device = teensy4_bsp, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
 const APP: () = {
     struct Resources {
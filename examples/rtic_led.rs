@@ -16,15 +16,15 @@ extern crate panic_halt;
 use embedded_hal::digital::v2::OutputPin;
 use teensy4_bsp as bsp;
 
-#[rtic::app(device = teensy4_bsp, peripherals = true)]
+#[rtic::app(device = teensy4_bsp::device, peripherals = true)]
 const APP: () = {
     #[init]
     fn init(cx: init::Context) {
         // Cortex-M peripherals
         let _core: cortex_m::Peripherals = cx.core;
 
-        // Device-specific peripherals
-        let mut device: bsp::Peripherals = cx.device;
+        // Device-specific peripherals, created from the HAL peripherals that rtic hands us
+        let mut device = bsp::Peripherals::from_rtic(cx.device);
 
         let mut led = bsp::configure_led(&mut device.gpr, device.pins.p13);
         led.set_high().unwrap();
@@ -15,6 +15,8 @@
 
 #![no_std]
 #![no_main]
+// Without its interrupt handlers, the example has unused items.
+#![cfg_attr(feature = "serial-interrupts", allow(dead_code, unused_imports))]
 
 use embedded_hal::digital::v2::{OutputPin, ToggleableOutputPin};
 use embedded_hal::serial::Read;
@@ -37,6 +39,15 @@ type Consumer = heapless::spsc::Consumer<'static, Ty, Cap>;
 // The UART receiver.
 type UartRx = bsp::hal::uart::Rx<bsp::hal::iomuxc::uart::module::_2>;
 
+/// The `"serial-interrupts"` feature registers the `LPUART2` and `LPUART8` handlers, which
+/// this example binds and uses to dispatch tasks. Build the example without that feature.
+#[cfg(feature = "serial-interrupts")]
+#[bsp::rt::entry]
+fn main() -> ! {
+    panic!("the rtic_uart_log example binds `LPUART2`, and dispatches tasks on `LPUART8`");
+}
+
+#[cfg(not(feature = "serial-interrupts"))]
 #[rtic::app(device = teensy4_bsp, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
 const APP: () = {
     struct Resources {
@@ -0,0 +1,66 @@
+//! Demonstrates a buffered serial port with idle-line detection
+//!
+//! The example uses Serial3, on pins 14 and 15. It collects
+//! received bytes into a packet. When the line is idle for
+//! four characters, it echoes the packet and toggles the LED.
+//! The BSP services the serial port's interrupt.
+//!
+//! Pinout:
+//! - Pin 14: Serial3 TX
+//! - Pin 15: Serial3 RX
+//!
+//! Success criteria: each message sent to the Teensy is echoed
+//! back in one piece. Receive errors are logged over USB.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::rt::entry;
+use bsp::serial::Serial3;
+use embedded_hal::serial::{Read, Write};
+use teensy4_bsp as bsp;
+
+const BAUD: u32 = 115_200;
+
+#[entry]
+fn main() -> ! {
+    let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+    board.delay.delay(5_000);
+
+    let mut serial = Serial3::new(
+        &mut board.uart,
+        &mut board.ccm.handle,
+        board.pins.p14,
+        board.pins.p15,
+        BAUD,
+    )
+    .unwrap();
+    serial.enable_idle_detection(4, None);
+
+    let mut packet = [0u8; bsp::serial::BUFFER_SIZE];
+    let mut len = 0;
+    loop {
+        if !serial.message_received() {
+            continue;
+        }
+        loop {
+            match serial.read() {
+                Ok(byte) if len < packet.len() => {
+                    packet[len] = byte;
+                    len += 1;
+                }
+                Ok(_) => (),
+                Err(nb::Error::Other(error)) => log::warn!("Receive error: {:?}", error),
+                Err(nb::Error::WouldBlock) => break,
+            }
+        }
+        log::info!("Received {} bytes", len);
+        for &byte in &packet[..len] {
+            nb::block!(serial.write(byte)).unwrap();
+        }
+        len = 0;
+        board.led.toggle();
+    }
+}
@@ -0,0 +1,60 @@
+//! Demonstrates a hobby servo and an ESC on FlexPWM outputs
+//!
+//! Connect a servo's signal wire to pin 2, and an ESC's signal
+//! wire to pin 4. The servo receives 50Hz pulses, and the ESC
+//! receives 400Hz pulses.
+//!
+//! Success criteria: the ESC arms after receiving an idle throttle
+//! for three seconds. The servo sweeps from 0 to 180 degrees and
+//! back, while the ESC ramps between idle and 20% throttle. The
+//! angle and pulse width are logged over USB.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+
+use bsp::pwm;
+use bsp::rt;
+use bsp::servo::{Calibration, Servo};
+use teensy4_bsp as bsp;
+
+/// Degrees per step of the sweep
+const STEP_DEG: f32 = 10.0;
+
+#[rt::entry]
+fn main() -> ! {
+    let mut p = bsp::Peripherals::take().unwrap();
+    p.usb.init(Default::default());
+    p.systick.delay(5000);
+    let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+    pwm::init(
+        (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+        &mut p.ccm.handle,
+        clocks.ipg_hz,
+    );
+
+    let mut servo = Servo::on_pin(p.pins.p2, 50, Calibration::STANDARD).unwrap();
+    let mut esc = Servo::on_pin(p.pins.p4, 400, Calibration::STANDARD).unwrap();
+
+    esc.set_throttle(0.0);
+    p.systick.delay(3000);
+
+    let mut angle = 0.0;
+    let mut step = STEP_DEG;
+    loop {
+        servo.set_angle(angle);
+        esc.set_throttle(angle / 180.0 * 0.2);
+        log::info!(
+            "Servo at {} degrees ({}us)",
+            angle,
+            servo.pulse_us().unwrap()
+        );
+        p.systick.delay(100);
+
+        angle += step;
+        if angle >= 180.0 || angle <= 0.0 {
+            step = -step;
+        }
+    }
+}
@@ -25,16 +25,12 @@ fn main() -> ! {
     periphs.systick.delay(25);
     periphs.usb.init(Default::default());
 
-    let (_, ipg_hz) = periphs.ccm.pll1.set_arm_clock(
-        bsp::hal::ccm::PLL1::ARM_HZ,
-        &mut periphs.ccm.handle,
-        &mut periphs.dcdc,
-    );
+    let clocks = bsp::clocks::Profile::default().configure(&mut periphs.ccm, &mut periphs.dcdc);
 
     let mut cfg = periphs.ccm.perclk.configure(
         &mut periphs.ccm.handle,
         bsp::hal::ccm::perclk::PODF::DIVIDE_3,
-        bsp::hal::ccm::perclk::CLKSEL::IPG(ipg_hz),
+        bsp::hal::ccm::perclk::CLKSEL::IPG(clocks.ipg),
     );
 
     let mut gpt2 = periphs.gpt2.clock(&mut cfg);
@@ -0,0 +1,597 @@
+//! Analog to digital converters (ADCs)
+//!
+//! The Teensy's analog inputs, A0 through A13, are pins 14 through 27. The Teensy 4.1
+//! adds A14 through A17 on pins 38 through 41. Each analog input is connected to ADC1,
+//! ADC2, or both. [`AnalogPins`](struct.AnalogPins.html) collects the board's analog
+//! inputs. Construct it with the [`analog_pins!`](../macro.analog_pins.html) macro:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use embedded_hal::adc::OneShot;
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let mut adc1 = p.adc1.clock(&mut p.ccm.handle);
+//! let mut analog = bsp::analog_pins!(p.pins);
+//!
+//! let reading: u16 = nb::block!(adc1.read(&mut analog.a0)).unwrap();
+//! ```
+//!
+//! The ADC types implement `embedded_hal::adc::OneShot`. An [`AnalogInput`](struct.AnalogInput.html)
+//! only implements `embedded_hal::adc::Channel` for the ADCs that it's connected to. For
+//! instance, A12 and A13 are only connected to ADC2; it's a compile-time error to read
+//! them with ADC1.
+//!
+//! ## Continuous sampling
+//!
+//! An ADC may continuously sample one input, and signal a DMA channel after each
+//! conversion. Convert an ADC into a [`Continuous`](struct.Continuous.html) sampler,
+//! then use it as a DMA source with `hal::dma::Peripheral::new_receive`.
+//!
+//! ## Pad-level access
+//!
+//! [`channels`](fn.channels.html) and [`channel`](fn.channel.html) find a pad's ADC
+//! input channels when you only know the pad.
+
+use crate::gpio::{self, Group, Pad, TeensyPin};
+use crate::hal;
+use core::{convert::Infallible, marker::PhantomData, ptr};
+
+/// An ADC instance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instance {
+    /// ADC1
+    Adc1,
+    /// ADC2
+    Adc2,
+}
+
+impl Instance {
+    fn register(self, offset: u32) -> *mut u32 {
+        let base = match self {
+            Instance::Adc1 => 0x400C_4000,
+            Instance::Adc2 => 0x400C_8000,
+        };
+        (base + offset) as *mut u32
+    }
+}
+
+/// Returns the pad's ADC1 and ADC2 input channels
+///
+/// Returns `None` if the pad isn't an analog input.
+pub const fn channels(pad: Pad) -> Option<(Option<u8>, Option<u8>)> {
+    const AD_B1: [u8; 16] = [5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4];
+    let offset = pad.offset;
+    match pad.group {
+        Group::AdB0 if offset >= 12 => Some((Some(offset - 11), None)),
+        Group::AdB1 if offset < 12 => {
+            let channel = AD_B1[offset as usize];
+            Some((Some(channel), Some(channel)))
+        }
+        Group::AdB1 => Some((None, Some(AD_B1[offset as usize]))),
+        _ => None,
+    }
+}
+
+/// Returns the first ADC instance and channel for the pad
+///
+/// Prefers ADC1 over ADC2.
+pub const fn channel(pad: Pad) -> Option<(Instance, u8)> {
+    match channels(pad) {
+        Some((Some(channel), _)) => Some((Instance::Adc1, channel)),
+        Some((None, Some(channel))) => Some((Instance::Adc2, channel)),
+        _ => None,
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::ADC1 {}
+    impl Sealed for super::ADC2 {}
+}
+
+/// Type-level ADC instance
+///
+/// Implemented by [`ADC1`](struct.ADC1.html) and [`ADC2`](struct.ADC2.html).
+pub trait AdcInstance: private::Sealed {
+    /// The runtime instance
+    const INSTANCE: Instance;
+    /// The DMA MUX source that's signaled after each conversion
+    const DMA_REQUEST: u32;
+}
+
+/// ADC1 type tag
+pub struct ADC1;
+/// ADC2 type tag
+pub struct ADC2;
+
+impl AdcInstance for ADC1 {
+    const INSTANCE: Instance = Instance::Adc1;
+    const DMA_REQUEST: u32 = 24;
+}
+
+impl AdcInstance for ADC2 {
+    const INSTANCE: Instance = Instance::Adc2;
+    const DMA_REQUEST: u32 = 88;
+}
+
+/// A pad that's connected to ADC `A`
+///
+/// `CHANNEL` is the pad's input channel on that ADC.
+pub trait AnalogPin<A> {
+    /// ADC input channel
+    const CHANNEL: u8;
+}
+
+macro_rules! analog_pin_impls {
+    ($($pad:ident => $($adc:ident $channel:literal),+;)+) => {
+        $($(
+            impl<M> AnalogPin<$adc> for hal::iomuxc::gpio::$pad<M> {
+                const CHANNEL: u8 = $channel;
+            }
+        )+)+
+    };
+}
+
+analog_pin_impls! {
+    GPIO_AD_B0_12 => ADC1 1;
+    GPIO_AD_B0_13 => ADC1 2;
+    GPIO_AD_B1_00 => ADC1 5, ADC2 5;
+    GPIO_AD_B1_01 => ADC1 6, ADC2 6;
+    GPIO_AD_B1_02 => ADC1 7, ADC2 7;
+    GPIO_AD_B1_03 => ADC1 8, ADC2 8;
+    GPIO_AD_B1_04 => ADC1 9, ADC2 9;
+    GPIO_AD_B1_05 => ADC1 10, ADC2 10;
+    GPIO_AD_B1_06 => ADC1 11, ADC2 11;
+    GPIO_AD_B1_07 => ADC1 12, ADC2 12;
+    GPIO_AD_B1_08 => ADC1 13, ADC2 13;
+    GPIO_AD_B1_09 => ADC1 14, ADC2 14;
+    GPIO_AD_B1_10 => ADC1 15, ADC2 15;
+    GPIO_AD_B1_11 => ADC1 0, ADC2 0;
+    GPIO_AD_B1_12 => ADC2 1;
+    GPIO_AD_B1_13 => ADC2 2;
+    GPIO_AD_B1_14 => ADC2 3;
+    GPIO_AD_B1_15 => ADC2 4;
+}
+
+/// A Teensy pin that's configured as an analog input
+///
+/// Use an `AnalogInput` with any ADC that's connected to the pin.
+pub struct AnalogInput<P> {
+    pin: P,
+}
+
+impl<P: TeensyPin> AnalogInput<P> {
+    /// Configure the pin as an analog input
+    ///
+    /// Disables the pin's pull and keeper.
+    pub fn new(pin: P) -> Self {
+        let pad = gpio::pad(P::PIN).unwrap();
+        unsafe { ptr::write_volatile(pad.pad_ctl() as *mut u32, 0) };
+        AnalogInput { pin }
+    }
+
+    /// Release the pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<A, P: AnalogPin<A>> embedded_hal::adc::Channel<A> for AnalogInput<P> {
+    type ID = u8;
+    fn channel() -> u8 {
+        P::CHANNEL
+    }
+}
+
+/// The board's analog inputs
+///
+/// Construct `AnalogPins` with the [`analog_pins!`](../macro.analog_pins.html) macro.
+pub struct AnalogPins {
+    /// A0, pin 14
+    pub a0: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_02<hal::iomuxc::Alt5>>,
+    /// A1, pin 15
+    pub a1: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_03<hal::iomuxc::Alt5>>,
+    /// A2, pin 16
+    pub a2: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_07<hal::iomuxc::Alt5>>,
+    /// A3, pin 17
+    pub a3: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_06<hal::iomuxc::Alt5>>,
+    /// A4, pin 18
+    pub a4: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_01<hal::iomuxc::Alt5>>,
+    /// A5, pin 19
+    pub a5: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_00<hal::iomuxc::Alt5>>,
+    /// A6, pin 20
+    pub a6: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_10<hal::iomuxc::Alt5>>,
+    /// A7, pin 21
+    pub a7: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_11<hal::iomuxc::Alt5>>,
+    /// A8, pin 22
+    pub a8: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_08<hal::iomuxc::Alt5>>,
+    /// A9, pin 23
+    pub a9: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_09<hal::iomuxc::Alt5>>,
+    /// A10, pin 24. ADC1 only
+    pub a10: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B0_12<hal::iomuxc::Alt5>>,
+    /// A11, pin 25. ADC1 only
+    pub a11: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B0_13<hal::iomuxc::Alt5>>,
+    /// A12, pin 26. ADC2 only
+    pub a12: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_14<hal::iomuxc::Alt5>>,
+    /// A13, pin 27. ADC2 only
+    pub a13: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_15<hal::iomuxc::Alt5>>,
+    /// A14, pin 38. ADC2 only
+    #[cfg(feature = "teensy41")]
+    pub a14: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_12<hal::iomuxc::Alt5>>,
+    /// A15, pin 39. ADC2 only
+    #[cfg(feature = "teensy41")]
+    pub a15: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_13<hal::iomuxc::Alt5>>,
+    /// A16, pin 40
+    #[cfg(feature = "teensy41")]
+    pub a16: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_04<hal::iomuxc::Alt5>>,
+    /// A17, pin 41
+    #[cfg(feature = "teensy41")]
+    pub a17: AnalogInput<hal::iomuxc::gpio::GPIO_AD_B1_05<hal::iomuxc::Alt5>>,
+}
+
+/// Constructs [`AnalogPins`](adc/struct.AnalogPins.html) by moving the analog
+/// pins out of [`Pins`](struct.Pins.html)
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// let p = bsp::Peripherals::take().unwrap();
+/// let analog = bsp::analog_pins!(p.pins);
+/// // p.pins.p13 is still available...
+/// ```
+#[cfg(not(feature = "teensy41"))]
+#[macro_export]
+macro_rules! analog_pins {
+    ($pins:expr) => {
+        $crate::adc::AnalogPins {
+            a0: $crate::adc::AnalogInput::new($pins.p14),
+            a1: $crate::adc::AnalogInput::new($pins.p15),
+            a2: $crate::adc::AnalogInput::new($pins.p16),
+            a3: $crate::adc::AnalogInput::new($pins.p17),
+            a4: $crate::adc::AnalogInput::new($pins.p18),
+            a5: $crate::adc::AnalogInput::new($pins.p19),
+            a6: $crate::adc::AnalogInput::new($pins.p20),
+            a7: $crate::adc::AnalogInput::new($pins.p21),
+            a8: $crate::adc::AnalogInput::new($pins.p22),
+            a9: $crate::adc::AnalogInput::new($pins.p23),
+            a10: $crate::adc::AnalogInput::new($pins.p24),
+            a11: $crate::adc::AnalogInput::new($pins.p25),
+            a12: $crate::adc::AnalogInput::new($pins.p26),
+            a13: $crate::adc::AnalogInput::new($pins.p27),
+        }
+    };
+}
+
+/// Constructs [`AnalogPins`](adc/struct.AnalogPins.html) by moving the analog
+/// pins out of [`Pins`](struct.Pins.html)
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// let p = bsp::Peripherals::take().unwrap();
+/// let analog = bsp::analog_pins!(p.pins);
+/// // p.pins.p13 is still available...
+/// ```
+#[cfg(feature = "teensy41")]
+#[macro_export]
+macro_rules! analog_pins {
+    ($pins:expr) => {
+        $crate::adc::AnalogPins {
+            a0: $crate::adc::AnalogInput::new($pins.p14),
+            a1: $crate::adc::AnalogInput::new($pins.p15),
+            a2: $crate::adc::AnalogInput::new($pins.p16),
+            a3: $crate::adc::AnalogInput::new($pins.p17),
+            a4: $crate::adc::AnalogInput::new($pins.p18),
+            a5: $crate::adc::AnalogInput::new($pins.p19),
+            a6: $crate::adc::AnalogInput::new($pins.p20),
+            a7: $crate::adc::AnalogInput::new($pins.p21),
+            a8: $crate::adc::AnalogInput::new($pins.p22),
+            a9: $crate::adc::AnalogInput::new($pins.p23),
+            a10: $crate::adc::AnalogInput::new($pins.p24),
+            a11: $crate::adc::AnalogInput::new($pins.p25),
+            a12: $crate::adc::AnalogInput::new($pins.p26),
+            a13: $crate::adc::AnalogInput::new($pins.p27),
+            a14: $crate::adc::AnalogInput::new($pins.p38),
+            a15: $crate::adc::AnalogInput::new($pins.p39),
+            a16: $crate::adc::AnalogInput::new($pins.p40),
+            a17: $crate::adc::AnalogInput::new($pins.p41),
+        }
+    };
+}
+
+const HC0: u32 = 0x00;
+const HS: u32 = 0x20;
+const R0: u32 = 0x24;
+const CFG: u32 = 0x44;
+const GC: u32 = 0x48;
+const GS: u32 = 0x4C;
+
+const HS_COCO0: u32 = 1 << 0;
+const GC_DMAEN: u32 = 1 << 1;
+const GC_AVGE: u32 = 1 << 5;
+const GC_ADCO: u32 = 1 << 6;
+const GC_CAL: u32 = 1 << 7;
+const GS_CALF: u32 = 1 << 1;
+
+const CFG_ADICLK_IPG: u32 = 0;
+const CFG_MODE_SHIFT: u32 = 2;
+const CFG_ADLSMP: u32 = 1 << 4;
+const CFG_ADIV_4: u32 = 2 << 5;
+const CFG_ADSTS_SHIFT: u32 = 8;
+const CFG_AVGS_SHIFT: u32 = 14;
+
+/// Writing this channel to `HC0` aborts a conversion
+const CHANNEL_DISABLED: u32 = 0x1F;
+
+/// `CCM_CCGR1`, which gates the ADC clocks
+const CCM_CCGR1: *mut u32 = 0x400F_C06C as *mut u32;
+
+/// ADC conversion resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// 8 bit conversions
+    Bits8,
+    /// 10 bit conversions
+    Bits10,
+    /// 12 bit conversions
+    Bits12,
+}
+
+impl Resolution {
+    const fn mode(self) -> u32 {
+        match self {
+            Resolution::Bits8 => 0,
+            Resolution::Bits10 => 1,
+            Resolution::Bits12 => 2,
+        }
+    }
+}
+
+/// The number of hardware-averaged samples in each conversion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Averaging {
+    /// One sample per conversion
+    None,
+    /// Average four samples
+    Avg4,
+    /// Average eight samples
+    Avg8,
+    /// Average 16 samples
+    Avg16,
+    /// Average 32 samples
+    Avg32,
+}
+
+impl Averaging {
+    /// Returns the `CFG[AVGS]` field, and whether averaging is enabled
+    const fn avgs(self) -> (u32, bool) {
+        match self {
+            Averaging::None => (0, false),
+            Averaging::Avg4 => (0, true),
+            Averaging::Avg8 => (1, true),
+            Averaging::Avg16 => (2, true),
+            Averaging::Avg32 => (3, true),
+        }
+    }
+}
+
+/// Indicates that the ADC failed to calibrate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalibrationError;
+
+/// An ADC that's not yet clocked
+pub struct Unclocked<A> {
+    _adc: PhantomData<A>,
+}
+
+impl<A: AdcInstance> Unclocked<A> {
+    pub(crate) const fn new() -> Self {
+        Unclocked { _adc: PhantomData }
+    }
+
+    /// Enable the ADC's clock, and calibrate the ADC
+    ///
+    /// The ADC starts with 10 bit conversions, averaging four samples.
+    /// Use [`ADC::calibrate`](struct.ADC.html#method.calibrate) to check the
+    /// calibration.
+    pub fn clock(self, _: &mut hal::ccm::Handle) -> ADC<A> {
+        let cg = match A::INSTANCE {
+            Instance::Adc1 => 0b11 << 16,
+            Instance::Adc2 => 0b11 << 8,
+        };
+        unsafe { ptr::write_volatile(CCM_CCGR1, ptr::read_volatile(CCM_CCGR1) | cg) };
+        let mut adc = ADC {
+            _adc: PhantomData,
+            resolution: Resolution::Bits10,
+            averaging: Averaging::Avg4,
+            pending: None,
+        };
+        // Calibration failures are rare. Users may check with `calibrate()`.
+        let _ = adc.configure();
+        adc
+    }
+}
+
+/// A clocked ADC
+///
+/// Use `embedded_hal::adc::OneShot` to read an [`AnalogInput`](struct.AnalogInput.html).
+pub struct ADC<A> {
+    _adc: PhantomData<A>,
+    resolution: Resolution,
+    averaging: Averaging,
+    /// The channel that's being converted
+    pending: Option<u8>,
+}
+
+impl<A: AdcInstance> ADC<A> {
+    fn register(&self, offset: u32) -> *mut u32 {
+        A::INSTANCE.register(offset)
+    }
+
+    /// Write the resolution and averaging settings, and calibrate
+    fn configure(&mut self) -> Result<(), CalibrationError> {
+        let (avgs, avge) = self.averaging.avgs();
+        self.pending = None;
+        unsafe {
+            ptr::write_volatile(
+                self.register(CFG),
+                CFG_ADICLK_IPG
+                    | CFG_ADIV_4
+                    | CFG_ADLSMP
+                    | (3 << CFG_ADSTS_SHIFT)
+                    | (self.resolution.mode() << CFG_MODE_SHIFT)
+                    | (avgs << CFG_AVGS_SHIFT),
+            );
+            ptr::write_volatile(self.register(GC), if avge { GC_AVGE } else { 0 });
+        }
+        self.calibrate()
+    }
+
+    /// Calibrate the ADC
+    ///
+    /// Calibration depends on the resolution and averaging settings, so
+    /// changing those settings also re-calibrates the ADC.
+    pub fn calibrate(&mut self) -> Result<(), CalibrationError> {
+        self.pending = None;
+        unsafe {
+            let gc = self.register(GC);
+            ptr::write_volatile(gc, ptr::read_volatile(gc) | GC_CAL);
+            while ptr::read_volatile(gc) & GC_CAL != 0 {}
+            if ptr::read_volatile(self.register(GS)) & GS_CALF != 0 {
+                ptr::write_volatile(self.register(GS), GS_CALF);
+                return Err(CalibrationError);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the conversion resolution, then re-calibrate
+    pub fn set_resolution(&mut self, resolution: Resolution) -> Result<(), CalibrationError> {
+        self.resolution = resolution;
+        self.configure()
+    }
+
+    /// Returns the conversion resolution
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Set the hardware averaging, then re-calibrate
+    pub fn set_averaging(&mut self, averaging: Averaging) -> Result<(), CalibrationError> {
+        self.averaging = averaging;
+        self.configure()
+    }
+
+    /// Returns the hardware averaging
+    pub fn averaging(&self) -> Averaging {
+        self.averaging
+    }
+
+    /// Perform a blocking conversion on the input channel, `channel`
+    pub(crate) fn read_channel(&mut self, channel: u8) -> u16 {
+        self.pending = None;
+        unsafe {
+            ptr::write_volatile(self.register(HC0), channel as u32 & CHANNEL_DISABLED);
+            while ptr::read_volatile(self.register(HS)) & HS_COCO0 == 0 {}
+            ptr::read_volatile(self.register(R0)) as u16
+        }
+    }
+
+    /// Perform a blocking conversion on `input`
+    pub fn read_blocking<P: AnalogPin<A>>(&mut self, _input: &mut AnalogInput<P>) -> u16 {
+        self.read_channel(P::CHANNEL)
+    }
+
+    /// Continuously sample `input`, signaling DMA after each conversion
+    ///
+    /// See [`Continuous`](struct.Continuous.html) for more information.
+    pub fn into_continuous<P: AnalogPin<A>>(self, _input: &AnalogInput<P>) -> Continuous<A> {
+        Continuous {
+            adc: self,
+            channel: P::CHANNEL,
+        }
+    }
+}
+
+impl<A: AdcInstance, P: AnalogPin<A>> embedded_hal::adc::OneShot<A, u16, AnalogInput<P>>
+    for ADC<A>
+{
+    type Error = Infallible;
+
+    fn read(&mut self, _: &mut AnalogInput<P>) -> nb::Result<u16, Self::Error> {
+        if self.pending != Some(P::CHANNEL) {
+            unsafe { ptr::write_volatile(self.register(HC0), P::CHANNEL as u32) };
+            self.pending = Some(P::CHANNEL);
+        }
+        if unsafe { ptr::read_volatile(self.register(HS)) } & HS_COCO0 == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.pending = None;
+        Ok(unsafe { ptr::read_volatile(self.register(R0)) } as u16)
+    }
+}
+
+/// An ADC that continuously samples one input
+///
+/// `Continuous` is a DMA source. Each conversion signals the DMA channel, and
+/// the DMA channel reads the conversion. Use `Continuous` with `hal::dma::Peripheral`:
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::hal::dma;
+///
+/// static SAMPLES: dma::Buffer<[u16; 256]> = dma::Buffer::new([0; 256]);
+///
+/// let mut p = bsp::Peripherals::take().unwrap();
+/// let adc1 = p.adc1.clock(&mut p.ccm.handle);
+/// let analog = bsp::analog_pins!(p.pins);
+/// let mut channels = p.dma.clock(&mut p.ccm.handle);
+///
+/// let mut sampler = dma::Peripheral::new_receive(
+///     adc1.into_continuous(&analog.a0),
+///     channels[3].take().unwrap(),
+///     dma::ConfigBuilder::new().interrupt_on_completion(true).build(),
+/// );
+/// let buffer = dma::Linear::new(&SAMPLES).unwrap();
+/// sampler.start_receive(buffer).unwrap();
+/// ```
+///
+/// Conversions start when the DMA receive starts, and stop when the DMA receive
+/// completes or is cancelled.
+pub struct Continuous<A> {
+    adc: ADC<A>,
+    channel: u8,
+}
+
+impl<A: AdcInstance> Continuous<A> {
+    /// Stop sampling, and return the ADC
+    pub fn release(mut self) -> ADC<A> {
+        hal::dma::peripheral::Source::<u16>::disable_source(&mut self);
+        self.adc
+    }
+}
+
+unsafe impl<A: AdcInstance> hal::dma::peripheral::Source<u16> for Continuous<A> {
+    type Error = Infallible;
+    const SOURCE_REQUEST_SIGNAL: u32 = A::DMA_REQUEST;
+    fn source(&self) -> *const u16 {
+        self.adc.register(R0) as *const u16
+    }
+    fn enable_source(&mut self) -> Result<(), Self::Error> {
+        unsafe {
+            let gc = self.adc.register(GC);
+            ptr::write_volatile(gc, ptr::read_volatile(gc) | GC_ADCO | GC_DMAEN);
+            ptr::write_volatile(self.adc.register(HC0), self.channel as u32);
+        }
+        Ok(())
+    }
+    fn disable_source(&mut self) {
+        unsafe {
+            let gc = self.adc.register(GC);
+            ptr::write_volatile(gc, ptr::read_volatile(gc) & !(GC_ADCO | GC_DMAEN));
+            ptr::write_volatile(self.adc.register(HC0), CHANNEL_DISABLED);
+            // Clear a completed conversion, so it doesn't signal DMA later
+            ptr::read_volatile(self.adc.register(R0));
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Arduino-style, pin-level I/O
+//!
+//! The [`Arduino`](struct.Arduino.html) type provides the Teensyduino functions
+//! that select pins by number: `pin_mode`, `digital_write`, `digital_read`,
+//! `analog_read`, and `analog_write`. Unlike their Teensyduino counterparts,
+//! each function returns an [`Error`](enum.Error.html) when the pin can't do
+//! what's asked.
+//!
+//! If we're compiling this module, it's because the `"arduino"` feature is enabled.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::arduino::{Arduino, PinMode};
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+//! let mut arduino = Arduino::new(
+//!     p.pins,
+//!     (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+//!     (p.adc1, p.adc2),
+//!     &mut p.ccm.handle,
+//!     clocks.ipg_hz,
+//! );
+//!
+//! arduino.pin_mode(13, PinMode::Output).unwrap();
+//! arduino.digital_write(13, true).unwrap();
+//! arduino.analog_write(2, 128).unwrap();
+//! let value = arduino.analog_read(14).unwrap();
+//! ```
+//!
+//! `analog_write` supports the pins that are connected to a FlexPWM output. It
+//! does not support pins that are only connected to a QuadTimer. `analog_read`
+//! supports the pins that are connected to an ADC.
+
+use crate::{
+    adc, flexpwm,
+    gpio::{self, DynPin, ErasedPins, Pull, NUM_PINS},
+    hal,
+};
+
+/// Errors from pin-level I/O
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The pin number isn't available on the selected board
+    InvalidPin(u8),
+    /// The pin isn't configured as a digital input or output. Call
+    /// `pin_mode` before using the pin.
+    NotDigital(u8),
+    /// The pin isn't connected to a FlexPWM output
+    NoPwm(u8),
+    /// The pin isn't connected to an ADC
+    NoAnalogInput(u8),
+    /// The ADC failed to calibrate
+    AdcCalibration(adc::CalibrationError),
+    /// The resolution isn't supported
+    InvalidResolution(u8),
+}
+
+/// The direction and pull of a digital pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinMode {
+    /// Input, with no pull
+    Input,
+    /// Input, with a 22KOhm pull up
+    InputPullup,
+    /// Input, with a 100KOhm pull down
+    InputPulldown,
+    /// Output
+    Output,
+}
+
+/// The present use of a Teensy pin
+enum Slot {
+    /// Unused, or used as an analog input
+    Free,
+    /// A digital input or output
+    Digital(DynPin),
+    /// A PWM output
+    Pwm,
+}
+
+const FREE: Slot = Slot::Free;
+
+/// The `analog_write` resolution, matching Teensyduino
+const DEFAULT_WRITE_RESOLUTION: u8 = 8;
+
+/// Pin-level I/O by Teensy pin number
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct Arduino {
+    pins: ErasedPins,
+    slots: [Slot; NUM_PINS],
+    ipg_hz: u32,
+    write_resolution: u8,
+    adc1: adc::ADC<adc::ADC1>,
+    adc2: adc::ADC<adc::ADC2>,
+}
+
+impl Arduino {
+    /// Create pin-level I/O from the board's pins, PWM modules, and ADCs
+    ///
+    /// `ccm` is used to enable the PWM and ADC clocks. `ipg_hz` is the IPG clock
+    /// frequency, in Hz; see [`Clocks`](../clocks/struct.Clocks.html). If you later change
+    /// the IPG clock frequency, PWM frequencies will be incorrect.
+    pub fn new(
+        pins: crate::Pins,
+        pwm: (
+            hal::pwm::Unclocked<hal::pwm::module::_1>,
+            hal::pwm::Unclocked<hal::pwm::module::_2>,
+            hal::pwm::Unclocked<hal::pwm::module::_3>,
+            hal::pwm::Unclocked<hal::pwm::module::_4>,
+        ),
+        adc: (adc::Unclocked<adc::ADC1>, adc::Unclocked<adc::ADC2>),
+        ccm: &mut hal::ccm::Handle,
+        ipg_hz: u32,
+    ) -> Self {
+        let (pwm1, pwm2, pwm3, pwm4) = pwm;
+        pwm1.clock(ccm);
+        pwm2.clock(ccm);
+        pwm3.clock(ccm);
+        pwm4.clock(ccm);
+        for module in 1..=4 {
+            unsafe { flexpwm::init(module, ipg_hz) };
+        }
+        let (adc1, adc2) = adc;
+        Arduino {
+            pins: pins.erase(),
+            slots: [FREE; NUM_PINS],
+            ipg_hz,
+            write_resolution: DEFAULT_WRITE_RESOLUTION,
+            adc1: adc1.clock(ccm),
+            adc2: adc2.clock(ccm),
+        }
+    }
+
+    fn slot(&mut self, pin: u8) -> Result<&mut Slot, Error> {
+        self.slots
+            .get_mut(pin as usize)
+            .ok_or(Error::InvalidPin(pin))
+    }
+
+    /// Configure a pin as a digital input or output
+    ///
+    /// If the pin was a PWM output, the PWM output is disabled.
+    pub fn pin_mode(&mut self, pin: u8, mode: PinMode) -> Result<(), Error> {
+        let slot = self.slot(pin)?;
+        if let Slot::Pwm = slot {
+            unsafe { flexpwm::set_enable(flexpwm::output(pin).unwrap(), false) };
+        }
+        let mut dyn_pin = match core::mem::replace(slot, Slot::Free) {
+            Slot::Digital(dyn_pin) => dyn_pin,
+            // Free and PWM pins are never taken from `self.pins`
+            Slot::Free | Slot::Pwm => self.pins.take(pin).unwrap(),
+        };
+        match mode {
+            PinMode::Input => {
+                dyn_pin.set_pull(Pull::Keeper);
+                dyn_pin.set_input();
+            }
+            PinMode::InputPullup => {
+                dyn_pin.set_pull(Pull::Up22k);
+                dyn_pin.set_input();
+            }
+            PinMode::InputPulldown => {
+                dyn_pin.set_pull(Pull::Down100k);
+                dyn_pin.set_input();
+            }
+            PinMode::Output => {
+                dyn_pin.set_pull(Pull::None);
+                dyn_pin.set_output();
+            }
+        }
+        self.slots[pin as usize] = Slot::Digital(dyn_pin);
+        Ok(())
+    }
+
+    fn digital(&mut self, pin: u8) -> Result<&mut DynPin, Error> {
+        match self.slot(pin)? {
+            Slot::Digital(dyn_pin) => Ok(dyn_pin),
+            _ => Err(Error::NotDigital(pin)),
+        }
+    }
+
+    /// Drive a digital output high (`true`) or low (`false`)
+    ///
+    /// The pin must be configured with `pin_mode`.
+    pub fn digital_write(&mut self, pin: u8, high: bool) -> Result<(), Error> {
+        let dyn_pin = self.digital(pin)?;
+        if high {
+            dyn_pin.set();
+        } else {
+            dyn_pin.clear();
+        }
+        Ok(())
+    }
+
+    /// Toggle a digital output
+    ///
+    /// The pin must be configured with `pin_mode`.
+    pub fn digital_toggle(&mut self, pin: u8) -> Result<(), Error> {
+        self.digital(pin)?.toggle();
+        Ok(())
+    }
+
+    /// Read a digital input. Returns `true` if the pin is high
+    ///
+    /// The pin must be configured with `pin_mode`.
+    pub fn digital_read(&mut self, pin: u8) -> Result<bool, Error> {
+        Ok(self.digital(pin)?.is_high())
+    }
+
+    /// Set the resolution of `analog_write` values, from 1 to 16 bits
+    ///
+    /// The default resolution is 8 bits, so `analog_write` accepts values from 0 to 255.
+    pub fn analog_write_resolution(&mut self, bits: u8) -> Result<(), Error> {
+        if (1..=16).contains(&bits) {
+            self.write_resolution = bits;
+            Ok(())
+        } else {
+            Err(Error::InvalidResolution(bits))
+        }
+    }
+
+    /// Set the PWM switching frequency of a pin
+    ///
+    /// Pins that share a FlexPWM submodule also share a switching frequency.
+    /// Changing one pin's frequency changes the other pins' frequencies.
+    pub fn analog_write_frequency(&mut self, pin: u8, frequency_hz: u32) -> Result<(), Error> {
+        self.slot(pin)?;
+        let output = flexpwm::output(pin).ok_or(Error::NoPwm(pin))?;
+        unsafe { flexpwm::set_frequency(output, self.ipg_hz, frequency_hz) };
+        Ok(())
+    }
+
+    /// Drive a PWM output with a duty cycle of `value / 2^resolution`
+    ///
+    /// `value` saturates at the maximum value for the `analog_write` resolution.
+    /// If the pin was a digital input or output, it becomes a PWM output.
+    pub fn analog_write(&mut self, pin: u8, value: u32) -> Result<(), Error> {
+        self.slot(pin)?;
+        let output = flexpwm::output(pin).ok_or(Error::NoPwm(pin))?;
+        let compare = {
+            let full_scale = 1u64 << self.write_resolution;
+            let modulo = flexpwm::modulo(output) as u64 + 1;
+            let value = (value as u64).min(full_scale);
+            (value * modulo / full_scale).min(u16::max_value() as u64) as u16
+        };
+        unsafe { flexpwm::set_compare(output, compare) };
+        let slot = &mut self.slots[pin as usize];
+        if let Slot::Pwm = slot {
+            return Ok(());
+        }
+        if let Slot::Digital(dyn_pin) = core::mem::replace(slot, Slot::Pwm) {
+            self.pins.release(dyn_pin);
+        }
+        unsafe {
+            flexpwm::mux(gpio::pad(pin).unwrap(), output);
+            flexpwm::set_enable(output, true);
+        }
+        Ok(())
+    }
+
+    /// Set the resolution of `analog_read` conversions: 8, 10, or 12 bits
+    ///
+    /// The default resolution is 10 bits.
+    pub fn analog_read_resolution(&mut self, bits: u8) -> Result<(), Error> {
+        let resolution = match bits {
+            8 => adc::Resolution::Bits8,
+            10 => adc::Resolution::Bits10,
+            12 => adc::Resolution::Bits12,
+            _ => return Err(Error::InvalidResolution(bits)),
+        };
+        self.adc1
+            .set_resolution(resolution)
+            .map_err(Error::AdcCalibration)?;
+        self.adc2
+            .set_resolution(resolution)
+            .map_err(Error::AdcCalibration)
+    }
+
+    /// Perform a blocking analog conversion on a pin
+    ///
+    /// If the pin was a digital output or a PWM output, it becomes an analog input.
+    pub fn analog_read(&mut self, pin: u8) -> Result<u16, Error> {
+        self.slot(pin)?;
+        let pad = gpio::pad(pin).unwrap();
+        let (instance, channel) = adc::channel(pad).ok_or(Error::NoAnalogInput(pin))?;
+        match self.slots[pin as usize] {
+            Slot::Digital(ref dyn_pin) if !dyn_pin.is_output() => {}
+            _ => {
+                self.pin_mode(pin, PinMode::Input)?;
+                if let Slot::Digital(dyn_pin) = &mut self.slots[pin as usize] {
+                    dyn_pin.set_pull(Pull::None);
+                }
+            }
+        }
+        Ok(match instance {
+            adc::Instance::Adc1 => self.adc1.read_channel(channel),
+            adc::Instance::Adc2 => self.adc2.read_channel(channel),
+        })
+    }
+}
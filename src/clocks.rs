@@ -0,0 +1,262 @@
+//! Core clock profiles
+//!
+//! A [`Profile`](enum.Profile.html) names an ARM core clock frequency. Configure a
+//! profile once, early in `main()`, and use the returned [`Clocks`](struct.Clocks.html)
+//! to set up peripherals and delays:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::clocks::Profile;
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let clocks = Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+//!
+//! let mut cfg = p.ccm.perclk.configure(
+//!     &mut p.ccm.handle,
+//!     bsp::hal::ccm::perclk::PODF::DIVIDE_3,
+//!     bsp::hal::ccm::perclk::CLKSEL::IPG(clocks.ipg),
+//! );
+//! let mut gpt1 = p.gpt1.clock(&mut cfg);
+//! ```
+//!
+//! `Clocks` reports the resulting frequencies. Use `arm_hz` for
+//! cycle-counting delays, and `ipg_hz` for peripherals that are clocked from IPG.
+//!
+//! ## Overclocking
+//!
+//! The `Overclock` profiles run the core faster than its 600MHz rating. They raise the
+//! DCDC converter's output voltage before raising the clock, using the same voltage steps
+//! as Teensyduino. The processor runs hotter; consider a heat sink. Use them at your own risk.
+//!
+//! The HAL sets its own DCDC voltage when it changes the clock, and its voltage is too low
+//! for an overclock. So the BSP switches to an overclock without the HAL. It sets the final
+//! voltage once, then runs the core from the 24MHz oscillator while it re-programs the ARM
+//! PLL. Other profiles start from the oscillator too, then let the HAL select the clock and
+//! the voltage. The core never runs above 600MHz at less than the overclock voltage.
+
+use crate::hal;
+use core::ptr;
+
+/// An ARM core clock profile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// 24MHz, for low power
+    LowPower,
+    /// 150MHz
+    Mhz150,
+    /// 396MHz
+    Mhz396,
+    /// 600MHz, the processor's rated speed. This is the default profile.
+    Mhz600,
+    /// 816MHz overclock, at 1.425V
+    Overclock816,
+    /// 912MHz overclock, at 1.525V
+    Overclock912,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::Mhz600
+    }
+}
+
+/// The frequencies that result from a [`Profile`](enum.Profile.html)
+pub struct Clocks {
+    /// The profile that set these clocks
+    pub profile: Profile,
+    /// ARM core clock frequency, in Hz
+    pub arm_hz: u32,
+    /// IPG clock frequency, in Hz
+    pub ipg_hz: u32,
+    /// IPG clock frequency, for configuring HAL clocks like `perclk`
+    pub ipg: hal::ccm::IPGFrequency,
+}
+
+const CCM_CACRR: *mut u32 = 0x400F_C010 as *mut u32;
+const CCM_CBCDR: *mut u32 = 0x400F_C014 as *mut u32;
+const CCM_CBCMR: *mut u32 = 0x400F_C018 as *mut u32;
+const CCM_CDHIPR: *const u32 = 0x400F_C048 as *const u32;
+const CCM_ANALOG_PLL_ARM: *mut u32 = 0x400D_8000 as *mut u32;
+
+const CACRR_ARM_PODF_MASK: u32 = 0b111;
+const CBCDR_IPG_PODF_SHIFT: u32 = 8;
+const CBCDR_IPG_PODF_MASK: u32 = 0b11 << CBCDR_IPG_PODF_SHIFT;
+const CBCDR_AHB_PODF_MASK: u32 = 0b111 << 10;
+const CBCDR_PERIPH_CLK_SEL: u32 = 1 << 25;
+const CBCDR_PERIPH_CLK2_PODF_MASK: u32 = 0b111 << 27;
+const CBCMR_PERIPH_CLK2_SEL_MASK: u32 = 0b11 << 12;
+/// `PERIPH_CLK2_SEL` for the 24MHz oscillator
+const CBCMR_PERIPH_CLK2_SEL_OSC: u32 = 0b01 << 12;
+const CBCMR_PRE_PERIPH_CLK_SEL_MASK: u32 = 0b11 << 18;
+/// `PRE_PERIPH_CLK_SEL` for the divided ARM PLL
+const CBCMR_PRE_PERIPH_CLK_SEL_PLL1: u32 = 0b11 << 18;
+const CDHIPR_AHB_PODF_BUSY: u32 = 1 << 1;
+const CDHIPR_PERIPH_CLK_SEL_BUSY: u32 = 1 << 5;
+const CDHIPR_ARM_PODF_BUSY: u32 = 1 << 16;
+const PLL_ARM_DIV_SELECT_MASK: u32 = 0x7F;
+const PLL_ARM_ENABLE: u32 = 1 << 13;
+const PLL_ARM_LOCK: u32 = 1 << 31;
+
+/// The ARM PLL's reference clock, the 24MHz oscillator
+const OSC_HZ: u32 = 24_000_000;
+/// The IPG clock's highest divider
+const MAX_IPG_DIVIDER: u32 = 4;
+/// The IPG clock's rated frequency
+const IPG_RATED_HZ: u32 = 150_000_000;
+
+const DCDC_REG0: *const u32 = 0x4008_0000 as *const u32;
+const DCDC_REG3: *mut u32 = 0x4008_000C as *mut u32;
+const DCDC_REG0_STS_DC_OK: u32 = 1 << 31;
+const DCDC_REG3_TRG_MASK: u32 = 0x1F;
+
+/// Overclocks need this many more millivolts for each step above 600MHz
+const OVERCLOCK_MV_PER_STEP: u32 = 25;
+/// The overclock step size, in Hz
+const OVERCLOCK_STEP_HZ: u32 = 28_000_000;
+
+impl Profile {
+    /// Returns the ARM core clock frequency, in Hz
+    pub const fn arm_hz(self) -> u32 {
+        match self {
+            Profile::LowPower => 24_000_000,
+            Profile::Mhz150 => 150_000_000,
+            Profile::Mhz396 => 396_000_000,
+            Profile::Mhz600 => hal::ccm::PLL1::ARM_HZ,
+            Profile::Overclock816 => 816_000_000,
+            Profile::Overclock912 => 912_000_000,
+        }
+    }
+
+    /// Returns the DCDC output voltage that the profile requires, in
+    /// millivolts, or `None` if the HAL selects the voltage
+    const fn overclock_millivolts(self) -> Option<u32> {
+        match self {
+            Profile::Overclock816 | Profile::Overclock912 => Some(
+                1250 + (self.arm_hz() - 600_000_000) / OVERCLOCK_STEP_HZ * OVERCLOCK_MV_PER_STEP,
+            ),
+            _ => None,
+        }
+    }
+
+    /// Set the ARM core clock, and return the resulting clock frequencies
+    ///
+    /// Overclock profiles raise the DCDC voltage before changing the clock. See the
+    /// [module-level documentation](index.html) for the sequence.
+    pub fn configure(self, ccm: &mut hal::ccm::CCM, dcdc: &mut hal::dcdc::DCDC) -> Clocks {
+        match self.overclock_millivolts() {
+            Some(millivolts) => {
+                set_dcdc_millivolts(dcdc, millivolts);
+                let (div_select, ipg_divider) = overclock_dividers(self.arm_hz());
+                // Safety: we own the CCM, and the voltage supports the overclock
+                let ipg_hz = unsafe { set_overclock(div_select, ipg_divider) };
+                Clocks {
+                    profile: self,
+                    arm_hz: self.arm_hz(),
+                    ipg_hz,
+                    ipg: hal::ccm::IPGFrequency(hal::ccm::Frequency(ipg_hz)),
+                }
+            }
+            None => {
+                // Safety: we own the CCM. If the core is overclocked, this drops the
+                // clock before the HAL lowers the voltage.
+                unsafe { run_from_oscillator() };
+                let (arm, ipg) = ccm.pll1.set_arm_clock(self.arm_hz(), &mut ccm.handle, dcdc);
+                Clocks {
+                    profile: self,
+                    arm_hz: (arm.0).0,
+                    ipg_hz: (ipg.0).0,
+                    ipg,
+                }
+            }
+        }
+    }
+}
+
+/// Returns the ARM PLL's `DIV_SELECT`, and the IPG divider, for an overclock
+///
+/// The PLL runs at the core clock, and the ARM and AHB dividers are one. Like
+/// Teensyduino, the IPG divider saturates at four, so the IPG clock exceeds
+/// its rating.
+const fn overclock_dividers(arm_hz: u32) -> (u32, u32) {
+    let div_select = arm_hz * 2 / OSC_HZ;
+    let ipg_divider = (arm_hz + IPG_RATED_HZ - 1) / IPG_RATED_HZ;
+    let ipg_divider = if ipg_divider > MAX_IPG_DIVIDER {
+        MAX_IPG_DIVIDER
+    } else {
+        ipg_divider
+    };
+    (div_select, ipg_divider)
+}
+
+/// Read-modify-write `register`, replacing the bits in `mask` with `value`
+unsafe fn modify(register: *mut u32, mask: u32, value: u32) {
+    ptr::write_volatile(register, (ptr::read_volatile(register) & !mask) | value);
+}
+
+/// Wait for the CCM handshake bits in `busy` to clear
+unsafe fn handshake(busy: u32) {
+    while ptr::read_volatile(CCM_CDHIPR) & busy != 0 {}
+}
+
+/// Run the core from the 24MHz oscillator, so that the ARM PLL can change
+///
+/// # Safety
+///
+/// Caller must own the CCM.
+unsafe fn run_from_oscillator() {
+    modify(
+        CCM_CBCMR,
+        CBCMR_PERIPH_CLK2_SEL_MASK,
+        CBCMR_PERIPH_CLK2_SEL_OSC,
+    );
+    modify(
+        CCM_CBCDR,
+        CBCDR_PERIPH_CLK2_PODF_MASK | CBCDR_PERIPH_CLK_SEL,
+        CBCDR_PERIPH_CLK_SEL,
+    );
+    handshake(CDHIPR_PERIPH_CLK_SEL_BUSY);
+}
+
+/// Run the core from the ARM PLL at `div_select`, and return the IPG frequency
+///
+/// # Safety
+///
+/// Caller must own the CCM, and the DCDC voltage must support the clock.
+unsafe fn set_overclock(div_select: u32, ipg_divider: u32) -> u32 {
+    run_from_oscillator();
+    ptr::write_volatile(
+        CCM_ANALOG_PLL_ARM,
+        PLL_ARM_ENABLE | (div_select & PLL_ARM_DIV_SELECT_MASK),
+    );
+    while ptr::read_volatile(CCM_ANALOG_PLL_ARM) & PLL_ARM_LOCK == 0 {}
+    modify(CCM_CACRR, CACRR_ARM_PODF_MASK, 0);
+    handshake(CDHIPR_ARM_PODF_BUSY);
+    modify(
+        CCM_CBCDR,
+        CBCDR_AHB_PODF_MASK | CBCDR_IPG_PODF_MASK,
+        (ipg_divider - 1) << CBCDR_IPG_PODF_SHIFT,
+    );
+    handshake(CDHIPR_AHB_PODF_BUSY);
+    modify(
+        CCM_CBCMR,
+        CBCMR_PRE_PERIPH_CLK_SEL_MASK,
+        CBCMR_PRE_PERIPH_CLK_SEL_PLL1,
+    );
+    modify(CCM_CBCDR, CBCDR_PERIPH_CLK_SEL, 0);
+    handshake(CDHIPR_PERIPH_CLK_SEL_BUSY);
+    OSC_HZ / 2 * div_select / ipg_divider
+}
+
+/// Set the DCDC target voltage, and wait for the output to settle
+///
+/// The DCDC handle proves that we own the converter.
+fn set_dcdc_millivolts(_: &mut hal::dcdc::DCDC, millivolts: u32) {
+    let trg = (millivolts - 800) / 25;
+    unsafe {
+        ptr::write_volatile(
+            DCDC_REG3,
+            (ptr::read_volatile(DCDC_REG3) & !DCDC_REG3_TRG_MASK) | (trg & DCDC_REG3_TRG_MASK),
+        );
+        while ptr::read_volatile(DCDC_REG0) & DCDC_REG0_STS_DC_OK == 0 {}
+    }
+}
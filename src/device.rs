@@ -0,0 +1,19 @@
+//! An RTIC device that hands `init` the HAL's peripherals
+//!
+//! Select this module as the `rtic` device, then create the BSP's peripherals with
+//! [`Peripherals::from_rtic`](../struct.Peripherals.html#method.from_rtic):
+//!
+//! ```ignore
+//! #[rtic::app(device = teensy4_bsp::device, peripherals = true)]
+//! const APP: () = {
+//!     #[init]
+//!     fn init(cx: init::Context) {
+//!         let mut peripherals = teensy4_bsp::Peripherals::from_rtic(cx.device);
+//!     }
+//! };
+//! ```
+//!
+//! If we're compiling this module, it's because the `"rtic"` feature is enabled.
+
+pub use crate::hal::ral::{interrupt as Interrupt, NVIC_PRIO_BITS};
+pub use crate::hal::Peripherals;
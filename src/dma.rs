@@ -0,0 +1,1062 @@
+//! DMA buffers in the `.dmabuffers` memory region
+//!
+//! The runtime's linker script collects `.dmabuffers` into a `NOLOAD` section in OCRAM,
+//! away from the tightly-coupled memory that holds the stack and statics. This module
+//! reserves a fixed pool in that section, and hands out `'static` DMA buffers from the
+//! pool. Pass the buffers to `hal::dma::Linear::new` or `hal::dma::Circular::new`.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::dma;
+//!
+//! let tx = bsp::dma::linear([0u8; 64]).unwrap();
+//! let rx = bsp::dma::circular([0u8; 64]).unwrap();
+//!
+//! let tx_buffer = dma::Linear::new(tx).unwrap();
+//! let rx_buffer = dma::Circular::new(rx).unwrap();
+//! ```
+//!
+//! The pool never frees a buffer. Allocate buffers once, during setup. The allocators
+//! return `None` when the pool can't fit the buffer; see [`remaining`](fn.remaining.html).
+//!
+//! ## Circular buffers
+//!
+//! The DMA controller wraps a circular transfer by masking the buffer address. A circular
+//! buffer's length must be a power of two, and the buffer must be aligned to its size in
+//! bytes. [`circular`](fn.circular.html) takes care of the alignment.
+//!
+//! ## Scatter-gather
+//!
+//! A [`ChainBuilder`](struct.ChainBuilder.html) links a list of segments, so that one DMA
+//! channel moves data between a peripheral and each segment in turn. A circular chain of
+//! two segments double-buffers a stream, and a chain can start another channel after each
+//! segment.
+//!
+//! ## Caching
+//!
+//! The runtime caches OCRAM, except for the `.dma` section at the start of OCRAM. The
+//! pool's buffers are coherent with the DMA controller, and so are buffers in DTCM, like
+//! ordinary `static`s. If a DMA buffer lives anywhere else in OCRAM, or in the Teensy 4.1's
+//! external memory, [`clean`](fn.clean.html) the buffer before the DMA controller reads it,
+//! and [`invalidate`](fn.invalidate.html) the buffer after the DMA controller writes it.
+//! Buffers that you invalidate must start on a 32 byte cache line, and span whole cache
+//! lines, so that they don't share a line with other data:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//!
+//! #[repr(C, align(32))]
+//! struct RxBuffer([u8; 64]);
+//!
+//! # let rx: &'static mut RxBuffer = cortex_m::singleton!(: RxBuffer = RxBuffer([0; 64])).unwrap();
+//! // After the DMA controller writes rx...
+//! bsp::dma::invalidate(&mut rx.0);
+//! ```
+
+use crate::hal::dma::Buffer;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use cortex_m::{asm, interrupt};
+
+/// The size of the DMA buffer pool, in bytes
+pub const POOL_SIZE: usize = 32 * 1024;
+
+/// Aligned to a cache line, so that the first buffer doesn't share a line with other data
+#[repr(C, align(32))]
+struct Pool(MaybeUninit<[u8; POOL_SIZE]>);
+
+/// `.dmabuffers` is `NOLOAD`, so the startup code doesn't initialize the pool
+#[link_section = ".dmabuffers"]
+static mut POOL: Pool = Pool(MaybeUninit::uninit());
+
+/// Offset of the next free byte in `POOL`
+static mut NEXT: usize = 0;
+
+/// Reserve `size` bytes aligned to `align` bytes, and return their address
+///
+/// `align` must be a power of two.
+fn reserve(size: usize, align: usize) -> Option<usize> {
+    interrupt::free(|_| unsafe {
+        let base = POOL.0.as_mut_ptr() as usize;
+        let start = (base + NEXT + align - 1) & !(align - 1);
+        let end = start.checked_add(size)?;
+        if end > base + POOL_SIZE {
+            return None;
+        }
+        NEXT = end - base;
+        Some(start)
+    })
+}
+
+/// Reserve a buffer aligned to `align` bytes, and move `init` into it
+fn allocate<A>(init: A, align: usize) -> Option<&'static Buffer<A>> {
+    let buffer = reserve(mem::size_of::<Buffer<A>>(), align)? as *mut Buffer<A>;
+    unsafe {
+        buffer.write(Buffer::new(init));
+        Some(&*buffer)
+    }
+}
+
+/// Allocate a buffer for a linear transfer, initialized to `init`
+///
+/// `init` is an array, like `[0u16; 32]`. Returns `None` if the pool is exhausted.
+pub fn linear<A>(init: A) -> Option<&'static Buffer<A>> {
+    allocate(init, mem::align_of::<Buffer<A>>())
+}
+
+/// Allocate a buffer for a circular transfer, initialized to `init`
+///
+/// The buffer is aligned to the array's size, rounded up to a power of two. Returns `None`
+/// if the pool is exhausted. `hal::dma::Circular::new` rejects the buffer if the array's
+/// length isn't a power of two.
+pub fn circular<A>(init: A) -> Option<&'static Buffer<A>> {
+    let align = mem::size_of::<A>()
+        .next_power_of_two()
+        .max(mem::align_of::<Buffer<A>>());
+    allocate(init, align)
+}
+
+/// Returns the number of unallocated bytes in the pool
+///
+/// Alignment may waste some of these bytes when you allocate the next buffer.
+pub fn remaining() -> usize {
+    interrupt::free(|_| unsafe { POOL_SIZE - NEXT })
+}
+
+/// Data cache clean by address to the point of coherency
+const SCB_DCCMVAC: *mut u32 = 0xE000_EF68 as *mut u32;
+/// Data cache invalidate by address to the point of coherency
+const SCB_DCIMVAC: *mut u32 = 0xE000_EF5C as *mut u32;
+/// The data cache line size, in bytes
+const LINE: usize = 32;
+/// Memory below this address is tightly-coupled, and never cached
+const TCM_END: usize = 0x2020_0000;
+
+/// Returns `true` if the data cache may hold the `len` bytes at `start`
+fn is_cached(start: usize, len: usize) -> bool {
+    len != 0 && start + len > TCM_END
+}
+
+/// Write the cache lines that hold `data` with `operation`
+///
+/// Returns without touching the cache if `data` is in TCM.
+fn maintain(start: usize, len: usize, operation: *mut u32) {
+    if !is_cached(start, len) {
+        return;
+    }
+    let end = start + len;
+    asm::dsb();
+    let mut line = start & !(LINE - 1);
+    while line < end {
+        unsafe { ptr::write_volatile(operation, line as u32) };
+        line += LINE;
+    }
+    asm::dsb();
+    asm::isb();
+}
+
+/// Write `data` from the data cache to memory
+///
+/// Call before the DMA controller reads `data`, like before a transmit.
+pub fn clean<T: ?Sized>(data: &T) {
+    let start = data as *const T as *const u8 as usize;
+    maintain(start, mem::size_of_val(data), SCB_DCCMVAC);
+}
+
+/// Discard the cached copy of `data`, so that the CPU reads it from memory
+///
+/// Call after the DMA controller writes `data`, like after a receive. Cache lines are 32
+/// bytes. Invalidating a line discards everything in it, so `data` must start on a cache
+/// line, and its size must be a multiple of the line size. Cleaning a shared line isn't
+/// enough: a dirty line that's written back during the transfer overwrites the DMA data.
+///
+/// # Panics
+///
+/// Panics if `data` is in cached memory, and it doesn't start and end on a cache line.
+pub fn invalidate<T: ?Sized>(data: &mut T) {
+    let start = data as *mut T as *mut u8 as usize;
+    let len = mem::size_of_val(data);
+    if is_cached(start, len) {
+        assert!(
+            start % LINE == 0 && len % LINE == 0,
+            "DMA buffers that you invalidate must span whole 32 byte cache lines"
+        );
+    }
+    maintain(start, len, SCB_DCIMVAC);
+}
+
+//
+// Scatter-gather transfers
+//
+
+/// DMA controller (eDMA) registers
+const DMA: usize = 0x400E_8000;
+const DMA_CERQ: usize = DMA + 0x1A;
+const DMA_SERQ: usize = DMA + 0x1B;
+const DMA_CDNE: usize = DMA + 0x1C;
+const DMA_CINT: usize = DMA + 0x1F;
+const DMA_TCD: usize = DMA + 0x1000;
+/// DMA channel multiplexer registers
+const DMAMUX: usize = 0x400E_C000;
+const DMAMUX_ENBL: u32 = 1 << 31;
+
+const TCD_CSR_INTMAJOR: u16 = 1 << 1;
+const TCD_CSR_DREQ: u16 = 1 << 3;
+const TCD_CSR_ESG: u16 = 1 << 4;
+const TCD_CSR_MAJORELINK: u16 = 1 << 5;
+const TCD_CSR_DONE: u16 = 1 << 7;
+const fn tcd_csr_majorlinkch(channel: u8) -> u16 {
+    ((channel as u16) & 0x1F) << 8
+}
+
+/// The most elements in one segment
+pub const MAX_SEGMENT_LEN: usize = 0x7FFF;
+/// The most segments in one chain
+pub const MAX_SEGMENTS: usize = 8;
+
+/// A word that the DMA controller moves
+///
+/// Implemented for `u8`, `u16`, and `u32`.
+pub trait Word: crate::hal::dma::Element + private::Sealed {
+    /// The TCD `ATTR` size code, for both the source and destination
+    #[doc(hidden)]
+    const ATTR: u16;
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+impl Word for u8 {
+    const ATTR: u16 = 0;
+}
+impl Word for u16 {
+    const ATTR: u16 = (1 << 8) | 1;
+}
+impl Word for u32 {
+    const ATTR: u16 = (2 << 8) | 2;
+}
+
+/// A transfer control descriptor
+///
+/// The DMA controller loads the next descriptor from memory after each segment. Descriptors
+/// are 32-byte aligned, and live in the pool, so the DMA controller sees them without cache
+/// maintenance.
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(32))]
+struct Tcd {
+    saddr: u32,
+    soff: i16,
+    attr: u16,
+    nbytes: u32,
+    slast: i32,
+    daddr: u32,
+    doff: i16,
+    citer: u16,
+    dlast_sga: i32,
+    csr: u16,
+    biter: u16,
+}
+
+/// Scatter-gather errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainError {
+    /// The chain has no segments
+    NoSegments,
+    /// The chain has more than `MAX_SEGMENTS` segments
+    TooManySegments,
+    /// A segment is empty, or longer than `MAX_SEGMENT_LEN` elements
+    SegmentLength,
+    /// A receive chain has a segment to send, or a transmit chain has a segment to fill
+    Direction,
+    /// The pool can't fit the chain's descriptors
+    Pool,
+}
+
+/// Which side of the transfer is the peripheral
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    /// The DMA controller reads from the peripheral, and writes segments
+    Receive,
+    /// The DMA controller reads segments, and writes to the peripheral
+    Transmit,
+}
+
+/// Functions that enable, then disable, a peripheral's DMA requests
+type Requests<P, R> = (fn(&mut P) -> Result<(), R>, fn(&mut P));
+
+/// Builds a scatter-gather chain
+///
+/// A chain moves data between one peripheral and a list of memory segments, without the
+/// CPU between segments. Use [`receive`](#method.receive) to fill segments from a peripheral,
+/// like double-buffered ADC samples, or [`transmit`](#method.transmit) to send segments to a
+/// peripheral, like the parts of an SPI frame.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::dma::ChainBuilder;
+/// # fn spi4() -> bsp::hal::spi::SPI<bsp::hal::spi::module::_4> { unimplemented!() }
+///
+/// let mut peripherals = bsp::Peripherals::take().unwrap();
+/// let mut channels = peripherals.dma.clock(&mut peripherals.ccm.handle);
+/// let channel = channels[9].take().unwrap();
+/// # let spi = spi4();
+///
+/// static mut PING: [u16; 256] = [0; 256];
+/// static mut PONG: [u16; 256] = [0; 256];
+///
+/// let mut chain = ChainBuilder::receive(channel, spi)
+///     .segment(unsafe { &mut PING })
+///     .segment(unsafe { &mut PONG })
+///     .circular(true)
+///     .interrupt_on_segment(true)
+///     .build()
+///     .unwrap();
+/// chain.start().unwrap();
+/// ```
+///
+/// Use [`link`](#method.link) to start another chain's channel after each segment.
+///
+/// `R` is the error that the peripheral returns when it enables its DMA requests.
+pub struct ChainBuilder<E: Word, P, R> {
+    channel: crate::hal::dma::Channel,
+    peripheral: P,
+    direction: Direction,
+    /// The peripheral's data register
+    register: u32,
+    /// The peripheral's DMAMUX request signal
+    request: u32,
+    /// Enables, then disables, the peripheral's DMA requests
+    requests: Requests<P, R>,
+    segments: [(u32, usize); MAX_SEGMENTS],
+    count: usize,
+    too_many: bool,
+    wrong_direction: bool,
+    circular: bool,
+    interrupt: bool,
+    link: Option<u8>,
+    _element: core::marker::PhantomData<E>,
+}
+
+impl<E: Word, P, R> ChainBuilder<E, P, R> {
+    fn new(
+        channel: crate::hal::dma::Channel,
+        peripheral: P,
+        direction: Direction,
+        register: u32,
+        request: u32,
+        requests: Requests<P, R>,
+    ) -> Self {
+        ChainBuilder {
+            channel,
+            peripheral,
+            direction,
+            register,
+            request,
+            requests,
+            segments: [(0, 0); MAX_SEGMENTS],
+            count: 0,
+            too_many: false,
+            wrong_direction: false,
+            circular: false,
+            interrupt: false,
+            link: None,
+            _element: core::marker::PhantomData,
+        }
+    }
+
+    fn push(mut self, direction: Direction, address: u32, len: usize) -> Self {
+        if direction != self.direction {
+            self.wrong_direction = true;
+        } else if self.count < MAX_SEGMENTS {
+            self.segments[self.count] = (address, len);
+            self.count += 1;
+        } else {
+            self.too_many = true;
+        }
+        self
+    }
+
+    /// After the last segment, start again from the first segment
+    ///
+    /// A circular chain runs until you [`stop`](struct.Chain.html#method.stop) it. The
+    /// default is `false`: the peripheral's DMA requests are disabled after the last segment.
+    pub fn circular(mut self, circular: bool) -> Self {
+        self.circular = circular;
+        self
+    }
+
+    /// Interrupt after each segment
+    ///
+    /// The channel's DMA interrupt should call [`on_interrupt`](struct.Chain.html#method.on_interrupt).
+    pub fn interrupt_on_segment(mut self, interrupt: bool) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Start `chain`'s channel after each segment
+    ///
+    /// Linking to a built chain ensures that the linked channel has valid descriptors.
+    /// Usually, the linked chain isn't started; it runs once for each of this chain's
+    /// segments.
+    pub fn link<F: Word, Q, S>(mut self, chain: &Chain<F, Q, S>) -> Self {
+        self.link = Some(chain.channel.channel() as u8);
+        self
+    }
+
+    /// Write the descriptors, and prepare the channel
+    ///
+    /// Descriptors are allocated from the pool, and they're never freed.
+    pub fn build(self) -> Result<Chain<E, P, R>, ChainError> {
+        if self.wrong_direction {
+            return Err(ChainError::Direction);
+        }
+        if self.too_many {
+            return Err(ChainError::TooManySegments);
+        }
+        if self.count == 0 {
+            return Err(ChainError::NoSegments);
+        }
+        let segments = &self.segments[..self.count];
+        if segments
+            .iter()
+            .any(|&(_, len)| len == 0 || len > MAX_SEGMENT_LEN)
+        {
+            return Err(ChainError::SegmentLength);
+        }
+
+        let tcds = reserve(mem::size_of::<Tcd>() * self.count, mem::align_of::<Tcd>())
+            .ok_or(ChainError::Pool)? as *mut Tcd;
+        let size = mem::size_of::<E>();
+        for (index, &(address, len)) in segments.iter().enumerate() {
+            let mut tcd = Tcd {
+                attr: E::ATTR,
+                nbytes: size as u32,
+                citer: len as u16,
+                biter: len as u16,
+                ..Tcd::default()
+            };
+            match self.direction {
+                Direction::Receive => {
+                    tcd.saddr = self.register;
+                    tcd.daddr = address;
+                    tcd.doff = size as i16;
+                }
+                Direction::Transmit => {
+                    tcd.saddr = address;
+                    tcd.soff = size as i16;
+                    tcd.daddr = self.register;
+                }
+            }
+            let next = if index + 1 < segments.len() {
+                Some(index + 1)
+            } else if self.circular {
+                Some(0)
+            } else {
+                None
+            };
+            match next {
+                Some(next) => {
+                    tcd.csr |= TCD_CSR_ESG;
+                    tcd.dlast_sga = unsafe { tcds.add(next) } as i32;
+                }
+                None => tcd.csr |= TCD_CSR_DREQ,
+            }
+            if self.interrupt {
+                tcd.csr |= TCD_CSR_INTMAJOR;
+            }
+            if let Some(link) = self.link {
+                tcd.csr |= TCD_CSR_MAJORELINK | tcd_csr_majorlinkch(link);
+            }
+            unsafe { tcds.add(index).write(tcd) };
+        }
+
+        let number = self.channel.channel();
+        unsafe {
+            ptr::write_volatile(DMA_CERQ as *mut u8, number as u8);
+            load(number as u8, &*tcds);
+            let mux = (DMAMUX + number * 4) as *mut u32;
+            ptr::write_volatile(mux, 0);
+            ptr::write_volatile(mux, self.request | DMAMUX_ENBL);
+        }
+
+        Ok(Chain {
+            channel: self.channel,
+            peripheral: self.peripheral,
+            direction: self.direction,
+            requests: self.requests,
+            tcds,
+            count: self.count,
+            next: 0,
+            _element: core::marker::PhantomData,
+        })
+    }
+}
+
+impl<E: Word, P: crate::hal::dma::peripheral::Source<E>> ChainBuilder<E, P, P::Error> {
+    /// Fill segments with data from `peripheral`
+    pub fn receive(channel: crate::hal::dma::Channel, peripheral: P) -> Self {
+        let register = peripheral.source() as u32;
+        Self::new(
+            channel,
+            peripheral,
+            Direction::Receive,
+            register,
+            P::SOURCE_REQUEST_SIGNAL,
+            (P::enable_source, P::disable_source),
+        )
+    }
+
+    /// Add a segment for the DMA controller to fill
+    ///
+    /// `build` fails if this is a transmit chain.
+    pub fn segment(self, segment: &'static mut [E]) -> Self {
+        let address = segment.as_mut_ptr() as u32;
+        self.push(Direction::Receive, address, segment.len())
+    }
+}
+
+impl<E: Word, P: crate::hal::dma::peripheral::Destination<E>>
+    ChainBuilder<E, P, <P as crate::hal::dma::peripheral::Destination<E>>::Error>
+{
+    /// Send segments to `peripheral`
+    pub fn transmit(channel: crate::hal::dma::Channel, peripheral: P) -> Self {
+        let register = peripheral.destination() as u32;
+        Self::new(
+            channel,
+            peripheral,
+            Direction::Transmit,
+            register,
+            P::DESTINATION_REQUEST_SIGNAL,
+            (P::enable_destination, P::disable_destination),
+        )
+    }
+
+    /// Add a segment for the DMA controller to send
+    ///
+    /// `build` fails if this is a receive chain.
+    pub fn send(self, segment: &'static [E]) -> Self {
+        self.push(Direction::Transmit, segment.as_ptr() as u32, segment.len())
+    }
+}
+
+/// A scatter-gather chain, ready to run
+///
+/// Use a [`ChainBuilder`](struct.ChainBuilder.html) to create a chain.
+pub struct Chain<E, P, R> {
+    channel: crate::hal::dma::Channel,
+    peripheral: P,
+    direction: Direction,
+    requests: Requests<P, R>,
+    tcds: *mut Tcd,
+    count: usize,
+    /// The segment that completes next
+    next: usize,
+    _element: core::marker::PhantomData<E>,
+}
+
+// Safety: the descriptors belong to the chain, and live in the pool for the rest of the program
+unsafe impl<E, P: Send, R> Send for Chain<E, P, R> {}
+
+impl<E: Word, P, R> Chain<E, P, R> {
+    fn number(&self) -> u8 {
+        self.channel.channel() as u8
+    }
+
+    fn hardware(&self) -> *mut Tcd {
+        (DMA_TCD + self.channel.channel() * mem::size_of::<Tcd>()) as *mut Tcd
+    }
+
+    /// Returns the number of segments
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns `true` if the chain has no segments, which never happens
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns `true` if a non-circular chain finished its last segment
+    pub fn is_complete(&self) -> bool {
+        unsafe { ptr::read_volatile(&(*self.hardware()).csr) & TCD_CSR_DONE != 0 }
+    }
+
+    /// Handle the channel's interrupt
+    ///
+    /// Returns the index of the segment that completed. The index is only meaningful if you
+    /// enabled [`interrupt_on_segment`](struct.ChainBuilder.html#method.interrupt_on_segment).
+    pub fn on_interrupt(&mut self) -> usize {
+        unsafe { ptr::write_volatile(DMA_CINT as *mut u8, self.number()) };
+        let completed = self.next;
+        self.next = (self.next + 1) % self.count;
+        completed
+    }
+
+    /// Access the segment at `index`
+    ///
+    /// # Safety
+    ///
+    /// The DMA controller may be writing to the segment. Only access a segment that
+    /// completed, and that the DMA controller won't reach until you're done with it.
+    pub unsafe fn segment(&self, index: usize) -> &[E] {
+        let tcd = &*self.tcds.add(index);
+        let (address, len) = match self.direction {
+            Direction::Receive => (tcd.daddr, tcd.biter),
+            Direction::Transmit => (tcd.saddr, tcd.biter),
+        };
+        core::slice::from_raw_parts(address as *const E, len as usize)
+    }
+
+    /// Stop the transfer, and return the channel and peripheral
+    ///
+    /// The chain's descriptors stay allocated in the pool.
+    pub fn release(mut self) -> (crate::hal::dma::Channel, P) {
+        self.stop();
+        (self.channel, self.peripheral)
+    }
+
+    fn stop_requests(&mut self) {
+        unsafe {
+            ptr::write_volatile(DMA_CERQ as *mut u8, self.number());
+            ptr::write_volatile(DMA_CINT as *mut u8, self.number());
+        }
+    }
+}
+
+impl<E: Word, P, R> Chain<E, P, R> {
+    /// Start, or restart, the chain from the first segment
+    ///
+    /// Returns the peripheral's error if it can't enable its DMA requests. The channel
+    /// is stopped when that happens.
+    pub fn start(&mut self) -> Result<(), R> {
+        let number = self.number();
+        unsafe {
+            ptr::write_volatile(DMA_CERQ as *mut u8, number);
+            load(number, &*self.tcds);
+            ptr::write_volatile(DMA_SERQ as *mut u8, number);
+        }
+        self.next = 0;
+        (self.requests.0)(&mut self.peripheral).map_err(|error| {
+            self.stop_requests();
+            error
+        })
+    }
+
+    /// Stop the chain
+    pub fn stop(&mut self) {
+        (self.requests.1)(&mut self.peripheral);
+        self.stop_requests();
+    }
+}
+
+/// Load `tcd` into `channel`'s hardware descriptor
+///
+/// # Safety
+///
+/// The channel's requests must be disabled.
+unsafe fn load(channel: u8, tcd: &Tcd) {
+    let hardware = (DMA_TCD + channel as usize * mem::size_of::<Tcd>()) as *mut Tcd;
+    // ESG can't be set while DONE is set
+    ptr::write_volatile(&mut (*hardware).csr, 0);
+    ptr::write_volatile(DMA_CDNE as *mut u8, channel);
+    ptr::write_volatile(&mut (*hardware).saddr, tcd.saddr);
+    ptr::write_volatile(&mut (*hardware).soff, tcd.soff);
+    ptr::write_volatile(&mut (*hardware).attr, tcd.attr);
+    ptr::write_volatile(&mut (*hardware).nbytes, tcd.nbytes);
+    ptr::write_volatile(&mut (*hardware).slast, tcd.slast);
+    ptr::write_volatile(&mut (*hardware).daddr, tcd.daddr);
+    ptr::write_volatile(&mut (*hardware).doff, tcd.doff);
+    ptr::write_volatile(&mut (*hardware).citer, tcd.citer);
+    ptr::write_volatile(&mut (*hardware).dlast_sga, tcd.dlast_sga);
+    ptr::write_volatile(&mut (*hardware).biter, tcd.biter);
+    ptr::write_volatile(&mut (*hardware).csr, tcd.csr);
+}
+
+#[cfg(feature = "async-interrupts")]
+pub use self::wait::{duplex, receive, transfer, wait, ChainWait, Duplex, Receive, Transfer, Wait};
+
+/// DMA futures, woken by the BSP's `DMAn_DMAm` interrupt handlers
+#[cfg(feature = "async-interrupts")]
+mod wait {
+    use super::{Chain, Word, DMA, DMA_CINT};
+    use crate::executor::WakerSet;
+    use crate::hal::dma::{
+        peripheral::{Destination, Source},
+        Circular, Element, Error, Linear, Peripheral,
+    };
+    use crate::interrupt;
+    use core::{
+        future::Future,
+        pin::Pin,
+        ptr,
+        sync::atomic::{AtomicU32, Ordering},
+        task::{Context, Poll},
+    };
+
+    const DMA_INT: *mut u32 = (DMA + 0x24) as *mut u32;
+
+    /// One bit for each channel that interrupted, and that a task hasn't seen
+    static INTERRUPTED: AtomicU32 = AtomicU32::new(0);
+    static WAKERS: WakerSet = WakerSet::new();
+
+    /// Each channel's interrupt, indexed by channel number
+    const INTERRUPTS: [interrupt; 16] = [
+        interrupt::DMA0_DMA16,
+        interrupt::DMA1_DMA17,
+        interrupt::DMA2_DMA18,
+        interrupt::DMA3_DMA19,
+        interrupt::DMA4_DMA20,
+        interrupt::DMA5_DMA21,
+        interrupt::DMA6_DMA22,
+        interrupt::DMA7_DMA23,
+        interrupt::DMA8_DMA24,
+        interrupt::DMA9_DMA25,
+        interrupt::DMA10_DMA26,
+        interrupt::DMA11_DMA27,
+        interrupt::DMA12_DMA28,
+        interrupt::DMA13_DMA29,
+        interrupt::DMA14_DMA30,
+        interrupt::DMA15_DMA31,
+    ];
+
+    /// Wait for DMA `channel` to interrupt
+    ///
+    /// Use `wait` with a `hal::dma::Peripheral` that interrupts on completion, like a UART
+    /// or SPI transfer. When the future completes, the BSP has already cleared the channel's
+    /// interrupt flag. Call the peripheral's `transfer_complete` or `receive_complete` to
+    /// take back the buffer. [`transfer`](fn.transfer.html), [`receive`](fn.receive.html),
+    /// and [`duplex`](fn.duplex.html) do this for you.
+    ///
+    /// ```no_run
+    /// # async fn f() {
+    /// use teensy4_bsp as bsp;
+    ///
+    /// // DMA channel 7 transmits for a UART
+    /// bsp::dma::wait(7).await;
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` isn't a DMA channel, 0 through 31.
+    pub fn wait(channel: usize) -> Wait {
+        assert!(channel < 32, "DMA channel {} doesn't exist", channel);
+        Wait { channel }
+    }
+
+    /// A future that waits for a DMA channel's interrupt
+    ///
+    /// Use [`wait`](fn.wait.html) to create a `Wait`.
+    pub struct Wait {
+        channel: usize,
+    }
+
+    impl Future for Wait {
+        type Output = ();
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            WAKERS.register(cx.waker());
+            let mask = 1 << self.channel;
+            if INTERRUPTED.fetch_and(!mask, Ordering::AcqRel) & mask != 0 {
+                Poll::Ready(())
+            } else {
+                unsafe { cortex_m::peripheral::NVIC::unmask(INTERRUPTS[self.channel % 16]) };
+                Poll::Pending
+            }
+        }
+    }
+
+    impl<E: Word, P, R> Chain<E, P, R> {
+        /// Wait for the next segment to complete
+        ///
+        /// Returns the index of the segment that completed. Build the chain with
+        /// `interrupt_on_segment(true)`. Use either `wait` or `on_interrupt`, not both.
+        pub fn wait(&mut self) -> ChainWait<'_, E, P, R> {
+            ChainWait {
+                wait: wait(self.channel.channel()),
+                chain: self,
+            }
+        }
+    }
+
+    /// A future that waits for a chain's next segment
+    ///
+    /// Use [`Chain::wait`](struct.Chain.html#method.wait) to create a `ChainWait`.
+    pub struct ChainWait<'a, E, P, R> {
+        chain: &'a mut Chain<E, P, R>,
+        wait: Wait,
+    }
+
+    impl<'a, E: Word, P, R> Future for ChainWait<'a, E, P, R> {
+        type Output = usize;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            let this = self.get_mut();
+            match Pin::new(&mut this.wait).poll(cx) {
+                Poll::Ready(()) => {
+                    let completed = this.chain.next;
+                    this.chain.next = (this.chain.next + 1) % this.chain.count;
+                    Poll::Ready(completed)
+                }
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    /// Unmask all DMA interrupts
+    ///
+    /// `hal::dma::Peripheral` doesn't say which channels it uses. The BSP owns all of the
+    /// DMA interrupts, so unmasking the ones that aren't in use is harmless.
+    fn unmask_all() {
+        for &interrupt in INTERRUPTS.iter() {
+            unsafe { cortex_m::peripheral::NVIC::unmask(interrupt) };
+        }
+    }
+
+    /// Send `buffer` to a DMA peripheral, like a UART or SPI peripheral
+    ///
+    /// The future starts the transfer when it's first polled, and returns the buffer when
+    /// the transfer completes. Configure the transfer channel to interrupt on completion.
+    /// Await these futures, or `wait` on the peripheral's channels, not both.
+    ///
+    /// ```no_run
+    /// # async fn f() {
+    /// use teensy4_bsp as bsp;
+    /// use bsp::hal::dma;
+    ///
+    /// static TX: dma::Buffer<[u8; 64]> = dma::Buffer::new([0; 64]);
+    ///
+    /// let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+    /// let uart = board
+    ///     .uart
+    ///     .p14_p15(&mut board.ccm.handle, board.pins.p14, board.pins.p15, 115_200)
+    ///     .unwrap();
+    /// let mut channels = board.dma.clock(&mut board.ccm.handle);
+    /// let config = dma::ConfigBuilder::new()
+    ///     .interrupt_on_completion(true)
+    ///     .build();
+    /// let mut uart: dma::Peripheral<_, u8, dma::Linear<u8>> =
+    ///     dma::Peripheral::new_transfer(uart, channels[7].take().unwrap(), config);
+    ///
+    /// let mut tx = dma::Linear::new(&TX).unwrap();
+    /// tx.as_mut_elements()[..6].copy_from_slice(b"Hello!");
+    /// tx.set_transfer_len(6);
+    /// let tx = bsp::dma::transfer(&mut uart, tx).await.ok().unwrap();
+    /// # }
+    /// ```
+    pub fn transfer<P, E, R>(
+        peripheral: &mut Peripheral<P, E, Linear<E>, R>,
+        buffer: Linear<E>,
+    ) -> Transfer<'_, P, E, R>
+    where
+        E: Element,
+    {
+        Transfer {
+            peripheral,
+            buffer: Some(buffer),
+        }
+    }
+
+    /// A future that sends a buffer to a DMA peripheral
+    ///
+    /// Use [`transfer`](fn.transfer.html) to create a `Transfer`.
+    pub struct Transfer<'a, P, E: Element, R> {
+        peripheral: &'a mut Peripheral<P, E, Linear<E>, R>,
+        /// Taken when the transfer starts
+        buffer: Option<Linear<E>>,
+    }
+
+    impl<'a, P, E, R> Future for Transfer<'a, P, E, R>
+    where
+        P: Destination<E>,
+        E: Element,
+    {
+        type Output = Result<Linear<E>, (Linear<E>, Error<<P as Destination<E>>::Error>)>;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = self.get_mut();
+            WAKERS.register(cx.waker());
+            if let Some(buffer) = this.buffer.take() {
+                if let Err(error) = this.peripheral.start_transfer(buffer) {
+                    return Poll::Ready(Err(error));
+                }
+                unmask_all();
+            } else if this.peripheral.is_transfer_complete() {
+                if let Some(buffer) = this.peripheral.transfer_complete() {
+                    return Poll::Ready(Ok(buffer));
+                }
+            }
+            Poll::Pending
+        }
+    }
+
+    /// Fill `buffer` from a DMA peripheral, like a UART or SPI peripheral
+    ///
+    /// The future starts the receive when it's first polled, and returns the buffer when
+    /// the receive completes. `buffer` may be a `Linear` or a `Circular` buffer. Configure
+    /// the receive channel to interrupt on completion.
+    pub fn receive<P, E, T, B>(
+        peripheral: &mut Peripheral<P, E, T, B>,
+        buffer: B,
+    ) -> Receive<'_, P, E, T, B>
+    where
+        E: Element,
+    {
+        Receive {
+            peripheral,
+            buffer: Some(buffer),
+        }
+    }
+
+    /// A future that fills a buffer from a DMA peripheral
+    ///
+    /// Use [`receive`](fn.receive.html) to create a `Receive`.
+    pub struct Receive<'a, P, E: Element, T, B> {
+        peripheral: &'a mut Peripheral<P, E, T, B>,
+        /// Taken when the receive starts
+        buffer: Option<B>,
+    }
+
+    /// Implements `Future` for `Receive`s with a receive buffer type
+    macro_rules! receive_future {
+        ($($buffer:ident),*) => {
+            $(
+                impl<'a, P, E, T> Future for Receive<'a, P, E, T, $buffer<E>>
+                where
+                    P: Source<E>,
+                    E: Element,
+                {
+                    type Output = Result<$buffer<E>, ($buffer<E>, Error<P::Error>)>;
+                    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                        let this = self.get_mut();
+                        WAKERS.register(cx.waker());
+                        if let Some(buffer) = this.buffer.take() {
+                            if let Err(error) = this.peripheral.start_receive(buffer) {
+                                return Poll::Ready(Err(error));
+                            }
+                            unmask_all();
+                        } else if this.peripheral.is_receive_complete() {
+                            if let Some(buffer) = this.peripheral.receive_complete() {
+                                return Poll::Ready(Ok(buffer));
+                            }
+                        }
+                        Poll::Pending
+                    }
+                }
+            )*
+        };
+    }
+
+    receive_future!(Linear, Circular);
+
+    /// Exchange buffers with a full-duplex DMA peripheral, like a SPI peripheral
+    ///
+    /// The future starts the receive, then the transfer, when it's first polled. It returns
+    /// `(tx, rx)` when both complete. Configure the receive channel to interrupt on
+    /// completion; the receive completes after the transfer.
+    ///
+    /// If the transfer can't start, the future cancels the receive, and returns both
+    /// buffers with the error.
+    pub fn duplex<P, E>(
+        peripheral: &mut Peripheral<P, E, Linear<E>, Linear<E>>,
+        tx: Linear<E>,
+        rx: Linear<E>,
+    ) -> Duplex<'_, P, E>
+    where
+        E: Element,
+    {
+        Duplex {
+            peripheral,
+            buffers: Some((tx, rx)),
+        }
+    }
+
+    /// A future that exchanges buffers with a full-duplex DMA peripheral
+    ///
+    /// Use [`duplex`](fn.duplex.html) to create a `Duplex`.
+    pub struct Duplex<'a, P, E: Element> {
+        peripheral: &'a mut Peripheral<P, E, Linear<E>, Linear<E>>,
+        /// Taken when the transfers start
+        buffers: Option<(Linear<E>, Linear<E>)>,
+    }
+
+    impl<'a, P, E> Future for Duplex<'a, P, E>
+    where
+        P: Source<E> + Destination<E, Error = <P as Source<E>>::Error>,
+        E: Element,
+    {
+        #[allow(clippy::type_complexity)]
+        type Output =
+            Result<(Linear<E>, Linear<E>), (Linear<E>, Linear<E>, Error<<P as Source<E>>::Error>)>;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = self.get_mut();
+            WAKERS.register(cx.waker());
+            if let Some((tx, rx)) = this.buffers.take() {
+                if let Err((rx, error)) = this.peripheral.start_receive(rx) {
+                    return Poll::Ready(Err((tx, rx, error)));
+                }
+                if let Err((tx, error)) = this.peripheral.start_transfer(tx) {
+                    let rx = this.peripheral.receive_cancel().unwrap();
+                    return Poll::Ready(Err((tx, rx, error)));
+                }
+                unmask_all();
+            } else if this.peripheral.is_transfer_complete()
+                && this.peripheral.is_receive_complete()
+            {
+                if let (Some(tx), Some(rx)) = (
+                    this.peripheral.transfer_complete(),
+                    this.peripheral.receive_complete(),
+                ) {
+                    return Poll::Ready(Ok((tx, rx)));
+                }
+            }
+            Poll::Pending
+        }
+    }
+
+    /// Clear the interrupts of channels `n` and `n + 16`, and wake their tasks
+    fn on_interrupt(n: u8) {
+        unsafe {
+            let int = ptr::read_volatile(DMA_INT);
+            let mask = int & ((1 << n) | (1 << (n + 16)));
+            for channel in [n, n + 16].iter() {
+                if mask & (1 << channel) != 0 {
+                    ptr::write_volatile(DMA_CINT as *mut u8, *channel);
+                }
+            }
+            INTERRUPTED.fetch_or(mask, Ordering::Release);
+        }
+        WAKERS.wake();
+    }
+
+    macro_rules! handlers {
+        ($($handler:ident => $n:expr,)*) => {
+            $(
+                #[crate::rt::interrupt]
+                fn $handler() {
+                    on_interrupt($n);
+                }
+            )*
+        };
+    }
+
+    handlers! {
+        DMA0_DMA16 => 0,
+        DMA1_DMA17 => 1,
+        DMA2_DMA18 => 2,
+        DMA3_DMA19 => 3,
+        DMA4_DMA20 => 4,
+        DMA5_DMA21 => 5,
+        DMA6_DMA22 => 6,
+        DMA7_DMA23 => 7,
+        DMA8_DMA24 => 8,
+        DMA9_DMA25 => 9,
+        DMA10_DMA26 => 10,
+        DMA11_DMA27 => 11,
+        DMA12_DMA28 => 12,
+        DMA13_DMA29 => 13,
+        DMA14_DMA30 => 14,
+        DMA15_DMA31 => 15,
+    }
+}
@@ -0,0 +1,409 @@
+//! A minimal async executor
+//!
+//! [`spawn`](fn.spawn.html) moves a future into a static task arena, and [`run`](fn.run.html)
+//! polls the tasks that are ready. When no task is ready, the executor sleeps with `wfe`
+//! until an interrupt wakes a task.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::executor;
+//! use embedded_hal::digital::v2::ToggleableOutputPin;
+//!
+//! async fn blink(mut led: bsp::LED) {
+//!     loop {
+//!         led.toggle().unwrap();
+//!         executor::sleep(250).await;
+//!     }
+//! }
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let led = bsp::configure_led(&mut p.gpr, p.pins.p13);
+//! executor::spawn(blink(led)).unwrap();
+//! executor::run();
+//! ```
+//!
+//! The executor runs on the main thread. Spawn tasks from the main thread, or from another
+//! task, but not from an interrupt. Call `run` once; it panics if it's already running.
+//!
+//! ## Futures
+//!
+//! These futures wake their tasks from the interrupt handlers that the BSP owns:
+//!
+//! - [`sleep`](fn.sleep.html) waits on the `SysTick` counter, with the `"systick"` feature.
+//! - `pin_interrupt::edges` waits for pin edges, with the `"pin-interrupts"` feature.
+//! - `dma::wait`, and `Chain::wait` in the [`dma`](../dma/index.html) module, wait for a DMA
+//!   channel to interrupt. Requires the `"async-interrupts"` feature.
+//! - [`pit`](fn.pit.html) and [`gpt`](fn.gpt.html) wait for timers. Requires the
+//!   `"async-interrupts"` feature.
+//! - `dma::transfer`, `dma::receive`, and `dma::duplex` run UART and SPI DMA transfers with
+//!   a `hal::dma::Peripheral`, and return the buffers when the transfers complete. Requires
+//!   the `"async-interrupts"` feature.
+//! - The `i2c::transfer` module's futures run I2C controller transfers from the `LPI2Cn`
+//!   interrupts. Requires the `"async-interrupts"` feature.
+//!
+//! The LPI2C controllers can signal DMA requests, but the HAL doesn't implement DMA for
+//! them, so the I2C futures move each byte in the LPI2C's interrupt.
+//!
+//! Use a [`WakerSet`](struct.WakerSet.html) to wake tasks from your own interrupt handlers.
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    mem::{self, MaybeUninit},
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+use cortex_m::{
+    asm,
+    interrupt::{self, Mutex},
+};
+
+/// The most tasks that may exist at once
+pub const MAX_TASKS: usize = 8;
+/// The size of the task arena, in bytes
+pub const ARENA_SIZE: usize = 8 * 1024;
+
+/// Errors when spawning a task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnError {
+    /// There are already `MAX_TASKS` tasks
+    TooManyTasks,
+    /// The arena can't fit the task's future
+    Arena,
+}
+
+#[repr(C, align(8))]
+struct Arena(MaybeUninit<[u8; ARENA_SIZE]>);
+
+static mut ARENA: Arena = Arena(MaybeUninit::uninit());
+/// Offset of the next free byte in `ARENA`
+static mut ARENA_NEXT: usize = 0;
+
+type Task = *mut dyn Future<Output = ()>;
+static mut TASKS: [Option<Task>; MAX_TASKS] = [None; MAX_TASKS];
+
+/// One bit for each task that should be polled
+static READY: AtomicU32 = AtomicU32::new(0);
+/// Set once `run` starts
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Spawn a task
+///
+/// The future moves into the task arena, and runs on the next pass of [`run`](fn.run.html).
+/// The arena never frees a future. When a task completes, its slot may hold another task,
+/// but its arena memory is lost. Spawn long-lived tasks.
+pub fn spawn<F: Future<Output = ()> + 'static>(future: F) -> Result<(), SpawnError> {
+    interrupt::free(|_| unsafe {
+        let index = TASKS
+            .iter()
+            .position(Option::is_none)
+            .ok_or(SpawnError::TooManyTasks)?;
+        let base = ARENA.0.as_mut_ptr() as usize;
+        let align = mem::align_of::<F>();
+        let start = (base + ARENA_NEXT + align - 1) & !(align - 1);
+        let end = start + mem::size_of::<F>();
+        if end > base + ARENA_SIZE {
+            return Err(SpawnError::Arena);
+        }
+        ARENA_NEXT = end - base;
+        let task = start as *mut F;
+        task.write(future);
+        TASKS[index] = Some(task as Task);
+        READY.fetch_or(1 << index, Ordering::Release);
+        Ok(())
+    })
+}
+
+/// Poll tasks forever
+///
+/// When no task is ready, `run` waits for an event with `wfe`.
+///
+/// # Panics
+///
+/// Panics if `run` is already running, like when a task or an interrupt calls `run`.
+pub fn run() -> ! {
+    if RUNNING.swap(true, Ordering::AcqRel) {
+        panic!("the executor is already running");
+    }
+    loop {
+        let ready = READY.swap(0, Ordering::AcqRel);
+        if ready == 0 {
+            // A wake between the swap and here signals an event, so wfe returns
+            asm::wfe();
+            continue;
+        }
+        for index in 0..MAX_TASKS {
+            if ready & (1 << index) != 0 {
+                unsafe { poll(index) };
+            }
+        }
+    }
+}
+
+/// Poll the task at `index`, and drop it if it completed
+///
+/// # Safety
+///
+/// Only call from `run`, which only runs once.
+unsafe fn poll(index: usize) {
+    let task = match TASKS[index] {
+        Some(task) => task,
+        None => return,
+    };
+    let waker = Waker::from_raw(RawWaker::new(index as *const (), &VTABLE));
+    let mut context = Context::from_waker(&waker);
+    // Tasks never move out of the arena
+    if let Poll::Ready(()) = Pin::new_unchecked(&mut *task).poll(&mut context) {
+        core::ptr::drop_in_place(task);
+        interrupt::free(|_| TASKS[index] = None);
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop_waker);
+
+unsafe fn clone(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    READY.fetch_or(1 << data as usize, Ordering::Release);
+    asm::sev();
+}
+
+unsafe fn drop_waker(_: *const ()) {}
+
+const NO_WAKER: Option<Waker> = None;
+
+/// Wakers for the tasks that wait on an interrupt
+///
+/// A future registers its task's waker when it's pending, and the interrupt handler wakes
+/// all registered tasks. The set holds one waker for each task.
+///
+/// ```no_run
+/// use teensy4_bsp::executor::WakerSet;
+///
+/// static RECEIVED: WakerSet = WakerSet::new();
+///
+/// // In a future's poll():
+/// // RECEIVED.register(cx.waker());
+///
+/// // In the interrupt handler:
+/// RECEIVED.wake();
+/// ```
+pub struct WakerSet {
+    wakers: Mutex<RefCell<[Option<Waker>; MAX_TASKS]>>,
+}
+
+impl Default for WakerSet {
+    fn default() -> Self {
+        WakerSet::new()
+    }
+}
+
+impl WakerSet {
+    /// Create an empty set
+    pub const fn new() -> Self {
+        WakerSet {
+            wakers: Mutex::new(RefCell::new([NO_WAKER; MAX_TASKS])),
+        }
+    }
+
+    /// Register `waker`, to be woken by the next call to `wake`
+    ///
+    /// If the set is full, `waker` is woken immediately, and its task polls again.
+    pub fn register(&self, waker: &Waker) {
+        interrupt::free(|cs| {
+            let mut wakers = self.wakers.borrow(cs).borrow_mut();
+            if wakers
+                .iter()
+                .flatten()
+                .any(|registered| registered.will_wake(waker))
+            {
+                return;
+            }
+            match wakers.iter_mut().find(|slot| slot.is_none()) {
+                Some(slot) => *slot = Some(waker.clone()),
+                None => waker.wake_by_ref(),
+            }
+        })
+    }
+
+    /// Wake, and remove, all registered wakers
+    pub fn wake(&self) {
+        interrupt::free(|cs| {
+            for waker in self.wakers.borrow(cs).borrow_mut().iter_mut() {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "systick")]
+pub use crate::systick::{sleep, Sleep};
+
+#[cfg(feature = "async-interrupts")]
+pub use timers::{gpt, pit, Gpt, Pit};
+
+/// Timer futures, woken by the BSP's `PIT` and `GPTn` interrupt handlers
+#[cfg(feature = "async-interrupts")]
+mod timers {
+    use super::WakerSet;
+    use crate::hal::{gpt, pit};
+    use crate::interrupt;
+    use core::{
+        future::Future,
+        pin::Pin,
+        ptr,
+        task::{Context, Poll},
+        time::Duration,
+    };
+    use embedded_hal::timer::CountDown;
+
+    const PIT_TCTRL: [*mut u32; 4] = [
+        0x4008_4108 as *mut u32,
+        0x4008_4118 as *mut u32,
+        0x4008_4128 as *mut u32,
+        0x4008_4138 as *mut u32,
+    ];
+    const PIT_TFLG_TIF: u32 = 1 << 0;
+    const PIT_TCTRL_TIE: u32 = 1 << 1;
+
+    /// `(SR, IR)` for GPT1 and GPT2
+    const GPT: [(*mut u32, *mut u32); 2] = [
+        (0x401E_C008 as *mut u32, 0x401E_C00C as *mut u32),
+        (0x401F_0008 as *mut u32, 0x401F_000C as *mut u32),
+    ];
+    const GPT_OF1: u32 = 1 << 0;
+
+    static PIT_WAKERS: WakerSet = WakerSet::new();
+    static GPT_WAKERS: WakerSet = WakerSet::new();
+
+    /// The output compare register that `gpt` uses
+    const OCR: gpt::OutputCompareRegister = gpt::OutputCompareRegister::One;
+
+    /// Wait for a PIT channel to count down `duration`
+    ///
+    /// The timer keeps running after it expires.
+    pub fn pit<C>(timer: &mut pit::PIT<C>, duration: Duration) -> Pit<'_, C> {
+        Pit {
+            timer,
+            duration: Some(duration),
+        }
+    }
+
+    /// A future that waits for a PIT channel
+    ///
+    /// Use [`pit`](fn.pit.html) to create a `Pit`.
+    pub struct Pit<'a, C> {
+        timer: &'a mut pit::PIT<C>,
+        /// Taken when the timer starts
+        duration: Option<Duration>,
+    }
+
+    impl<'a, C> Future for Pit<'a, C>
+    where
+        pit::PIT<C>: CountDown<Time = Duration>,
+    {
+        type Output = ();
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let this = self.get_mut();
+            PIT_WAKERS.register(cx.waker());
+            if let Some(duration) = this.duration.take() {
+                this.timer.start(duration);
+            } else if this.timer.wait().is_ok() {
+                this.timer.set_interrupt_enable(false);
+                return Poll::Ready(());
+            }
+            this.timer.set_interrupt_enable(true);
+            unsafe { cortex_m::peripheral::NVIC::unmask(interrupt::PIT) };
+            Poll::Pending
+        }
+    }
+
+    /// Wait for a GPT to count `duration`
+    ///
+    /// The future uses output compare register one, and enables the timer. With the `"rtic"`
+    /// feature, only GPT1 wakes its task; GPT2 is the `monotonic` timer.
+    pub fn gpt(timer: &mut gpt::GPT, duration: Duration) -> Gpt<'_> {
+        Gpt {
+            timer,
+            duration: Some(duration),
+        }
+    }
+
+    /// A future that waits for a GPT
+    ///
+    /// Use [`gpt`](fn.gpt.html) to create a `Gpt`.
+    pub struct Gpt<'a> {
+        timer: &'a mut gpt::GPT,
+        /// Taken when the timer starts
+        duration: Option<Duration>,
+    }
+
+    impl<'a> Future for Gpt<'a> {
+        type Output = ();
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let this = self.get_mut();
+            GPT_WAKERS.register(cx.waker());
+            if let Some(duration) = this.duration.take() {
+                this.timer.set_enable(false);
+                this.timer.output_compare_status(OCR).clear();
+                this.timer.set_output_compare_duration(OCR, duration);
+                this.timer.set_enable(true);
+            } else if this.timer.output_compare_status(OCR).is_set() {
+                this.timer.output_compare_status(OCR).clear();
+                this.timer.set_output_interrupt_on_compare(OCR, false);
+                return Poll::Ready(());
+            }
+            this.timer.set_output_interrupt_on_compare(OCR, true);
+            unsafe {
+                cortex_m::peripheral::NVIC::unmask(interrupt::GPT1);
+                #[cfg(not(feature = "rtic"))]
+                cortex_m::peripheral::NVIC::unmask(interrupt::GPT2);
+            }
+            Poll::Pending
+        }
+    }
+
+    #[crate::rt::interrupt]
+    fn PIT() {
+        // Mask expired channels, and leave their flags for the futures
+        for &tctrl in PIT_TCTRL.iter() {
+            unsafe {
+                let tflg = tctrl.add(1);
+                let value = ptr::read_volatile(tctrl);
+                if value & PIT_TCTRL_TIE != 0 && ptr::read_volatile(tflg) & PIT_TFLG_TIF != 0 {
+                    ptr::write_volatile(tctrl, value & !PIT_TCTRL_TIE);
+                }
+            }
+        }
+        PIT_WAKERS.wake();
+    }
+
+    /// Mask the output compare interrupt of GPT `n`, and leave its flag for the future
+    fn on_gpt(n: usize) {
+        let (sr, ir) = GPT[n];
+        unsafe {
+            let value = ptr::read_volatile(ir);
+            if value & ptr::read_volatile(sr) & GPT_OF1 != 0 {
+                ptr::write_volatile(ir, value & !GPT_OF1);
+            }
+        }
+        GPT_WAKERS.wake();
+    }
+
+    #[crate::rt::interrupt]
+    fn GPT1() {
+        on_gpt(0);
+    }
+
+    /// With the `"rtic"` feature, GPT2 is the `monotonic` timer
+    #[cfg(not(feature = "rtic"))]
+    #[crate::rt::interrupt]
+    fn GPT2() {
+        on_gpt(1);
+    }
+}
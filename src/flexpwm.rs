@@ -0,0 +1,280 @@
+//! FlexPWM register access, keyed by Teensy pin
+//!
+//! The HAL's PWM API requires that we know the pin's type. This module lets
+//! us drive a FlexPWM output when we only know the Teensy pin number. Each board
+//! module describes its FlexPWM outputs in a `PWM_OUTPUTS` table.
+//!
+//! We configure FlexPWM submodules the same way that Teensyduino configures them:
+//! each submodule counts up from zero, with independent A, B, and X outputs. The A
+//! and B outputs turn on at the start of the period. The X output turns off at the
+//! end of the period.
+
+use crate::{board, gpio};
+use core::ptr;
+
+/// A FlexPWM output channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// The `PWMA` output
+    A,
+    /// The `PWMB` output
+    B,
+    /// The auxiliary `PWMX` output
+    X,
+}
+
+/// A FlexPWM output that's connected to a Teensy pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Output {
+    /// FlexPWM module, 1 through 4
+    pub module: u8,
+    /// Submodule, 0 through 3
+    pub submodule: u8,
+    /// Output channel
+    pub channel: Channel,
+    /// Pad alternate that selects this output
+    pub alt: u8,
+}
+
+impl Output {
+    pub(crate) const fn new(module: u8, submodule: u8, channel: Channel, alt: u8) -> Self {
+        Output {
+            module,
+            submodule,
+            channel,
+            alt,
+        }
+    }
+}
+
+/// Returns the FlexPWM output for the Teensy pin, `pin`
+///
+/// Returns `None` if the pin doesn't have a FlexPWM output.
+pub fn output(pin: u8) -> Option<Output> {
+    board::PWM_OUTPUTS
+        .iter()
+        .find(|(number, _)| *number == pin)
+        .map(|(_, output)| *output)
+}
+
+const BASE: [u32; 4] = [0x403D_C000, 0x403E_0000, 0x403E_4000, 0x403E_8000];
+const SUBMODULE_STRIDE: u32 = 0x60;
+
+const SM_INIT: u32 = 0x02;
+const SM_CTRL2: u32 = 0x04;
+const SM_CTRL: u32 = 0x06;
+const SM_VAL0: u32 = 0x0A;
+const SM_VAL1: u32 = 0x0E;
+const SM_VAL2: u32 = 0x12;
+const SM_VAL3: u32 = 0x16;
+const SM_VAL4: u32 = 0x1A;
+const SM_VAL5: u32 = 0x1E;
+const SM_OCTRL: u32 = 0x22;
+const SM_DTCNT0: u32 = 0x30;
+
+const OUTEN: u32 = 0x180;
+const MCTRL: u32 = 0x188;
+const FCTRL0: u32 = 0x18C;
+const FSTS0: u32 = 0x18E;
+const FFILT0: u32 = 0x190;
+
+const SM_CTRL2_INDEP: u16 = 1 << 13;
+const SM_CTRL2_WAITEN: u16 = 1 << 14;
+const SM_CTRL2_DBGEN: u16 = 1 << 15;
+const SM_CTRL_FULL: u16 = 1 << 10;
+const SM_CTRL_PRSC_SHIFT: u16 = 4;
+const fn mctrl_ldok(mask: u16) -> u16 {
+    mask & 0xF
+}
+const fn mctrl_cldok(mask: u16) -> u16 {
+    (mask & 0xF) << 4
+}
+const fn mctrl_run(mask: u16) -> u16 {
+    (mask & 0xF) << 8
+}
+/// Fault inputs are active high. They're pulled low, so they never fault.
+const FCTRL0_FLVL_ALL: u16 = 0xF << 12;
+
+/// Pad drive strength for PWM outputs
+const PAD_CTL_DSE_6: u32 = 6 << 3;
+
+/// The default switching frequency, matching Teensyduino
+pub const DEFAULT_FREQUENCY_HZ: u32 = 4_482;
+
+fn register(module: u8, offset: u32) -> *mut u16 {
+    (BASE[module as usize - 1] + offset) as *mut u16
+}
+
+fn sm_register(output: Output, offset: u32) -> *mut u16 {
+    register(
+        output.module,
+        SUBMODULE_STRIDE * output.submodule as u32 + offset,
+    )
+}
+
+unsafe fn modify(register: *mut u16, f: impl FnOnce(u16) -> u16) {
+    ptr::write_volatile(register, f(ptr::read_volatile(register)));
+}
+
+/// Computes the submodule prescaler and modulo for a switching frequency
+///
+/// Returns `(prescaler, modulo)`. The prescaler is a power of two exponent,
+/// 0 through 7.
+pub const fn timing(ipg_hz: u32, frequency_hz: u32) -> (u16, u16) {
+    let mut divider = (ipg_hz + frequency_hz / 2) / frequency_hz;
+    let mut prescaler = 0;
+    while divider > 0xFFFF && prescaler < 7 {
+        divider >>= 1;
+        prescaler += 1;
+    }
+    if divider > 0xFFFF {
+        divider = 0xFFFF;
+    } else if divider < 2 {
+        divider = 2;
+    }
+    (prescaler, (divider - 1) as u16)
+}
+
+/// Initialize all four submodules of a FlexPWM module, and start
+/// them at the default frequency
+///
+/// # Safety
+///
+/// The module's clock gate must be on. The caller must own the module.
+pub unsafe fn init(module: u8, ipg_hz: u32) {
+    ptr::write_volatile(register(module, FCTRL0), FCTRL0_FLVL_ALL);
+    ptr::write_volatile(register(module, FSTS0), 0x000F);
+    ptr::write_volatile(register(module, FFILT0), 0);
+    modify(register(module, MCTRL), |mctrl| mctrl | mctrl_cldok(0xF));
+    let (prescaler, modulo) = timing(ipg_hz, DEFAULT_FREQUENCY_HZ);
+    for submodule in 0..4 {
+        let output = Output::new(module, submodule, Channel::A, 0);
+        ptr::write_volatile(
+            sm_register(output, SM_CTRL2),
+            SM_CTRL2_INDEP | SM_CTRL2_WAITEN | SM_CTRL2_DBGEN,
+        );
+        ptr::write_volatile(
+            sm_register(output, SM_CTRL),
+            SM_CTRL_FULL | (prescaler << SM_CTRL_PRSC_SHIFT),
+        );
+        ptr::write_volatile(sm_register(output, SM_OCTRL), 0);
+        ptr::write_volatile(sm_register(output, SM_DTCNT0), 0);
+        ptr::write_volatile(sm_register(output, SM_INIT), 0);
+        ptr::write_volatile(sm_register(output, SM_VAL0), 0);
+        ptr::write_volatile(sm_register(output, SM_VAL1), modulo);
+        for &val in &[SM_VAL2, SM_VAL3, SM_VAL4, SM_VAL5] {
+            ptr::write_volatile(sm_register(output, val), 0);
+        }
+    }
+    modify(register(module, MCTRL), |mctrl| {
+        mctrl | mctrl_ldok(0xF) | mctrl_run(0xF)
+    });
+}
+
+/// Returns the submodule's modulo, which is the largest compare value
+pub fn modulo(output: Output) -> u16 {
+    unsafe { ptr::read_volatile(sm_register(output, SM_VAL1)) }
+}
+
+/// Set the switching frequency of the output's submodule
+///
+/// The new frequency affects all outputs of the submodule. Returns the
+/// new modulo. Compare values are scaled to keep each output's duty cycle.
+///
+/// # Safety
+///
+/// The module must be initialized. The caller must own the submodule.
+pub unsafe fn set_frequency(output: Output, ipg_hz: u32, frequency_hz: u32) -> u16 {
+    let (prescaler, modulo) = timing(ipg_hz, frequency_hz.max(1));
+    let old_modulo = self::modulo(output) as u32 + 1;
+    let mask = 1 << output.submodule;
+    modify(register(output.module, MCTRL), |mctrl| {
+        mctrl | mctrl_cldok(mask)
+    });
+    ptr::write_volatile(
+        sm_register(output, SM_CTRL),
+        SM_CTRL_FULL | (prescaler << SM_CTRL_PRSC_SHIFT),
+    );
+    for &val in &[SM_VAL3, SM_VAL5] {
+        let compare = ptr::read_volatile(sm_register(output, val)) as u32;
+        let compare = (compare * (modulo as u32 + 1)) / old_modulo;
+        ptr::write_volatile(sm_register(output, val), compare as u16);
+    }
+    let compare = old_modulo - 1 - ptr::read_volatile(sm_register(output, SM_VAL0)) as u32;
+    let compare = (compare * (modulo as u32 + 1)) / old_modulo;
+    ptr::write_volatile(sm_register(output, SM_VAL0), modulo - compare as u16);
+    ptr::write_volatile(sm_register(output, SM_VAL1), modulo);
+    modify(register(output.module, MCTRL), |mctrl| {
+        mctrl | mctrl_ldok(mask)
+    });
+    modulo
+}
+
+/// Set the output's compare value, which is the number of counts that
+/// the output is high
+///
+/// `compare` is clamped to the submodule's modulo.
+///
+/// # Safety
+///
+/// The module must be initialized. The caller must own the output.
+pub unsafe fn set_compare(output: Output, compare: u16) {
+    let modulo = self::modulo(output);
+    let compare = compare.min(modulo);
+    let mask = 1 << output.submodule;
+    modify(register(output.module, MCTRL), |mctrl| {
+        mctrl | mctrl_cldok(mask)
+    });
+    match output.channel {
+        Channel::A => ptr::write_volatile(sm_register(output, SM_VAL3), compare),
+        Channel::B => ptr::write_volatile(sm_register(output, SM_VAL5), compare),
+        Channel::X => ptr::write_volatile(sm_register(output, SM_VAL0), modulo - compare),
+    }
+    modify(register(output.module, MCTRL), |mctrl| {
+        mctrl | mctrl_ldok(mask)
+    });
+}
+
+/// Returns the output's compare value
+pub fn compare(output: Output) -> u16 {
+    unsafe {
+        match output.channel {
+            Channel::A => ptr::read_volatile(sm_register(output, SM_VAL3)),
+            Channel::B => ptr::read_volatile(sm_register(output, SM_VAL5)),
+            Channel::X => modulo(output) - ptr::read_volatile(sm_register(output, SM_VAL0)),
+        }
+    }
+}
+
+/// Enable or disable the output
+///
+/// # Safety
+///
+/// The caller must own the output.
+pub unsafe fn set_enable(output: Output, enable: bool) {
+    let mask = 1 << output.submodule;
+    let bits = match output.channel {
+        Channel::X => mask,
+        Channel::B => mask << 4,
+        Channel::A => mask << 8,
+    };
+    cortex_m::interrupt::free(|_| {
+        modify(register(output.module, OUTEN), |outen| {
+            if enable {
+                outen | bits
+            } else {
+                outen & !bits
+            }
+        })
+    });
+}
+
+/// Route the Teensy pin's pad to its FlexPWM output
+///
+/// # Safety
+///
+/// The caller must own the pad.
+pub unsafe fn mux(pad: gpio::Pad, output: Output) {
+    ptr::write_volatile(pad.pad_ctl() as *mut u32, PAD_CTL_DSE_6);
+    ptr::write_volatile(pad.mux_ctl() as *mut u32, output.alt as u32);
+}
@@ -0,0 +1,405 @@
+//! Type-erased GPIO pins
+//!
+//! Each field of [`Pins`](../struct.Pins.html) has a distinct type. That's great for
+//! statically checking pin assignments, but it prevents storing pins in an array, or
+//! selecting a pin from a configuration value. A [`DynPin`](struct.DynPin.html) erases
+//! the pin's type, and remembers only the Teensy pin number. Use a `DynPin` as a
+//! GPIO input or output.
+//!
+//! Create a `DynPin` by either
+//!
+//! - consuming a single field of `Pins`, with [`DynPin::new`](struct.DynPin.html#method.new), or
+//! - consuming all of the `Pins` with [`Pins::erase`](../struct.Pins.html#method.erase), then
+//!   taking pins by number from the returned [`ErasedPins`](struct.ErasedPins.html).
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use embedded_hal::digital::v2::ToggleableOutputPin;
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let mut pins = p.pins.erase();
+//! let mut outputs = [pins.take(2).unwrap(), pins.take(3).unwrap()];
+//! for output in outputs.iter_mut() {
+//!     output.set_output();
+//!     output.toggle().unwrap();
+//! }
+//! ```
+//!
+//! By default, a `DynPin` uses the processor's fast GPIO banks (GPIO6 through GPIO9), just
+//! like [`configure_led`](../fn.configure_led.html). Use [`set_fast`](struct.DynPin.html#method.set_fast)
+//! to select the normal GPIO banks (GPIO1 through GPIO4). Only the normal GPIO banks
+//! can signal the pin interrupts described in the [`pin_interrupt`](../pin_interrupt/index.html)
+//! module.
+
+use crate::{board, hal};
+use core::{convert::Infallible, ptr};
+
+/// The number of pins on the selected board
+pub const NUM_PINS: usize = board::NUM_PINS;
+
+pub use teensy4_pads::{Group, Pad};
+
+/// Returns the pad for the Teensy pin, `pin`
+///
+/// Returns `None` if the pin is not available on the selected board.
+pub fn pad(pin: u8) -> Option<Pad> {
+    board::PADS.get(pin as usize).copied()
+}
+
+/// A pad that's physically connected to a Teensy pin
+///
+/// `TeensyPin` is implemented on each pad type that's in [`Pins`](../struct.Pins.html).
+pub trait TeensyPin {
+    /// The Teensy pin number
+    const PIN: u8;
+}
+
+/// Implements `TeensyPin` for each pad, and defines the board's pad table,
+/// `PADS`, which is indexed by Teensy pin number
+///
+/// `$table` is the board's table from the `teensy4-pads` crate. The build fails if
+/// a listed pad doesn't match the table.
+macro_rules! teensy_pins {
+    ($table:expr; $($pin:literal => $pad:ident ($group:ident, $offset:literal),)+) => {
+        /// Pads indexed by Teensy pin number
+        pub(crate) static PADS: [$crate::gpio::Pad; NUM_PINS] = $table;
+
+        $(
+            impl<M> $crate::gpio::TeensyPin for $crate::hal::iomuxc::gpio::$pad<M> {
+                const PIN: u8 = $pin;
+            }
+            const _: [(); 1] = [(); ($table[$pin].mux_ctl()
+                == $crate::gpio::Pad::new($crate::gpio::Group::$group, $offset).mux_ctl())
+                as usize];
+        )+
+    };
+}
+
+//
+// Register addresses
+//
+
+/// GPIO6 through GPIO9
+const FAST_GPIO_BASE: [u32; 4] = [0x4200_0000, 0x4200_4000, 0x4200_8000, 0x4200_C000];
+/// GPIO1 through GPIO4
+const GPIO_BASE: [u32; 4] = [0x401B_8000, 0x401B_C000, 0x401C_0000, 0x401C_4000];
+const GPIO_DR: u32 = 0x00;
+const GPIO_GDIR: u32 = 0x04;
+const GPIO_PSR: u32 = 0x08;
+const GPIO_DR_SET: u32 = 0x84;
+const GPIO_DR_CLEAR: u32 = 0x88;
+const GPIO_DR_TOGGLE: u32 = 0x8C;
+
+/// Base of `IOMUXC_GPR_GPR26`, which selects GPIO1 or GPIO6. The next three registers
+/// select between GPIO2 and GPIO7, GPIO3 and GPIO8, and GPIO4 and GPIO9.
+const IOMUXC_GPR_GPR26: u32 = 0x400A_C068;
+
+const ALT5: u32 = 5;
+
+/// Pad pull-up, pull-down, or keeper configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    /// No pull, and no keeper
+    None,
+    /// Keep the last driven value
+    Keeper,
+    /// 100KOhm pull down
+    Down100k,
+    /// 47KOhm pull up
+    Up47k,
+    /// 100KOhm pull up
+    Up100k,
+    /// 22KOhm pull up
+    Up22k,
+}
+
+const PAD_CTL_PUS_SHIFT: u32 = 14;
+const PAD_CTL_PUE: u32 = 1 << 13;
+const PAD_CTL_PKE: u32 = 1 << 12;
+const PAD_CTL_PULL_MASK: u32 = (0b11 << PAD_CTL_PUS_SHIFT) | PAD_CTL_PUE | PAD_CTL_PKE;
+const PAD_CTL_HYS: u32 = 1 << 16;
+
+impl Pull {
+    /// Returns the `SW_PAD_CTL_PAD` bits that select this configuration
+    const fn pad_ctl(self) -> u32 {
+        const fn pull(pus: u32) -> u32 {
+            (pus << PAD_CTL_PUS_SHIFT) | PAD_CTL_PUE | PAD_CTL_PKE
+        }
+        match self {
+            Pull::None => 0,
+            Pull::Keeper => PAD_CTL_PKE,
+            Pull::Down100k => pull(0b00),
+            Pull::Up47k => pull(0b01),
+            Pull::Up100k => pull(0b10),
+            Pull::Up22k => pull(0b11),
+        }
+    }
+}
+
+/// A type-erased GPIO pin
+///
+/// See the [module-level documentation](index.html) for more information.
+#[derive(Debug)]
+pub struct DynPin {
+    pin: u8,
+    pad: Pad,
+    fast: bool,
+}
+
+impl DynPin {
+    /// Consumes a Teensy pin, and returns a GPIO input
+    ///
+    /// The `GPR` handle ensures that nothing else is modifying the fast GPIO
+    /// selection. The pin is configured as an input.
+    pub fn new<P: TeensyPin>(_pin: P, _gpr: &mut hal::iomuxc::GPR) -> Self {
+        // Safety: we consumed the pad, and the pad table covers all `TeensyPin`s
+        unsafe { Self::steal(P::PIN) }.unwrap()
+    }
+
+    /// Create a `DynPin` from a Teensy pin number, without consuming
+    /// the pin
+    ///
+    /// Returns `None` if the pin number isn't available on the selected board.
+    /// The pin is configured as an input.
+    ///
+    /// # Safety
+    ///
+    /// The pad may be owned elsewhere. Prefer [`ErasedPins`](struct.ErasedPins.html)
+    /// to safely select pins by number.
+    pub unsafe fn steal(pin: u8) -> Option<Self> {
+        let pad = pad(pin)?;
+        let dyn_pin = DynPin {
+            pin,
+            pad,
+            fast: true,
+        };
+        cortex_m::interrupt::free(|_| {
+            ptr::write_volatile(pad.mux_ctl() as *mut u32, ALT5);
+            dyn_pin.modify(dyn_pin.gpr(), |gpr| gpr | dyn_pin.mask());
+            dyn_pin.modify(dyn_pin.register(GPIO_GDIR), |gdir| gdir & !dyn_pin.mask());
+        });
+        Some(dyn_pin)
+    }
+
+    /// Returns the Teensy pin number
+    pub fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    /// Returns the processor pad for this pin
+    pub fn pad(&self) -> Pad {
+        self.pad
+    }
+
+    /// Select the fast GPIO bank (`true`), or the normal GPIO bank (`false`)
+    ///
+    /// The pin keeps its direction and output value when switching banks.
+    pub fn set_fast(&mut self, fast: bool) {
+        if fast == self.fast {
+            return;
+        }
+        let is_output = self.is_output();
+        let is_set = self.is_set();
+        self.fast = fast;
+        if is_set {
+            self.set();
+        } else {
+            self.clear();
+        }
+        if is_output {
+            self.set_output();
+        } else {
+            self.set_input();
+        }
+        cortex_m::interrupt::free(|_| unsafe {
+            self.modify(self.gpr(), |gpr| {
+                if fast {
+                    gpr | self.mask()
+                } else {
+                    gpr & !self.mask()
+                }
+            })
+        });
+    }
+
+    /// Returns `true` if the pin uses the fast GPIO bank
+    pub fn is_fast(&self) -> bool {
+        self.fast
+    }
+
+    /// Configure the pin as an output
+    pub fn set_output(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            self.modify(self.register(GPIO_GDIR), |gdir| gdir | self.mask())
+        });
+    }
+
+    /// Configure the pin as an input
+    pub fn set_input(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            self.modify(self.register(GPIO_GDIR), |gdir| gdir & !self.mask())
+        });
+    }
+
+    /// Returns `true` if the pin is configured as an output
+    pub fn is_output(&self) -> bool {
+        unsafe { ptr::read_volatile(self.register(GPIO_GDIR)) & self.mask() != 0 }
+    }
+
+    /// Set the pin's pull-up, pull-down, or keeper
+    pub fn set_pull(&mut self, pull: Pull) {
+        let pad_ctl = self.pad.pad_ctl() as *mut u32;
+        unsafe {
+            self.modify(pad_ctl, |ctl| (ctl & !PAD_CTL_PULL_MASK) | pull.pad_ctl());
+        }
+    }
+
+    /// Enable or disable the input hysteresis
+    pub fn set_hysteresis(&mut self, hysteresis: bool) {
+        let pad_ctl = self.pad.pad_ctl() as *mut u32;
+        unsafe {
+            self.modify(pad_ctl, |ctl| {
+                if hysteresis {
+                    ctl | PAD_CTL_HYS
+                } else {
+                    ctl & !PAD_CTL_HYS
+                }
+            });
+        }
+    }
+
+    /// Drive the pin high
+    ///
+    /// This has no observable effect until the pin is an output.
+    pub fn set(&mut self) {
+        unsafe { ptr::write_volatile(self.register(GPIO_DR_SET), self.mask()) }
+    }
+
+    /// Drive the pin low
+    ///
+    /// This has no observable effect until the pin is an output.
+    pub fn clear(&mut self) {
+        unsafe { ptr::write_volatile(self.register(GPIO_DR_CLEAR), self.mask()) }
+    }
+
+    /// Toggle the pin's output
+    pub fn toggle(&mut self) {
+        unsafe { ptr::write_volatile(self.register(GPIO_DR_TOGGLE), self.mask()) }
+    }
+
+    /// Returns `true` if the pin is being driven high
+    pub fn is_set(&self) -> bool {
+        unsafe { ptr::read_volatile(self.register(GPIO_DR)) & self.mask() != 0 }
+    }
+
+    /// Returns `true` if the pin's input is high
+    pub fn is_high(&self) -> bool {
+        unsafe { ptr::read_volatile(self.register(GPIO_PSR)) & self.mask() != 0 }
+    }
+
+    pub(crate) fn mask(&self) -> u32 {
+        1 << self.pad.gpio().1
+    }
+
+    /// Returns the GPIO register at `offset`, in the selected GPIO bank
+    pub(crate) fn register(&self, offset: u32) -> *mut u32 {
+        let (port, _) = self.pad.gpio();
+        let base = if self.fast { FAST_GPIO_BASE } else { GPIO_BASE };
+        (base[port as usize - 1] + offset) as *mut u32
+    }
+
+    /// Returns the `IOMUXC_GPR` register that selects the fast GPIO for this pin
+    fn gpr(&self) -> *mut u32 {
+        let (port, _) = self.pad.gpio();
+        (IOMUXC_GPR_GPR26 + 4 * (port as u32 - 1)) as *mut u32
+    }
+
+    /// Read-modify-write a register. Callers must ensure that this is
+    /// not preempted if other pins share the register.
+    pub(crate) unsafe fn modify<F: FnOnce(u32) -> u32>(&self, register: *mut u32, f: F) {
+        ptr::write_volatile(register, f(ptr::read_volatile(register)));
+    }
+}
+
+impl embedded_hal::digital::v2::OutputPin for DynPin {
+    type Error = Infallible;
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set();
+        Ok(())
+    }
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.clear();
+        Ok(())
+    }
+}
+
+impl embedded_hal::digital::v2::StatefulOutputPin for DynPin {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_set())
+    }
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.is_set())
+    }
+}
+
+impl embedded_hal::digital::v2::ToggleableOutputPin for DynPin {
+    type Error = Infallible;
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        DynPin::toggle(self);
+        Ok(())
+    }
+}
+
+impl embedded_hal::digital::v2::InputPin for DynPin {
+    type Error = Infallible;
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(DynPin::is_high(self))
+    }
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!DynPin::is_high(self))
+    }
+}
+
+/// All of the board's pins, selectable by Teensy pin number
+///
+/// Create `ErasedPins` with [`Pins::erase`](../struct.Pins.html#method.erase).
+pub struct ErasedPins {
+    taken: [bool; NUM_PINS],
+}
+
+impl ErasedPins {
+    /// Take the pin numbered `pin` as a [`DynPin`](struct.DynPin.html)
+    ///
+    /// Returns `None` if the pin was already taken, or if the pin number
+    /// isn't available on the selected board. The pin is configured as
+    /// an input.
+    pub fn take(&mut self, pin: u8) -> Option<DynPin> {
+        let taken = self.taken.get_mut(pin as usize)?;
+        if *taken {
+            None
+        } else {
+            *taken = true;
+            // Safety: we own all the pads, and we only hand out each pin once
+            unsafe { DynPin::steal(pin) }
+        }
+    }
+
+    /// Returns a pin that was taken from this collection
+    ///
+    /// Once returned, the pin may be taken again.
+    pub fn release(&mut self, pin: DynPin) {
+        self.taken[pin.pin as usize] = false;
+    }
+}
+
+impl crate::Pins {
+    /// Consume all of the pins, and return a collection that
+    /// hands out pins by Teensy pin number
+    ///
+    /// See the [`gpio`](gpio/index.html) module for more information.
+    pub fn erase(self) -> ErasedPins {
+        ErasedPins {
+            taken: [false; NUM_PINS],
+        }
+    }
+}
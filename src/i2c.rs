@@ -0,0 +1,778 @@
+//! Shared I2C buses, and bus recovery
+//!
+//! An [`I2cBus`](struct.I2cBus.html) owns an I2C peripheral, and hands out
+//! [`I2cProxy`](struct.I2cProxy.html) handles. Each proxy implements the `embedded_hal`
+//! blocking I2C traits, so that more than one driver can use the bus:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::i2c::I2cBus;
+//! use embedded_hal::blocking::i2c::WriteRead;
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let i2c1 = board
+//!     .i2c
+//!     .p19_p18(
+//!         &mut board.ccm.handle,
+//!         board.pins.p19,
+//!         board.pins.p18,
+//!         bsp::hal::i2c::ClockSpeed::KHz400,
+//!     )
+//!     .unwrap();
+//! let bus = I2cBus::new(i2c1, board.clocks.arm_hz);
+//!
+//! let mut imu = bus.proxy();
+//! let mut pressure = bus.proxy();
+//! let mut who_am_i = [0];
+//! imu.write_read(0x68, &[0x75], &mut who_am_i).unwrap();
+//! let mut id = [0];
+//! pressure.write_read(0x77, &[0xD0], &mut id).unwrap();
+//! ```
+//!
+//! Like the [`spi`](../spi/index.html) module's buses, the bus lock is a critical section.
+//! Proxies on a `&'static I2cBus` may be used from interrupts and RTIC tasks.
+//!
+//! ## Bus recovery
+//!
+//! A device that's reset, or that misses clocks, may hold SDA low, and the controller can't
+//! start another transfer. [`recover`](struct.I2cBus.html#method.recover) detects a stuck
+//! SDA line. It switches the pins to GPIO, clocks SCL until the device releases SDA, and
+//! issues a STOP. It then returns the pins to I2C, and resets the LPI2C controller. A proxy
+//! runs the recovery after a transfer that loses arbitration, times out on a low pin, or
+//! leaves the bus busy, then returns the transfer's error. A NACK, like when probing for a
+//! missing device, ends with a STOP, and it doesn't run the recovery.
+//!
+//! ## Target mode
+//!
+//! An [`I2cTarget`](struct.I2cTarget.html) lets the Teensy act as an I2C target (slave)
+//! with a register map. It answers its 7-bit address, and optionally the general call
+//! address. The LPI2C stretches SCL while the interrupt handles each address and byte, so
+//! the controller waits for the register map. The [`target`](target/index.html) module,
+//! a re-export of the `teensy4-i2c-target` crate, holds the register-map state machine.
+//! It doesn't depend on hardware, so you can test your register map on the host.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::i2c::{target::Registers, I2cTarget};
+//!
+//! struct Map([u8; 16]);
+//!
+//! impl Registers for Map {
+//!     fn read(&mut self, register: u8) -> u8 {
+//!         self.0[register as usize % 16]
+//!     }
+//!     fn write(&mut self, register: u8, value: u8) {
+//!         self.0[register as usize % 16] = value;
+//!     }
+//! }
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let i2c1 = board
+//!     .i2c
+//!     .p19_p18(
+//!         &mut board.ccm.handle,
+//!         board.pins.p19,
+//!         board.pins.p18,
+//!         bsp::hal::i2c::ClockSpeed::KHz400,
+//!     )
+//!     .unwrap();
+//! let mut target = I2cTarget::new(i2c1, 0x42, false, Map([0; 16]));
+//! // Share target with the LPI2C1 interrupt, which calls target.on_interrupt()...
+//! unsafe { cortex_m::peripheral::NVIC::unmask(bsp::interrupt::LPI2C1) };
+//! ```
+//!
+//! ## Async transfers
+//!
+//! With the `"async-interrupts"` feature, the [`transfer`](transfer/index.html) module's
+//! `write`, `read`, and `write_read` return futures that run a transfer from the LPI2C's
+//! interrupt. The BSP registers the `LPI2C1`, `LPI2C3`, and `LPI2C4` handlers, so you
+//! can't also register a handler for an `I2cTarget`.
+//!
+//! ```no_run
+//! # async fn f(mut i2c1: teensy4_bsp::hal::i2c::I2C<teensy4_bsp::hal::i2c::module::_1>) {
+//! use teensy4_bsp as bsp;
+//!
+//! let mut who_am_i = [0];
+//! bsp::i2c::transfer::write_read(&mut i2c1, 0x68, &[0x75], &mut who_am_i)
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use crate::{
+    gpio::{self, DynPin},
+    hal, interrupt,
+};
+use core::{cell::RefCell, ptr};
+use cortex_m::interrupt::Mutex;
+use embedded_hal::blocking::i2c;
+
+pub use teensy4_i2c_target as target;
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::hal::i2c::module::_1 {}
+    impl Sealed for super::hal::i2c::module::_3 {}
+    impl Sealed for super::hal::i2c::module::_4 {}
+}
+
+/// An LPI2C module that's available on Teensy pins
+///
+/// Implemented on the HAL's I2C module types.
+pub trait Lpi2c: private::Sealed {
+    /// The LPI2C number
+    const NUMBER: u8;
+    /// The LPI2C's interrupt
+    const INTERRUPT: interrupt;
+    /// The Teensy pin used for SCL
+    const SCL: u8;
+    /// The Teensy pin used for SDA
+    const SDA: u8;
+}
+
+impl Lpi2c for hal::i2c::module::_1 {
+    const NUMBER: u8 = 1;
+    const INTERRUPT: interrupt = interrupt::LPI2C1;
+    const SCL: u8 = 19;
+    const SDA: u8 = 18;
+}
+
+impl Lpi2c for hal::i2c::module::_3 {
+    const NUMBER: u8 = 3;
+    const INTERRUPT: interrupt = interrupt::LPI2C3;
+    const SCL: u8 = 16;
+    const SDA: u8 = 17;
+}
+
+impl Lpi2c for hal::i2c::module::_4 {
+    const NUMBER: u8 = 4;
+    const INTERRUPT: interrupt = interrupt::LPI2C4;
+    const SCL: u8 = 24;
+    const SDA: u8 = 25;
+}
+
+const LPI2C_BASE: [u32; 4] = [0x403F_0000, 0x403F_4000, 0x403F_8000, 0x403F_C000];
+const MCR: u32 = 0x10;
+const MSR: u32 = 0x14;
+
+const MCR_MEN: u32 = 1 << 0;
+const MCR_RTF: u32 = 1 << 8;
+const MCR_RRF: u32 = 1 << 9;
+/// Write-1-to-clear controller flags
+const MSR_W1C: u32 = 0x7F << 8;
+const MSR_ALF: u32 = 1 << 11;
+const MSR_PLTF: u32 = 1 << 13;
+const MSR_BBF: u32 = 1 << 25;
+/// SCL half periods to wait for the bus to go idle after a failed transfer
+const IDLE_HALF_PERIODS: u32 = 4;
+
+const SCR: u32 = 0x110;
+const SSR: u32 = 0x114;
+const SIER: u32 = 0x118;
+const SCFGR1: u32 = 0x124;
+const SAMR: u32 = 0x140;
+const SASR: u32 = 0x150;
+const STDR: u32 = 0x160;
+const SRDR: u32 = 0x170;
+
+const SCR_SEN: u32 = 1 << 0;
+const SCR_RST: u32 = 1 << 1;
+const SCR_FILTEN: u32 = 1 << 4;
+const SCR_RTF: u32 = 1 << 8;
+const SCR_RRF: u32 = 1 << 9;
+
+const SSR_TDF: u32 = 1 << 0;
+const SSR_RDF: u32 = 1 << 1;
+const SSR_AVF: u32 = 1 << 2;
+const SSR_RSF: u32 = 1 << 8;
+const SSR_SDF: u32 = 1 << 9;
+const SSR_GCF: u32 = 1 << 14;
+/// Write-1-to-clear target flags: repeated start, stop, bit error, FIFO error
+const SSR_W1C: u32 = 0xF << 8;
+
+/// Address stall, receive stall, and transmit data stall
+const SCFGR1_STALL: u32 = 0b111;
+const SCFGR1_GCEN: u32 = 1 << 8;
+
+const SASR_READ: u32 = 1 << 0;
+
+/// Standard-mode SCL half period, in microseconds
+const RECOVERY_HALF_PERIOD_US: u32 = 5;
+/// A device releases SDA within nine clocks
+const RECOVERY_CLOCKS: u32 = 9;
+
+/// The outcome of a bus recovery
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recovery {
+    /// SDA wasn't stuck
+    Idle,
+    /// SDA was stuck, and the recovery released it
+    Recovered,
+    /// SDA is still stuck after the recovery. Check the wiring, or reset the device.
+    Stuck,
+}
+
+/// An I2C bus that's shared by drivers
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct I2cBus<M> {
+    i2c: Mutex<RefCell<hal::i2c::I2C<M>>>,
+    /// Core clock cycles in an SCL half period
+    half_period_cycles: u32,
+}
+
+impl<M: Lpi2c> I2cBus<M> {
+    /// Share an I2C peripheral
+    ///
+    /// `arm_hz` is the ARM core clock frequency, used to time recovery clocks.
+    pub fn new(i2c: hal::i2c::I2C<M>, arm_hz: u32) -> Self {
+        I2cBus {
+            i2c: Mutex::new(RefCell::new(i2c)),
+            half_period_cycles: arm_hz / 1_000_000 * RECOVERY_HALF_PERIOD_US,
+        }
+    }
+
+    /// Create a proxy that uses the bus
+    pub fn proxy(&self) -> I2cProxy<'_, M> {
+        I2cProxy { bus: self }
+    }
+
+    /// Release a stuck SDA line, and reset the LPI2C controller
+    ///
+    /// The controller is reset even if SDA wasn't stuck. Don't call this during a
+    /// transfer.
+    pub fn recover(&self) -> Recovery {
+        cortex_m::interrupt::free(|token| {
+            let _i2c = self.i2c.borrow(token).borrow_mut();
+            // Safety: we own the I2C peripheral, so we own its pads. The pads
+            // return to I2C before we release the lock.
+            unsafe {
+                let recovery = release_sda(M::SCL, M::SDA, self.half_period_cycles);
+                reset_controller(M::NUMBER);
+                recovery
+            }
+        })
+    }
+
+    /// Return the I2C peripheral
+    ///
+    /// Requires that all proxies were dropped, since they borrow the bus.
+    pub fn release(self) -> hal::i2c::I2C<M> {
+        self.i2c.into_inner().into_inner()
+    }
+
+    /// Run `f` with the I2C peripheral, and recover the bus if `f` fails and
+    /// the bus is stuck
+    fn with<R, E>(&self, f: impl FnOnce(&mut hal::i2c::I2C<M>) -> Result<R, E>) -> Result<R, E> {
+        let (result, stuck) = cortex_m::interrupt::free(|token| {
+            let result = f(&mut self.i2c.borrow(token).borrow_mut());
+            // Safety: we own the I2C peripheral, and we only read its status
+            let stuck = result.is_err() && unsafe { is_stuck(M::NUMBER, self.half_period_cycles) };
+            (result, stuck)
+        });
+        if stuck {
+            self.recover();
+        }
+        result
+    }
+}
+
+/// A handle to a shared I2C bus
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct I2cProxy<'a, M> {
+    bus: &'a I2cBus<M>,
+}
+
+impl<'a, M> i2c::Write for I2cProxy<'a, M>
+where
+    M: Lpi2c,
+    hal::i2c::I2C<M>: i2c::Write,
+{
+    type Error = <hal::i2c::I2C<M> as i2c::Write>::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.bus.with(|i2c| i2c.write(address, bytes))
+    }
+}
+
+impl<'a, M> i2c::Read for I2cProxy<'a, M>
+where
+    M: Lpi2c,
+    hal::i2c::I2C<M>: i2c::Read,
+{
+    type Error = <hal::i2c::I2C<M> as i2c::Read>::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.bus.with(|i2c| i2c.read(address, buffer))
+    }
+}
+
+impl<'a, M> i2c::WriteRead for I2cProxy<'a, M>
+where
+    M: Lpi2c,
+    hal::i2c::I2C<M>: i2c::WriteRead,
+{
+    type Error = <hal::i2c::I2C<M> as i2c::WriteRead>::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.bus.with(|i2c| i2c.write_read(address, bytes, buffer))
+    }
+}
+
+/// Returns `true` if a failed transfer left the bus stuck
+///
+/// Arbitration loss and pin low timeouts need a recovery. Otherwise, the bus should go
+/// idle, like after the STOP that follows a NACK. If it stays busy, a device is holding
+/// SDA low.
+///
+/// # Safety
+///
+/// Caller must own LPI2C `number`.
+unsafe fn is_stuck(number: u8, half_period_cycles: u32) -> bool {
+    if read(number, MSR) & (MSR_ALF | MSR_PLTF) != 0 {
+        return true;
+    }
+    for _ in 0..IDLE_HALF_PERIODS {
+        if read(number, MSR) & MSR_BBF == 0 {
+            return false;
+        }
+        cortex_m::asm::delay(half_period_cycles.max(1));
+    }
+    read(number, MSR) & MSR_BBF != 0
+}
+
+/// Clock SCL until SDA is released, then issue a STOP
+///
+/// # Safety
+///
+/// Caller must own the SCL and SDA pads. The pads are returned to their
+/// previous alternate functions.
+unsafe fn release_sda(scl: u8, sda: u8, half_period_cycles: u32) -> Recovery {
+    let delay = || cortex_m::asm::delay(half_period_cycles.max(1));
+    // The I2C mux selections, including SION, before steal() selects GPIO
+    let scl_mux = ptr::read_volatile(gpio::pad(scl).unwrap().mux_ctl() as *const u32);
+    let sda_mux = ptr::read_volatile(gpio::pad(sda).unwrap().mux_ctl() as *const u32);
+    let mut scl = DynPin::steal(scl).unwrap();
+    let mut sda = DynPin::steal(sda).unwrap();
+
+    // Inputs release the line, outputs drive it low. This works without
+    // the pads' open-drain setting.
+    let recovery = if sda.is_high() {
+        Recovery::Idle
+    } else {
+        for _ in 0..RECOVERY_CLOCKS {
+            scl.clear();
+            scl.set_output();
+            delay();
+            scl.set_input();
+            delay();
+            if sda.is_high() {
+                break;
+            }
+        }
+        // STOP: SDA rises while SCL is high
+        sda.clear();
+        sda.set_output();
+        delay();
+        sda.set_input();
+        delay();
+        if sda.is_high() {
+            Recovery::Recovered
+        } else {
+            Recovery::Stuck
+        }
+    };
+
+    ptr::write_volatile(scl.pad().mux_ctl() as *mut u32, scl_mux);
+    ptr::write_volatile(sda.pad().mux_ctl() as *mut u32, sda_mux);
+    recovery
+}
+
+/// Reset the LPI2C controller's state and FIFOs, keeping its configuration
+///
+/// # Safety
+///
+/// Caller must own LPI2C `number`.
+unsafe fn reset_controller(number: u8) {
+    let enabled = read(number, MCR) & MCR_MEN;
+    modify(number, MCR, |mcr| mcr & !MCR_MEN);
+    modify(number, MCR, |mcr| mcr | MCR_RTF | MCR_RRF);
+    write(number, MSR, MSR_W1C);
+    modify(number, MCR, |mcr| mcr | enabled);
+}
+
+/// An I2C target (slave), with a register map
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct I2cTarget<M, R> {
+    i2c: hal::i2c::I2C<M>,
+    target: target::Target<R>,
+}
+
+impl<M: Lpi2c, R: target::Registers> I2cTarget<M, R> {
+    /// The LPI2C's interrupt
+    pub const INTERRUPT: interrupt = M::INTERRUPT;
+
+    /// Turn an I2C controller into a target that answers the 7-bit `address`
+    ///
+    /// If `general_call` is `true`, the target also receives general call writes.
+    /// Enables the target's interrupts. You're responsible for unmasking the interrupt
+    /// in the NVIC, and for calling [`on_interrupt`](#method.on_interrupt).
+    pub fn new(i2c: hal::i2c::I2C<M>, address: u8, general_call: bool, registers: R) -> Self {
+        let number = M::NUMBER;
+        cortex_m::interrupt::free(|_| unsafe {
+            // Controller off, so it doesn't drive the bus
+            modify(number, MCR, |mcr| mcr & !MCR_MEN);
+            write(number, SCR, SCR_RST);
+            write(number, SCR, 0);
+            let gcen = if general_call { SCFGR1_GCEN } else { 0 };
+            write(number, SCFGR1, SCFGR1_STALL | gcen);
+            write(number, SAMR, ((address & 0x7F) as u32) << 1);
+            write(number, SSR, SSR_W1C);
+            write(
+                number,
+                SIER,
+                SSR_TDF | SSR_RDF | SSR_AVF | SSR_SDF | SSR_RSF,
+            );
+            write(number, SCR, SCR_SEN | SCR_FILTEN | SCR_RTF | SCR_RRF);
+        });
+        I2cTarget {
+            i2c,
+            target: target::Target::new(registers),
+        }
+    }
+
+    /// Handle the target's bus events
+    ///
+    /// Call `on_interrupt` from the LPI2C's interrupt handler. The register map's
+    /// callbacks run in the interrupt, while the controller waits.
+    pub fn on_interrupt(&mut self) {
+        use target::Event;
+        let number = M::NUMBER;
+        unsafe {
+            let ssr = read(number, SSR);
+            write(number, SSR, ssr & SSR_W1C);
+            if ssr & SSR_RDF != 0 {
+                let data = read(number, SRDR) as u8;
+                self.target.on_event(Event::Received(data));
+            }
+            if ssr & (SSR_SDF | SSR_RSF) != 0 {
+                self.target.on_event(Event::Stop);
+            }
+            if ssr & SSR_AVF != 0 {
+                // Reading the address releases the address stall
+                let sasr = read(number, SASR);
+                self.target.on_event(Event::Address {
+                    read: sasr & SASR_READ != 0,
+                    general_call: ssr & SSR_GCF != 0,
+                });
+            }
+            if ssr & SSR_TDF != 0 && self.target.is_addressed() {
+                // TDF only sets while the controller reads. If the target wasn't
+                // addressed for a read, send an idle byte so the bus doesn't stall.
+                let byte = self.target.on_event(Event::TransmitRequest).unwrap_or(0xFF);
+                write(number, STDR, byte as u32);
+            }
+        }
+    }
+
+    /// Access the register map
+    pub fn registers(&self) -> &R {
+        self.target.registers()
+    }
+
+    /// Mutably access the register map
+    ///
+    /// If the register map is shared with the interrupt, do this in a critical section.
+    pub fn registers_mut(&mut self) -> &mut R {
+        self.target.registers_mut()
+    }
+
+    /// Disable the target, and return the I2C controller and the register map
+    ///
+    /// The controller is re-enabled.
+    pub fn release(self) -> (hal::i2c::I2C<M>, R) {
+        let number = M::NUMBER;
+        cortex_m::interrupt::free(|_| unsafe {
+            write(number, SIER, 0);
+            write(number, SCR, 0);
+            modify(number, MCR, |mcr| mcr | MCR_MEN);
+        });
+        (self.i2c, self.target.release())
+    }
+}
+
+fn register(number: u8, offset: u32) -> *mut u32 {
+    (LPI2C_BASE[number as usize - 1] + offset) as *mut u32
+}
+
+unsafe fn read(number: u8, offset: u32) -> u32 {
+    ptr::read_volatile(register(number, offset))
+}
+
+unsafe fn write(number: u8, offset: u32, value: u32) {
+    ptr::write_volatile(register(number, offset), value)
+}
+
+unsafe fn modify(number: u8, offset: u32, f: impl FnOnce(u32) -> u32) {
+    write(number, offset, f(read(number, offset)))
+}
+
+/// I2C controller futures, woken by the BSP's `LPI2Cn` interrupt handlers
+///
+/// See the [module-level documentation](../index.html#async-transfers) for more information.
+#[cfg(feature = "async-interrupts")]
+pub mod transfer {
+    use super::{
+        hal, modify, read as read_register, write as write_register, Lpi2c, MCR, MCR_RRF, MCR_RTF,
+        MSR, MSR_ALF, MSR_PLTF, MSR_W1C,
+    };
+    use crate::executor::WakerSet;
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    const MPARAM: u32 = 0x04;
+    const MIER: u32 = 0x18;
+    const MFCR: u32 = 0x58;
+    const MFSR: u32 = 0x5C;
+    const MTDR: u32 = 0x60;
+    const MRDR: u32 = 0x70;
+
+    const MSR_TDF: u32 = 1 << 0;
+    const MSR_RDF: u32 = 1 << 1;
+    const MSR_SDF: u32 = 1 << 9;
+    const MSR_NDF: u32 = 1 << 10;
+    const MSR_FEF: u32 = 1 << 12;
+    const MSR_ERRORS: u32 = MSR_NDF | MSR_ALF | MSR_FEF | MSR_PLTF;
+
+    const MPARAM_MTXFIFO_MASK: u32 = 0xF;
+    const MFSR_TXCOUNT_MASK: u32 = 0x7;
+    const MRDR_RXEMPTY: u32 = 1 << 14;
+
+    const CMD_TRANSMIT: u32 = 0b000 << 8;
+    const CMD_RECEIVE: u32 = 0b001 << 8;
+    const CMD_STOP: u32 = 0b010 << 8;
+    const CMD_START: u32 = 0b100 << 8;
+    /// The most bytes that one receive command reads
+    const RECEIVE_MAX: usize = 256;
+
+    static WAKERS: WakerSet = WakerSet::new();
+
+    /// An async I2C transfer error
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Error {
+        /// The device didn't acknowledge its address, or a byte
+        Nack,
+        /// Another controller won the bus
+        ArbitrationLost,
+        /// SCL or SDA stayed low for too long
+        PinLowTimeout,
+        /// The controller saw a command without a START
+        Fifo,
+    }
+
+    impl Error {
+        fn from_msr(msr: u32) -> Self {
+            if msr & MSR_NDF != 0 {
+                Error::Nack
+            } else if msr & MSR_ALF != 0 {
+                Error::ArbitrationLost
+            } else if msr & MSR_PLTF != 0 {
+                Error::PinLowTimeout
+            } else {
+                Error::Fifo
+            }
+        }
+    }
+
+    /// Write `bytes` to the device at the 7-bit `address`
+    pub fn write<'a, M: Lpi2c>(
+        i2c: &'a mut hal::i2c::I2C<M>,
+        address: u8,
+        bytes: &'a [u8],
+    ) -> Transfer<'a, M> {
+        Transfer::new(i2c, address, bytes, &mut [])
+    }
+
+    /// Read into `buffer` from the device at the 7-bit `address`
+    pub fn read<'a, M: Lpi2c>(
+        i2c: &'a mut hal::i2c::I2C<M>,
+        address: u8,
+        buffer: &'a mut [u8],
+    ) -> Transfer<'a, M> {
+        Transfer::new(i2c, address, &[], buffer)
+    }
+
+    /// Write `bytes` to the device at the 7-bit `address`, then read into `buffer` after a
+    /// repeated START
+    pub fn write_read<'a, M: Lpi2c>(
+        i2c: &'a mut hal::i2c::I2C<M>,
+        address: u8,
+        bytes: &'a [u8],
+        buffer: &'a mut [u8],
+    ) -> Transfer<'a, M> {
+        Transfer::new(i2c, address, bytes, buffer)
+    }
+
+    /// A future that runs an I2C transfer
+    ///
+    /// Use [`write`](fn.write.html), [`read`](fn.read.html), or
+    /// [`write_read`](fn.write_read.html) to create a `Transfer`. The transfer starts when
+    /// the future is first polled. Dropping the future before it completes leaves the
+    /// controller mid-transfer.
+    pub struct Transfer<'a, M> {
+        _i2c: &'a mut hal::i2c::I2C<M>,
+        address: u8,
+        bytes: &'a [u8],
+        buffer: &'a mut [u8],
+        /// Commands written to the transmit FIFO
+        queued: usize,
+        /// Bytes read from the receive FIFO
+        received: usize,
+    }
+
+    impl<'a, M: Lpi2c> Transfer<'a, M> {
+        fn new(
+            i2c: &'a mut hal::i2c::I2C<M>,
+            address: u8,
+            bytes: &'a [u8],
+            buffer: &'a mut [u8],
+        ) -> Self {
+            Transfer {
+                _i2c: i2c,
+                address,
+                bytes,
+                buffer,
+                queued: 0,
+                received: 0,
+            }
+        }
+
+        /// Returns the controller command at `index`, or `None` after the STOP
+        fn command(&self, mut index: usize) -> Option<u32> {
+            let address = ((self.address & 0x7F) as u32) << 1;
+            if !self.bytes.is_empty() || self.buffer.is_empty() {
+                if index == 0 {
+                    return Some(CMD_START | address);
+                }
+                index -= 1;
+                if let Some(&byte) = self.bytes.get(index) {
+                    return Some(CMD_TRANSMIT | byte as u32);
+                }
+                index -= self.bytes.len();
+            }
+            if !self.buffer.is_empty() {
+                if index == 0 {
+                    return Some(CMD_START | address | 1);
+                }
+                index -= 1;
+                let mut receives = self.buffer.chunks(RECEIVE_MAX);
+                let count = receives.len();
+                if let Some(receive) = receives.nth(index) {
+                    return Some(CMD_RECEIVE | (receive.len() - 1) as u32);
+                }
+                index -= count;
+            }
+            if index == 0 {
+                Some(CMD_STOP)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl<'a, M: Lpi2c> Future for Transfer<'a, M> {
+        type Output = Result<(), Error>;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = self.get_mut();
+            let number = M::NUMBER;
+            WAKERS.register(cx.waker());
+            // Safety: we borrow the I2C controller for the whole transfer
+            unsafe {
+                if this.queued == 0 {
+                    // Both watermarks at zero: TDF when the transmit FIFO is empty, and RDF
+                    // for each received byte
+                    write_register(number, MFCR, 0);
+                    write_register(number, MSR, MSR_W1C);
+                }
+
+                let msr = read_register(number, MSR);
+                if msr & MSR_ERRORS != 0 {
+                    write_register(number, MIER, 0);
+                    modify(number, MCR, |mcr| mcr | MCR_RTF | MCR_RRF);
+                    write_register(number, MSR, MSR_W1C);
+                    return Poll::Ready(Err(Error::from_msr(msr)));
+                }
+
+                while this.received < this.buffer.len() {
+                    let data = read_register(number, MRDR);
+                    if data & MRDR_RXEMPTY != 0 {
+                        break;
+                    }
+                    this.buffer[this.received] = data as u8;
+                    this.received += 1;
+                }
+
+                let fifo_size = 1 << (read_register(number, MPARAM) & MPARAM_MTXFIFO_MASK);
+                while let Some(command) = this.command(this.queued) {
+                    if read_register(number, MFSR) & MFSR_TXCOUNT_MASK >= fifo_size {
+                        break;
+                    }
+                    write_register(number, MTDR, command);
+                    this.queued += 1;
+                }
+
+                let sent = this.command(this.queued).is_none();
+                let received = this.received == this.buffer.len();
+                if sent && received && msr & MSR_SDF != 0 {
+                    write_register(number, MIER, 0);
+                    write_register(number, MSR, MSR_SDF);
+                    return Poll::Ready(Ok(()));
+                }
+
+                // Only interrupt on the FIFOs while there's work for them, so that an
+                // empty transmit FIFO doesn't keep interrupting
+                let mut mier = MSR_ERRORS | MSR_SDF;
+                if !sent {
+                    mier |= MSR_TDF;
+                }
+                if !received {
+                    mier |= MSR_RDF;
+                }
+                write_register(number, MIER, mier);
+                cortex_m::peripheral::NVIC::unmask(M::INTERRUPT);
+            }
+            Poll::Pending
+        }
+    }
+
+    /// Mask the interrupts of LPI2C `number`, and leave its flags for the future
+    fn on_interrupt(number: u8) {
+        unsafe { write_register(number, MIER, 0) };
+        WAKERS.wake();
+    }
+
+    #[crate::rt::interrupt]
+    fn LPI2C1() {
+        on_interrupt(1);
+    }
+
+    #[crate::rt::interrupt]
+    fn LPI2C3() {
+        on_interrupt(3);
+    }
+
+    #[crate::rt::interrupt]
+    fn LPI2C4() {
+        on_interrupt(4);
+    }
+}
@@ -18,6 +18,18 @@
 //!   peripheral for logging. Enabled with the `"usb-logging"` feature,
 //!   which is on by default. Depends on the `"systick"` feature.
 //!
+//! Other features register interrupt handlers, but they're off by default:
+//!
+//! - `"pin-interrupts"` registers the `GPIOn_Combined_x_y` interrupts for GPIO1
+//!   through GPIO4. See the [`pin_interrupt`](pin_interrupt/index.html) module.
+//! - `"serial-interrupts"` registers the `LPUARTn` interrupts, and services the buffered
+//!   serial ports. See the [`serial`](serial/index.html) module.
+//! - `"async-interrupts"` registers the `PIT`, `GPT1`, `GPT2`, `DMAn_DMAm`, and `LPI2Cn`
+//!   interrupts, and wakes async tasks that wait on timers, DMA transfers, and I2C
+//!   transfers. See the [`executor`](executor/index.html) module.
+//! - `"rtic"` registers the `GPT2` interrupt for the RTIC monotonic timer. See the
+//!   [`monotonic`](monotonic/index.html) module.
+//!
 //! These peripherals and capabilities are not exported from the BSP.
 //! If a user also registers a `SysTick` or `USB_OTG1` handler, it may
 //! result in a duplicate definition error.
@@ -28,6 +40,8 @@
 //!
 //! - the `teensy4-rt` crate, as `rt`
 //! - the `imxrt-hal` crate, as `hal`
+//! - the `teensy4-lin` crate, as `lin`
+//! - the `teensy4-pads` crate's `Pad` and `Group`, in `gpio`
 //!
 //! See the accompanying documentation of each crate for more
 //! information.
@@ -40,16 +54,44 @@
 //! specific to the Teensy 4. See the `teensy4-fcb` crate for details
 //! on FCBs.
 //!
+//! ## Boards
+//!
+//! By default, the BSP targets the Teensy 4.0. Enable the `"teensy41"` feature to target
+//! the Teensy 4.1, or the `"micromod"` feature to target the Teensy MicroMod. Select at
+//! most one board feature. The feature selects
+//!
+//! - the [`Pins`](struct.Pins.html) for the selected board. See the `t40`, `t41`, and
+//!   `micromod` modules for each board's pins.
+//! - the board's flash size in the FCB.
+//! - the board's memory map in the runtime's linker script.
+//!
+//! Use the [`clocks`](clocks/index.html) module to select the core clock frequency.
+//! [`Board::init`](struct.Board.html#method.init) sets the clock, starts USB logging, and
+//! prepares the LED and serial peripherals in one call. See the [`setup`](setup/index.html)
+//! module. The [`serial`](serial/index.html) module provides buffered serial ports, named
+//! like Teensyduino's `Serial1` through `Serial8`, and the [`spi`](spi/index.html) module
+//! shares an SPI bus among devices with their own chip selects. The [`i2c`](i2c/index.html)
+//! module shares I2C buses, and recovers a bus that's held by a stuck device. The
+//! [`dma`](dma/index.html) module allocates DMA buffers in the `.dmabuffers` region.
+//! Write firmware as `async` tasks with the [`executor`](executor/index.html) module.
+//!
+//! Use the [`gpio`](gpio/index.html) module to select pins by their Teensy pin number, and
+//! the [`adc`](adc/index.html) module to use the board's analog inputs. The
+//! [`pwm`](pwm/index.html) module drives PWM outputs by Teensy pin, and the
+//! [`servo`](servo/index.html) module drives hobby servos and ESCs.
+//! Enable the `"arduino"` feature for the [`arduino`](arduino/index.html) module, which
+//! provides Teensyduino-style digital and analog I/O by Teensy pin number.
+//!
 //! ## Physical Pins to Pads and Alternative Functions
 //!
-//! The sparse table below describes the Teensy 4 pins, the pad ID, and some of the notable alternative functionalities
+//! The sparse table below describes the Teensy 4.0 pins, the pad ID, and some of the notable alternative functionalities
 //! for each pin. We add entries to the table as we add capabilities to the underlying HAL crate. Contributions to complete
 //! this table are welcome! If a pad's alternatives are not listed here, consult the iMXRT1060 reference manual.
 //!
 //! | Pin  | Pad ID   |  Alt0    |  Alt1        |  Alt2        |  Alt3     |  Alt4        |  Alt5            |  Alt6        |  Alt7   |  Alt8   |  Alt9   |
 //! | ---- | -------- | -------- | ------------ | ------------ | --------- | ------------ | ---------------- | ------------ | ------- | ------- | ------- |
-//! |  0   |`AD_B0_03`|          |              |  `UART6_RX`  |           |`FlexPWM1_1_X`|                  |              |         |         |         |
-//! |  1   |`AD_B0_02`|          |              |  `UART6_TX`  |           |`FlexPWM1_0_X`|                  |              |         |         |         |
+//! |  0   |`AD_B0_03`|          |              |  `UART6_RX`  |           |`FlexPWM1_1_X`|                  |              |`SPI3_PCS0`|         |         |
+//! |  1   |`AD_B0_02`|          |              |  `UART6_TX`  |           |`FlexPWM1_0_X`|                  |              |`SPI3_SDI` |         |         |
 //! |  2   |`EMC_04`  |          |`FlexPWM4_2_A`|              |           |              |                  |              |         |         |         |
 //! |  3   |`EMC_05`  |          |`FlexPWM4_2_B`|              |           |              |                  |              |         |         |         |
 //! |  4   |`EMC_06`  |          |`FlexPWM2_0_A`|              |           |              |                  |              |         |         |         |
@@ -74,12 +116,12 @@
 //! |  23  |`AD_B1_09`|          |`FlexPWM4_1_A`|              |           |              |                  |              |         |         |         |
 //! |  24  |`AD_B0_12`|`I2C4_SCL`|              |  `UART1_TX`  |           |`FlexPWM1_2_X`|                  |              |         |         |         |
 //! |  25  |`AD_B0_13`|`I2C4_SDA`|              |  `UART1_RX`  |           |`FlexPWM1_3_X`|                  |              |         |         |         |
-//! |  26  |`AD_B1_14`|          |              |              |           |              |                  |              |         |         |         |
-//! |  27  |`AD_B1_15`|          |              |              |           |              |                  |              |         |         |         |
+//! |  26  |`AD_B1_14`|          |              |  `SPI3_SDO`  |           |              |                  |              |         |         |         |
+//! |  27  |`AD_B1_15`|          |              |  `SPI3_SCK`  |           |              |                  |              |         |         |         |
 //! |  28  |`EMC_32`  |          |`FlexPWM3_1_B`|  `UART7_RX`  |           |              |                  |              |         |         |         |
 //! |  29  |`EMC_31`  |          |`FlexPWM3_1_A`|  `UART7_TX`  |`SPI1_PCS1`|              |                  |              |         |         |         |
-//! |  30  |`EMC_37`  |          |              |              |           |              |                  |              |         |         |         |
-//! |  31  |`EMC_36`  |          |              |              |           |              |                  |              |         |         |         |
+//! |  30  |`EMC_37`  |          |              |              |           |              |                  |              |         |         |`CAN3_RX`|
+//! |  31  |`EMC_36`  |          |              |              |           |              |                  |              |         |         |`CAN3_TX`|
 //! |  32  |`B0_12`   |          |              |              |           |              |                  |              |         |         |         |
 //! |  33  |`EMC_07`  |          |`FlexPWM2_0_B`|              |           |              |                  |              |         |         |         |
 //! |  34  |`SD_B0_03`|          |`FlexPWM1_1_B`|              |           |  `SPI1_SDI`  |                  |              |         |         |         |
@@ -89,6 +131,9 @@
 //! |  38  |`SD_B0_05`|          |`FlexPWM1_2_B`|  `UART8_RX`  |           |              |                  |              |         |         |         |
 //! |  39  |`SD_B0_04`|          |`FlexPWM1_2_A`|  `UART8_TX`  |           |              |                  |              |         |         |         |
 //!
+//! The Teensy 4.1 and Teensy MicroMod share pins 0 through 33 with the Teensy 4.0. See
+//! the `t41` and `micromod` modules for each board's remaining pins.
+//!
 //! References:
 //! - [Teensy 4.0 Schematic Diagram](https://www.pjrc.com/teensy/schematic.html)
 //!
@@ -110,13 +155,62 @@
 // Need to reference this so that it doesn't get stripped out
 extern crate teensy4_fcb;
 
+pub mod adc;
+#[cfg(feature = "arduino")]
+pub mod arduino;
+pub mod clocks;
+#[cfg(feature = "rtic")]
+pub mod device;
+pub mod dma;
+pub mod executor;
+pub mod flexpwm;
+#[macro_use]
+pub mod gpio;
+pub mod i2c;
 #[cfg(feature = "systick")]
 mod systick;
+#[cfg(feature = "micromod")]
+#[macro_use]
+pub mod micromod;
+#[cfg(feature = "rtic")]
+pub mod monotonic;
+#[cfg(not(any(feature = "teensy41", feature = "micromod")))]
+#[macro_use]
+pub mod t40;
+#[cfg(feature = "pin-interrupts")]
+pub mod pin_interrupt;
+pub mod pwm;
+pub mod serial;
+pub mod servo;
+pub mod setup;
+pub mod spi;
+#[cfg(feature = "teensy41")]
+#[macro_use]
+pub mod t41;
 #[cfg(feature = "usb-logging")]
 pub mod usb;
 
+#[cfg(feature = "micromod")]
+pub use micromod::{BoardPins, Pins};
+pub use setup::{Board, BoardConfig};
 #[cfg(feature = "systick")]
 pub use systick::SysTick;
+#[cfg(not(any(feature = "teensy41", feature = "micromod")))]
+pub use t40::{BoardPins, Pins};
+#[cfg(feature = "teensy41")]
+pub use t41::{BoardPins, EthernetPins, Pins};
+
+#[cfg(feature = "micromod")]
+use micromod as board;
+#[cfg(not(any(feature = "teensy41", feature = "micromod")))]
+use t40 as board;
+#[cfg(feature = "teensy41")]
+use t41 as board;
+
+#[cfg(all(feature = "teensy41", feature = "micromod"))]
+compile_error!(
+    "The 'teensy41' and 'micromod' features are mutually exclusive. Select at most one board."
+);
 
 pub use hal::ral::interrupt;
 // `rtic` expects these in the root.
@@ -125,84 +219,18 @@ pub use hal::ral::{interrupt as Interrupt, NVIC_PRIO_BITS};
 
 pub use cortex_m_rt as rt;
 pub use imxrt_hal as hal;
+pub use teensy4_lin as lin;
 
 /// The LED in its final configuration
 pub type LED = hal::gpio::GPIO2IO03<hal::gpio::GPIO7, hal::gpio::Output>;
 
-/// Teensy pins that do not yet have a function
-///
-/// Pin 13 can be used for several things; one common usage is for the on-board LED.
-pub struct Pins {
-    /// Pin 0
-    pub p0: hal::iomuxc::gpio::GPIO_AD_B0_03<hal::iomuxc::Alt5>,
-    /// Pin 1
-    pub p1: hal::iomuxc::gpio::GPIO_AD_B0_02<hal::iomuxc::Alt5>,
-    /// Pin 2
-    pub p2: hal::iomuxc::gpio::GPIO_EMC_04<hal::iomuxc::Alt5>,
-    /// Pin 3
-    pub p3: hal::iomuxc::gpio::GPIO_EMC_05<hal::iomuxc::Alt5>,
-    /// Pin 4
-    pub p4: hal::iomuxc::gpio::GPIO_EMC_06<hal::iomuxc::Alt5>,
-    /// Pin 5
-    pub p5: hal::iomuxc::gpio::GPIO_EMC_08<hal::iomuxc::Alt5>,
-    /// Pin 6
-    pub p6: hal::iomuxc::gpio::GPIO_B0_10<hal::iomuxc::Alt5>,
-    /// Pin 7
-    pub p7: hal::iomuxc::gpio::GPIO_B1_01<hal::iomuxc::Alt5>,
-    /// Pin 8
-    pub p8: hal::iomuxc::gpio::GPIO_B1_00<hal::iomuxc::Alt5>,
-    /// Pin 9
-    pub p9: hal::iomuxc::gpio::GPIO_B0_11<hal::iomuxc::Alt5>,
-    /// Pin 10
-    pub p10: hal::iomuxc::gpio::GPIO_B0_00<hal::iomuxc::Alt5>,
-    /// Pin 11
-    pub p11: hal::iomuxc::gpio::GPIO_B0_02<hal::iomuxc::Alt5>,
-    /// Pin 12
-    pub p12: hal::iomuxc::gpio::GPIO_B0_01<hal::iomuxc::Alt5>,
-    /// Pin 13
-    pub p13: hal::iomuxc::gpio::GPIO_B0_03<hal::iomuxc::Alt5>,
-    /// Pin 14
-    pub p14: hal::iomuxc::gpio::GPIO_AD_B1_02<hal::iomuxc::Alt5>,
-    /// Pin 15
-    pub p15: hal::iomuxc::gpio::GPIO_AD_B1_03<hal::iomuxc::Alt5>,
-    /// Pin 16
-    pub p16: hal::iomuxc::gpio::GPIO_AD_B1_07<hal::iomuxc::Alt5>,
-    /// Pin 17
-    pub p17: hal::iomuxc::gpio::GPIO_AD_B1_06<hal::iomuxc::Alt5>,
-    /// Pin 18
-    pub p18: hal::iomuxc::gpio::GPIO_AD_B1_01<hal::iomuxc::Alt5>,
-    /// Pin 19
-    pub p19: hal::iomuxc::gpio::GPIO_AD_B1_00<hal::iomuxc::Alt5>,
-    /// Pin 20
-    pub p20: hal::iomuxc::gpio::GPIO_AD_B1_10<hal::iomuxc::Alt5>,
-    /// Pin 21
-    pub p21: hal::iomuxc::gpio::GPIO_AD_B1_11<hal::iomuxc::Alt5>,
-    /// Pin 22
-    pub p22: hal::iomuxc::gpio::GPIO_AD_B1_08<hal::iomuxc::Alt5>,
-    /// Pin 23
-    pub p23: hal::iomuxc::gpio::GPIO_AD_B1_09<hal::iomuxc::Alt5>,
-    /// Pin 24
-    pub p24: hal::iomuxc::gpio::GPIO_AD_B0_12<hal::iomuxc::Alt5>,
-    /// Pin 25
-    pub p25: hal::iomuxc::gpio::GPIO_AD_B0_13<hal::iomuxc::Alt5>,
-    /// Pin 28
-    pub p28: hal::iomuxc::gpio::GPIO_EMC_32<hal::iomuxc::Alt5>,
-    /// Pin 29
-    pub p29: hal::iomuxc::gpio::GPIO_EMC_31<hal::iomuxc::Alt5>,
-    /// Pin 33
-    pub p33: hal::iomuxc::gpio::GPIO_EMC_07<hal::iomuxc::Alt5>,
-    /// Pin 36
-    pub p36: hal::iomuxc::gpio::GPIO_SD_B0_01<hal::iomuxc::Alt5>,
-    /// Pin 37
-    pub p37: hal::iomuxc::gpio::GPIO_SD_B0_00<hal::iomuxc::Alt5>,
-}
-
 /// All peripherals available on the Teensy4
 ///
 /// Nearly all of these are re-exports from the HAL. Exclusions include
 ///
 /// - `usb`, which is a USB logger
 /// - `pins`, which are the Teensy 4's available pins
+/// - `adc1` and `adc2`, which are the BSP's ADCs
 ///
 /// See the [module-level documentation](index.html) for more information.
 #[non_exhaustive]
@@ -224,8 +252,15 @@ pub struct Peripherals {
     pub pwm3: hal::pwm::Unclocked<hal::pwm::module::_3>,
     /// PWM4 controller
     pub pwm4: hal::pwm::Unclocked<hal::pwm::module::_4>,
+    /// ADC1
+    pub adc1: adc::Unclocked<adc::ADC1>,
+    /// ADC2
+    pub adc2: adc::Unclocked<adc::ADC2>,
     /// Teensy pins
     pub pins: Pins,
+    /// Teensy 4.1 Ethernet PHY pads
+    #[cfg(feature = "teensy41")]
+    pub enet_pins: EthernetPins,
     /// Unclocked I2C peripherals
     pub i2c: hal::i2c::Unclocked,
     /// Unclocked SPI peripherals
@@ -237,6 +272,9 @@ pub struct Peripherals {
     /// General purpose timer 1
     pub gpt1: hal::gpt::Unclocked,
     /// General purpose timer 2
+    ///
+    /// With the `"rtic"` feature, GPT2 is the [`monotonic`](monotonic/index.html) timer.
+    #[cfg(not(feature = "rtic"))]
     pub gpt2: hal::gpt::Unclocked,
     /// DMA channels
     pub dma: hal::dma::Unclocked,
@@ -250,13 +288,16 @@ pub struct Peripherals {
 /// See Section 12.3.2.1 of the reference manual. The note
 /// explains that the 24MHz clock is divided down to 100KHz
 /// before reaching SYSTICK.
+#[cfg(not(feature = "rtic"))]
 const SYSTICK_EXT_FREQ: u32 = 100_000;
 
 impl Peripherals {
     /// Instantiate the system peripherals. This may only be called once!
     pub fn take() -> Option<Self> {
         let p = hal::Peripherals::take()?;
+        #[cfg_attr(feature = "rtic", allow(unused_mut, unused_variables))]
         let mut cp = cortex_m::Peripherals::take()?;
+        #[cfg(not(feature = "rtic"))]
         Self::set_systick(&mut cp.SYST);
         Some(Peripherals::new(p))
     }
@@ -267,12 +308,35 @@ impl Peripherals {
     /// # Safety
     ///
     /// NOTE: This constructor is only intended for use with the `rtic` crate. This is **not** an
-    /// alternative to the `take` constructor. The `take` constructor sets the system timer
-    /// interrupt while this constructor does not seeing as `rtic` will take care of this for us.
+    /// alternative to the `take` constructor. Prefer [`from_rtic`](#method.from_rtic), which
+    /// doesn't need `unsafe`.
     pub unsafe fn steal() -> Self {
         Self::new(hal::Peripherals::steal())
     }
 
+    /// Create the BSP's peripherals from the HAL peripherals that `rtic` passes to `init`
+    ///
+    /// Select the [`device`](device/index.html) module as the `rtic` device:
+    ///
+    /// ```ignore
+    /// #[rtic::app(device = teensy4_bsp::device, peripherals = true)]
+    /// const APP: () = {
+    ///     #[init]
+    ///     fn init(cx: init::Context) {
+    ///         let mut peripherals = teensy4_bsp::Peripherals::from_rtic(cx.device);
+    ///         // ...
+    ///     }
+    /// };
+    /// ```
+    ///
+    /// Like `steal`, `from_rtic` leaves SysTick to `rtic`. The BSP's millisecond counter,
+    /// used for delays and USB logging, advances with the GPT2 monotonic timer.
+    #[cfg(feature = "rtic")]
+    pub fn from_rtic(device: hal::Peripherals) -> Self {
+        Self::new(device)
+    }
+
+    #[cfg(not(feature = "rtic"))]
     fn set_systick(systick: &mut cortex_m::peripheral::SYST) {
         systick.disable_counter();
         systick.set_clock_source(cortex_m::peripheral::syst::SystClkSource::External);
@@ -283,6 +347,8 @@ impl Peripherals {
     }
 
     fn new(p: hal::Peripherals) -> Peripherals {
+        #[cfg(feature = "rtic")]
+        monotonic::Gpt2::init(p.gpt2);
         Peripherals {
             ccm: p.ccm,
             pit: p.pit,
@@ -293,44 +359,17 @@ impl Peripherals {
             pwm2: p.pwm2,
             pwm3: p.pwm3,
             pwm4: p.pwm4,
-            pins: Pins {
-                p0: p.iomuxc.gpio_ad_b0_03,
-                p1: p.iomuxc.gpio_ad_b0_02,
-                p2: p.iomuxc.gpio_emc_04,
-                p3: p.iomuxc.gpio_emc_05,
-                p4: p.iomuxc.gpio_emc_06,
-                p5: p.iomuxc.gpio_emc_08,
-                p6: p.iomuxc.gpio_b0_10,
-                p7: p.iomuxc.gpio_b1_01,
-                p8: p.iomuxc.gpio_b1_00,
-                p9: p.iomuxc.gpio_b0_11,
-                p10: p.iomuxc.gpio_b0_00,
-                p11: p.iomuxc.gpio_b0_02,
-                p12: p.iomuxc.gpio_b0_01,
-                p13: p.iomuxc.gpio_b0_03,
-                p14: p.iomuxc.gpio_ad_b1_02,
-                p15: p.iomuxc.gpio_ad_b1_03,
-                p16: p.iomuxc.gpio_ad_b1_07,
-                p17: p.iomuxc.gpio_ad_b1_06,
-                p18: p.iomuxc.gpio_ad_b1_01,
-                p19: p.iomuxc.gpio_ad_b1_00,
-                p20: p.iomuxc.gpio_ad_b1_10,
-                p21: p.iomuxc.gpio_ad_b1_11,
-                p22: p.iomuxc.gpio_ad_b1_08,
-                p23: p.iomuxc.gpio_ad_b1_09,
-                p24: p.iomuxc.gpio_ad_b0_12,
-                p25: p.iomuxc.gpio_ad_b0_13,
-                p28: p.iomuxc.gpio_emc_32,
-                p29: p.iomuxc.gpio_emc_31,
-                p33: p.iomuxc.gpio_emc_07,
-                p36: p.iomuxc.gpio_sd_b0_01,
-                p37: p.iomuxc.gpio_sd_b0_00,
-            },
+            adc1: adc::Unclocked::new(),
+            adc2: adc::Unclocked::new(),
+            pins: pins!(p),
+            #[cfg(feature = "teensy41")]
+            enet_pins: enet_pins!(p),
             i2c: p.i2c,
             spi: p.spi,
             uart: p.uart,
             gpr: p.iomuxc.gpr,
             gpt1: p.gpt1,
+            #[cfg(not(feature = "rtic"))]
             gpt2: p.gpt2,
             dma: p.dma,
             #[cfg(feature = "systick")]
@@ -0,0 +1,278 @@
+//! An RTIC monotonic timer on GPT2
+//!
+//! [`Gpt2`](struct.Gpt2.html) implements RTIC's `Monotonic` trait. It counts microseconds
+//! in 64 bits, so RTIC tasks may `schedule` with real-time [`Duration`](struct.Duration.html)s.
+//! GPT2 runs from the 24MHz crystal oscillator, and keeps running in the WAIT and STOP
+//! low-power modes, so the count doesn't depend on the core clock.
+//!
+//! If we're compiling this module, it's because the `"rtic"` feature is enabled. The BSP
+//! registers the `GPT2` interrupt handler, to extend the 32-bit counter to 64 bits. The
+//! handler also advances the BSP's millisecond counter, since RTIC owns `SysTick`.
+//! Constructing the BSP's `Peripherals` starts GPT2, so the `Peripherals` have no `gpt2`.
+//!
+//! ```ignore
+//! use teensy4_bsp as bsp;
+//! use bsp::monotonic::{Duration, Gpt2};
+//!
+//! #[rtic::app(device = teensy4_bsp, monotonic = teensy4_bsp::monotonic::Gpt2, peripherals = true)]
+//! const APP: () = {
+//!     #[init(schedule = [blink])]
+//!     fn init(cx: init::Context) {
+//!         cx.schedule.blink(cx.start + Duration::from_millis(500)).unwrap();
+//!     }
+//!     // ...
+//! };
+//! ```
+//!
+//! RTIC uses SysTick to wait for the next scheduled task. `Gpt2` converts its durations
+//! into SysTick cycles using the core clock frequency, which is initially `PLL1::ARM_HZ`.
+//! If you change the core clock, update the frequency with [`set_core_clock_hz`](struct.Gpt2.html#method.set_core_clock_hz).
+
+use crate::hal;
+use crate::interrupt;
+use core::{
+    convert::TryFrom,
+    ops, ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use cortex_m::interrupt as critical;
+
+const GPT2: usize = 0x401F_0000;
+const GPT_CR: *mut u32 = GPT2 as *mut u32;
+const GPT_PR: *mut u32 = (GPT2 + 0x04) as *mut u32;
+const GPT_SR: *mut u32 = (GPT2 + 0x08) as *mut u32;
+const GPT_IR: *mut u32 = (GPT2 + 0x0C) as *mut u32;
+const GPT_OCR1: *mut u32 = (GPT2 + 0x10) as *mut u32;
+const GPT_CNT: *const u32 = (GPT2 + 0x24) as *const u32;
+
+const GPT_CR_EN: u32 = 1 << 0;
+const GPT_CR_ENMOD: u32 = 1 << 1;
+const GPT_CR_WAITEN: u32 = 1 << 3;
+const GPT_CR_DOZEEN: u32 = 1 << 4;
+const GPT_CR_STOPEN: u32 = 1 << 5;
+/// Crystal oscillator clock source
+const GPT_CR_CLKSRC_OSC: u32 = 0b101 << 6;
+const GPT_CR_FRR: u32 = 1 << 9;
+const GPT_CR_EN_24M: u32 = 1 << 10;
+const GPT_CR_SWR: u32 = 1 << 15;
+/// 24MHz / 12 / 2 = 1MHz
+const GPT_PR_1MHZ: u32 = (11 << 12) | 1;
+const GPT_SR_OF1: u32 = 1 << 0;
+const GPT_SR_ROV: u32 = 1 << 5;
+/// Output compare one interrupts every millisecond
+const TICKS_PER_MS: u32 = 1_000;
+
+/// CCM clock gates for `gpt2_bus` and `gpt2_serial`
+const CCM_CCGR0: *mut u32 = 0x400F_C068 as *mut u32;
+const CCM_CCGR0_GPT2: u32 = 0b1111 << 24;
+
+/// Microseconds per second
+const TICK_HZ: u32 = 1_000_000;
+
+/// The number of times the 32-bit counter overflowed
+static OVERFLOWS: AtomicU32 = AtomicU32::new(0);
+/// The core clock frequency, which clocks SysTick
+static CORE_HZ: AtomicU32 = AtomicU32::new(hal::ccm::PLL1::ARM_HZ);
+
+/// A point in time, in microseconds since RTIC started
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// Returns the microseconds since RTIC started
+    pub fn as_micros(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the time that elapsed since `earlier`, or zero if `earlier` is later
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        Duration(self.0.saturating_sub(earlier.0))
+    }
+}
+
+/// A span of time, in microseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(u64);
+
+impl Duration {
+    /// A duration of `micros` microseconds
+    pub const fn from_micros(micros: u64) -> Self {
+        Duration(micros)
+    }
+
+    /// A duration of `millis` milliseconds
+    pub const fn from_millis(millis: u64) -> Self {
+        Duration(millis * 1_000)
+    }
+
+    /// A duration of `secs` seconds
+    pub const fn from_secs(secs: u64) -> Self {
+        Duration(secs * 1_000_000)
+    }
+
+    /// Returns the duration in microseconds
+    pub fn as_micros(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<core::time::Duration> for Duration {
+    fn from(duration: core::time::Duration) -> Self {
+        Duration(duration.as_micros() as u64)
+    }
+}
+
+/// RTIC converts a duration to a SysTick reload value. Durations that don't fit in a `u32`
+/// saturate, and RTIC waits again.
+impl TryFrom<Duration> for u32 {
+    type Error = core::convert::Infallible;
+    fn try_from(duration: Duration) -> Result<u32, Self::Error> {
+        Ok(u32::try_from(duration.0).unwrap_or(u32::max_value()))
+    }
+}
+
+impl ops::Add<Duration> for Instant {
+    type Output = Instant;
+    fn add(self, duration: Duration) -> Instant {
+        Instant(self.0 + duration.0)
+    }
+}
+
+impl ops::AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        self.0 += duration.0;
+    }
+}
+
+impl ops::Sub<Duration> for Instant {
+    type Output = Instant;
+    fn sub(self, duration: Duration) -> Instant {
+        Instant(self.0.saturating_sub(duration.0))
+    }
+}
+
+impl ops::Sub for Instant {
+    type Output = Duration;
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+impl ops::Add for Duration {
+    type Output = Duration;
+    fn add(self, other: Duration) -> Duration {
+        Duration(self.0 + other.0)
+    }
+}
+
+/// The GPT2 monotonic timer
+///
+/// The BSP starts the timer when it constructs its `Peripherals`.
+pub struct Gpt2(());
+
+impl Gpt2 {
+    /// Start GPT2, counting microseconds from the crystal oscillator
+    ///
+    /// The monotonic timer owns GPT2 for the rest of the program.
+    pub(crate) fn init(gpt2: hal::gpt::Unclocked) {
+        let _ = gpt2;
+        critical::free(|_| unsafe {
+            ptr::write_volatile(CCM_CCGR0, ptr::read_volatile(CCM_CCGR0) | CCM_CCGR0_GPT2);
+            ptr::write_volatile(GPT_CR, 0);
+            ptr::write_volatile(GPT_CR, GPT_CR_SWR);
+            while ptr::read_volatile(GPT_CR) & GPT_CR_SWR != 0 {}
+            ptr::write_volatile(GPT_IR, 0);
+            ptr::write_volatile(GPT_SR, 0x3F);
+            ptr::write_volatile(GPT_CR, GPT_CR_CLKSRC_OSC | GPT_CR_EN_24M);
+            ptr::write_volatile(GPT_PR, GPT_PR_1MHZ);
+            ptr::write_volatile(GPT_OCR1, TICKS_PER_MS);
+            ptr::write_volatile(GPT_IR, GPT_SR_ROV | GPT_SR_OF1);
+            OVERFLOWS.store(0, Ordering::Relaxed);
+            ptr::write_volatile(
+                GPT_CR,
+                GPT_CR_CLKSRC_OSC
+                    | GPT_CR_EN_24M
+                    | GPT_CR_FRR
+                    | GPT_CR_WAITEN
+                    | GPT_CR_DOZEEN
+                    | GPT_CR_STOPEN
+                    | GPT_CR_ENMOD
+                    | GPT_CR_EN,
+            );
+            cortex_m::peripheral::NVIC::unmask(interrupt::GPT2);
+        });
+    }
+
+    /// Set the core clock frequency, which clocks SysTick
+    pub fn set_core_clock_hz(core_hz: u32) {
+        CORE_HZ.store(core_hz, Ordering::Relaxed);
+    }
+}
+
+/// Returns the greatest common divisor of `a` and `b`
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl rtic::Monotonic for Gpt2 {
+    type Instant = Instant;
+
+    fn ratio() -> rtic::Fraction {
+        let core_hz = CORE_HZ.load(Ordering::Relaxed);
+        let divisor = gcd(core_hz, TICK_HZ);
+        rtic::Fraction {
+            numerator: core_hz / divisor,
+            denominator: TICK_HZ / divisor,
+        }
+    }
+
+    fn now() -> Instant {
+        critical::free(|_| unsafe {
+            let mut overflows = OVERFLOWS.load(Ordering::Relaxed);
+            let mut count = ptr::read_volatile(GPT_CNT);
+            // An overflow that the handler hasn't counted
+            if ptr::read_volatile(GPT_SR) & GPT_SR_ROV != 0 {
+                overflows += 1;
+                count = ptr::read_volatile(GPT_CNT);
+            }
+            Instant(((overflows as u64) << 32) | count as u64)
+        })
+    }
+
+    unsafe fn reset() {
+        critical::free(|_| {
+            let cr = ptr::read_volatile(GPT_CR);
+            ptr::write_volatile(GPT_CR, cr & !GPT_CR_EN);
+            ptr::write_volatile(GPT_SR, GPT_SR_ROV | GPT_SR_OF1);
+            ptr::write_volatile(GPT_OCR1, TICKS_PER_MS);
+            OVERFLOWS.store(0, Ordering::Relaxed);
+            // ENMOD resets the counter when the timer is enabled
+            ptr::write_volatile(GPT_CR, cr | GPT_CR_EN);
+        })
+    }
+
+    fn zero() -> Instant {
+        Instant(0)
+    }
+}
+
+#[crate::rt::interrupt]
+fn GPT2() {
+    unsafe {
+        if ptr::read_volatile(GPT_SR) & GPT_SR_ROV != 0 {
+            ptr::write_volatile(GPT_SR, GPT_SR_ROV);
+            OVERFLOWS.fetch_add(1, Ordering::Relaxed);
+        }
+        if ptr::read_volatile(GPT_SR) & GPT_SR_OF1 != 0 {
+            ptr::write_volatile(GPT_SR, GPT_SR_OF1);
+            let next = ptr::read_volatile(GPT_OCR1).wrapping_add(TICKS_PER_MS);
+            ptr::write_volatile(GPT_OCR1, next);
+            #[cfg(feature = "systick")]
+            crate::systick::tick();
+        }
+    }
+}
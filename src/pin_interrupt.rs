@@ -0,0 +1,317 @@
+//! GPIO pin-change interrupts
+//!
+//! Attach a handler to a Teensy pin with [`attach`](fn.attach.html). The handler runs
+//! when the pin sees the selected [`Edge`](enum.Edge.html). The BSP registers the shared
+//! `GPIOn_Combined_x_y` interrupt handlers, and dispatches to each pin's handler.
+//!
+//! If we're compiling this module, it's because the `"pin-interrupts"` feature is enabled.
+//! If a user also registers a `GPIOn_Combined_x_y` handler, it may result in a duplicate
+//! definition error.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::pin_interrupt::{self, Edge};
+//!
+//! fn on_press(pin: &mut bsp::gpio::DynPin) {
+//!     // Runs in interrupt context
+//! }
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let mut pins = p.pins.erase();
+//! let button = pins.take(2).unwrap();
+//! pin_interrupt::attach(button, Edge::Falling, on_press);
+//! ```
+//!
+//! The handler runs at the priority of the pin's `GPIOn_Combined_x_y` interrupt. Use
+//! [`interrupt_of`](fn.interrupt_of.html) to find a pin's interrupt, and change its priority
+//! through the NVIC.
+//!
+//! In an async task, use [`edges`](fn.edges.html) to wait for a pin's edges.
+
+use crate::executor::WakerSet;
+use crate::gpio::{self, DynPin, NUM_PINS};
+use crate::interrupt;
+use core::{
+    future::Future,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll},
+};
+
+/// The event that triggers a pin interrupt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Interrupt while the pin is low
+    Low,
+    /// Interrupt while the pin is high
+    High,
+    /// Interrupt on a rising edge
+    Rising,
+    /// Interrupt on a falling edge
+    Falling,
+    /// Interrupt on both rising and falling edges
+    Both,
+}
+
+impl Edge {
+    /// Returns the `ICR` field value. `Both` is selected through `EDGE_SEL`,
+    /// so its `ICR` value doesn't matter.
+    const fn icr(self) -> u32 {
+        match self {
+            Edge::Low => 0b00,
+            Edge::High => 0b01,
+            Edge::Rising | Edge::Both => 0b10,
+            Edge::Falling => 0b11,
+        }
+    }
+}
+
+/// A pin interrupt handler
+///
+/// The handler receives the pin that triggered the interrupt. A handler may
+/// attach and detach other pins, but it can't detach its own pin.
+pub type Handler = fn(&mut DynPin);
+
+const GPIO_ICR1: u32 = 0x0C;
+const GPIO_ICR2: u32 = 0x10;
+const GPIO_IMR: u32 = 0x14;
+const GPIO_ISR: u32 = 0x18;
+const GPIO_EDGE_SEL: u32 = 0x1C;
+
+/// Attached pins and handlers, indexed by Teensy pin number
+static mut HANDLERS: [Option<(DynPin, Handler)>; NUM_PINS] = [NONE; NUM_PINS];
+const NONE: Option<(DynPin, Handler)> = None;
+
+/// Returns the shared GPIO interrupt that's signaled by the Teensy pin, `pin`
+///
+/// Returns `None` if the pin isn't available on the selected board.
+pub fn interrupt_of(pin: u8) -> Option<interrupt> {
+    let (port, bit) = gpio::pad(pin)?.gpio();
+    let low = bit < 16;
+    Some(match (port, low) {
+        (1, true) => interrupt::GPIO1_Combined_0_15,
+        (1, false) => interrupt::GPIO1_Combined_16_31,
+        (2, true) => interrupt::GPIO2_Combined_0_15,
+        (2, false) => interrupt::GPIO2_Combined_16_31,
+        (3, true) => interrupt::GPIO3_Combined_0_15,
+        (3, false) => interrupt::GPIO3_Combined_16_31,
+        (4, true) => interrupt::GPIO4_Combined_0_15,
+        (4, false) => interrupt::GPIO4_Combined_16_31,
+        _ => unreachable!("Teensy pins only use GPIO1 through GPIO4"),
+    })
+}
+
+/// Attach an interrupt `handler` to a pin
+///
+/// The pin is switched to its normal GPIO bank, and configured as an input.
+/// If there's already a handler for the pin, the previous handler is
+/// replaced, and `attach` returns the previous handler's pin.
+///
+/// `attach` unmasks the pin's interrupt in the NVIC.
+pub fn attach(mut pin: DynPin, edge: Edge, handler: Handler) -> Option<DynPin> {
+    let number = pin.pin();
+    let interrupt = interrupt_of(number).unwrap();
+    pin.set_input();
+    pin.set_fast(false);
+    cortex_m::interrupt::free(|_| unsafe {
+        let mask = pin.mask();
+        pin.modify(pin.register(GPIO_IMR), |imr| imr & !mask);
+
+        let (_, bit) = pin.pad().gpio();
+        let (icr, shift) = if bit < 16 {
+            (GPIO_ICR1, bit * 2)
+        } else {
+            (GPIO_ICR2, (bit - 16) * 2)
+        };
+        pin.modify(pin.register(icr), |value| {
+            (value & !(0b11 << shift)) | (edge.icr() << shift)
+        });
+        pin.modify(pin.register(GPIO_EDGE_SEL), |edge_sel| {
+            if Edge::Both == edge {
+                edge_sel | mask
+            } else {
+                edge_sel & !mask
+            }
+        });
+        ptr::write_volatile(pin.register(GPIO_ISR), mask);
+
+        let register = pin.register(GPIO_IMR);
+        let replaced = HANDLERS[number as usize].replace((pin, handler));
+        ptr::write_volatile(register, ptr::read_volatile(register) | mask);
+        cortex_m::peripheral::NVIC::unmask(interrupt);
+        replaced.map(|(pin, _)| pin)
+    })
+}
+
+/// Detach the interrupt handler from the Teensy pin, `pin`
+///
+/// Returns the pin, or `None` if there was no attached handler. Also returns
+/// `None` if called from the pin's own handler. The returned pin remains in its
+/// normal GPIO bank.
+pub fn detach(pin: u8) -> Option<DynPin> {
+    cortex_m::interrupt::free(|_| unsafe {
+        let (pin, _) = HANDLERS.get_mut(pin as usize)?.take()?;
+        let mask = pin.mask();
+        pin.modify(pin.register(GPIO_IMR), |imr| imr & !mask);
+        ptr::write_volatile(pin.register(GPIO_ISR), mask);
+        Some(pin)
+    })
+}
+
+/// Pins with an edge that a task hasn't seen, indexed by Teensy pin number
+static mut EDGES: [bool; NUM_PINS] = [false; NUM_PINS];
+static EDGE_WAKERS: WakerSet = WakerSet::new();
+
+fn on_edge(pin: &mut DynPin) {
+    unsafe { EDGES[pin.pin() as usize] = true };
+    EDGE_WAKERS.wake();
+}
+
+/// Wait for a pin's edges in an async task
+///
+/// `edges` attaches a handler to `pin`, like [`attach`](fn.attach.html). Await
+/// [`next`](struct.Edges.html#method.next) for each edge.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::pin_interrupt::{self, Edge};
+///
+/// async fn on_press(button: bsp::gpio::DynPin) {
+///     let mut presses = pin_interrupt::edges(button, Edge::Falling);
+///     loop {
+///         presses.next().await;
+///         log::info!("Pressed!");
+///     }
+/// }
+/// ```
+pub fn edges(pin: DynPin, edge: Edge) -> Edges {
+    let number = pin.pin();
+    cortex_m::interrupt::free(|_| unsafe { EDGES[number as usize] = false });
+    attach(pin, edge, on_edge);
+    Edges { pin: number }
+}
+
+/// A pin's edges
+///
+/// Use [`edges`](fn.edges.html) to create `Edges`. Dropping `Edges` leaves the pin's
+/// handler attached. Use [`release`](#method.release) to detach the handler, and
+/// return the pin.
+pub struct Edges {
+    pin: u8,
+}
+
+impl Edges {
+    /// Wait for the next edge
+    ///
+    /// Edges that happen between calls to `next` are merged into one edge.
+    pub fn next(&mut self) -> NextEdge<'_> {
+        NextEdge { edges: self }
+    }
+
+    /// Detach the handler, and return the pin
+    pub fn release(self) -> DynPin {
+        detach(self.pin).unwrap()
+    }
+}
+
+/// A future that waits for a pin's next edge
+///
+/// Use [`Edges::next`](struct.Edges.html#method.next) to create a `NextEdge`.
+pub struct NextEdge<'a> {
+    edges: &'a mut Edges,
+}
+
+impl<'a> Future for NextEdge<'a> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let pin = self.edges.pin as usize;
+        EDGE_WAKERS.register(cx.waker());
+        let seen = cortex_m::interrupt::free(|_| unsafe {
+            let seen = EDGES[pin];
+            EDGES[pin] = false;
+            seen
+        });
+        if seen {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Dispatch to the handlers of all pending pins on `port`, within
+/// the bit range `bits`
+///
+/// # Safety
+///
+/// Only call from the port's combined interrupt handlers.
+unsafe fn dispatch(port: u8, bits: core::ops::Range<u8>) {
+    for number in 0..NUM_PINS {
+        // Take the pin out of HANDLERS while its handler runs, so that the
+        // handler can attach and detach other pins.
+        let pending = cortex_m::interrupt::free(|_| {
+            let slot = &mut HANDLERS[number];
+            let (pin, _) = slot.as_ref()?;
+            let (pin_port, bit) = pin.pad().gpio();
+            let mask = pin.mask();
+            let isr = pin.register(GPIO_ISR);
+            if pin_port != port
+                || !bits.contains(&bit)
+                || ptr::read_volatile(isr) & ptr::read_volatile(pin.register(GPIO_IMR)) & mask == 0
+            {
+                return None;
+            }
+            ptr::write_volatile(isr, mask);
+            slot.take()
+        });
+        if let Some((mut pin, handler)) = pending {
+            handler(&mut pin);
+            cortex_m::interrupt::free(|_| {
+                let slot = &mut HANDLERS[number];
+                if slot.is_none() {
+                    *slot = Some((pin, handler));
+                }
+            });
+        }
+    }
+}
+
+#[crate::rt::interrupt]
+fn GPIO1_Combined_0_15() {
+    unsafe { dispatch(1, 0..16) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO1_Combined_16_31() {
+    unsafe { dispatch(1, 16..32) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO2_Combined_0_15() {
+    unsafe { dispatch(2, 0..16) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO2_Combined_16_31() {
+    unsafe { dispatch(2, 16..32) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO3_Combined_0_15() {
+    unsafe { dispatch(3, 0..16) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO3_Combined_16_31() {
+    unsafe { dispatch(3, 16..32) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO4_Combined_0_15() {
+    unsafe { dispatch(4, 0..16) }
+}
+
+#[crate::rt::interrupt]
+fn GPIO4_Combined_16_31() {
+    unsafe { dispatch(4, 16..32) }
+}
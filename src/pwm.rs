@@ -0,0 +1,255 @@
+//! PWM outputs by Teensy pin
+//!
+//! A [`Pwm`](struct.Pwm.html) drives a Teensy pin's FlexPWM output. You don't need to
+//! know the pin's FlexPWM module, submodule, or channel; the BSP finds them in the
+//! board's pin table. Call [`init`](fn.init.html) once to start the PWM modules, then
+//! create `Pwm`s from the board's pins.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::pwm::{self, Pwm};
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+//! pwm::init(
+//!     (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+//!     &mut p.ccm.handle,
+//!     clocks.ipg_hz,
+//! );
+//!
+//! let mut motor = Pwm::on_pin(p.pins.p2, 20_000).unwrap();
+//! motor.set_duty(0.25);
+//! ```
+//!
+//! Outputs on the same FlexPWM submodule share a switching frequency. For example, pins 6
+//! and 9 are the A and B outputs of FlexPWM2 submodule 2. The BSP tracks the submodules
+//! that drive `Pwm`s. It won't create or re-tune a `Pwm` if that would change the frequency
+//! of another `Pwm` on the same submodule; instead, it returns
+//! [`Error::FrequencyConflict`](enum.Error.html#variant.FrequencyConflict).
+//!
+//! `Pwm` implements `embedded_hal::PwmPin`, with a `u16` duty cycle that counts up to
+//! `get_max_duty()`. The inherent `set_duty` method takes a fraction from 0 to 1.
+//!
+//! Pins that are only connected to a QuadTimer don't have a FlexPWM output, and they
+//! aren't supported.
+
+use crate::{
+    flexpwm,
+    gpio::{self, TeensyPin},
+    hal,
+};
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::interrupt as critical;
+
+/// PWM errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The PWM modules aren't started. Call [`init`](fn.init.html) first.
+    Uninitialized,
+    /// The pin isn't connected to a FlexPWM output
+    NoPwm(u8),
+    /// Another `Pwm` on the pin's submodule switches at `frequency_hz`
+    FrequencyConflict {
+        /// The Teensy pin number
+        pin: u8,
+        /// The submodule's switching frequency
+        frequency_hz: u32,
+    },
+}
+
+/// The IPG clock frequency, or zero if the PWM modules aren't started
+static IPG_HZ: AtomicU32 = AtomicU32::new(0);
+
+/// The state of a FlexPWM submodule
+#[derive(Clone, Copy)]
+struct Submodule {
+    /// The number of `Pwm`s that use the submodule
+    users: u8,
+    /// The switching frequency
+    frequency_hz: u32,
+}
+
+const IDLE: Submodule = Submodule {
+    users: 0,
+    frequency_hz: flexpwm::DEFAULT_FREQUENCY_HZ,
+};
+
+/// Submodules, indexed by module (minus one), then submodule
+///
+/// Only accessed in critical sections.
+static mut SUBMODULES: [[Submodule; 4]; 4] = [[IDLE; 4]; 4];
+
+/// Returns the state of the output's submodule
+///
+/// # Safety
+///
+/// Call in a critical section.
+unsafe fn submodule(output: flexpwm::Output) -> &'static mut Submodule {
+    &mut SUBMODULES[output.module as usize - 1][output.submodule as usize]
+}
+
+/// Start all four FlexPWM modules
+///
+/// `ccm` is used to enable the PWM clocks. `ipg_hz` is the IPG clock frequency, in Hz;
+/// see [`Clocks`](../clocks/struct.Clocks.html). If you later change the IPG clock
+/// frequency, PWM frequencies will be incorrect.
+pub fn init(
+    pwm: (
+        hal::pwm::Unclocked<hal::pwm::module::_1>,
+        hal::pwm::Unclocked<hal::pwm::module::_2>,
+        hal::pwm::Unclocked<hal::pwm::module::_3>,
+        hal::pwm::Unclocked<hal::pwm::module::_4>,
+    ),
+    ccm: &mut hal::ccm::Handle,
+    ipg_hz: u32,
+) {
+    let (pwm1, pwm2, pwm3, pwm4) = pwm;
+    pwm1.clock(ccm);
+    pwm2.clock(ccm);
+    pwm3.clock(ccm);
+    pwm4.clock(ccm);
+    for module in 1..=4 {
+        // Safety: we consumed the modules, and we just enabled their clocks
+        unsafe { flexpwm::init(module, ipg_hz) };
+    }
+    IPG_HZ.store(ipg_hz, Ordering::Release);
+}
+
+/// A PWM output on a Teensy pin
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct Pwm<P> {
+    pin: P,
+    output: flexpwm::Output,
+}
+
+impl<P: TeensyPin> Pwm<P> {
+    /// Drive `pin`'s FlexPWM output at `frequency_hz`
+    ///
+    /// The output starts with a zero duty cycle. Returns an error if the pin doesn't
+    /// have a FlexPWM output, or if another `Pwm` on the same submodule switches at
+    /// a different frequency.
+    pub fn on_pin(pin: P, frequency_hz: u32) -> Result<Self, Error> {
+        let output = flexpwm::output(P::PIN).ok_or(Error::NoPwm(P::PIN))?;
+        let ipg_hz = IPG_HZ.load(Ordering::Acquire);
+        if ipg_hz == 0 {
+            return Err(Error::Uninitialized);
+        }
+        // The pad table covers all `TeensyPin`s
+        let pad = gpio::pad(P::PIN).unwrap();
+        critical::free(|_| unsafe {
+            let submodule = submodule(output);
+            if submodule.users == 0 {
+                flexpwm::set_frequency(output, ipg_hz, frequency_hz);
+                submodule.frequency_hz = frequency_hz;
+            } else if submodule.frequency_hz != frequency_hz {
+                return Err(Error::FrequencyConflict {
+                    pin: P::PIN,
+                    frequency_hz: submodule.frequency_hz,
+                });
+            }
+            submodule.users += 1;
+            // Safety: we consumed the pin, so we own its output
+            flexpwm::set_compare(output, 0);
+            flexpwm::mux(pad, output);
+            flexpwm::set_enable(output, true);
+            Ok(())
+        })?;
+        Ok(Pwm { pin, output })
+    }
+
+    /// Returns the Teensy pin number
+    pub fn pin(&self) -> u8 {
+        P::PIN
+    }
+
+    /// Returns the FlexPWM output that drives the pin
+    pub fn output(&self) -> flexpwm::Output {
+        self.output
+    }
+
+    /// Returns the switching frequency, in Hz
+    pub fn frequency_hz(&self) -> u32 {
+        critical::free(|_| unsafe { submodule(self.output).frequency_hz })
+    }
+
+    /// Set the switching frequency
+    ///
+    /// The duty cycle is kept. Returns an error if another `Pwm` on the same submodule
+    /// switches at a different frequency.
+    pub fn set_frequency(&mut self, frequency_hz: u32) -> Result<(), Error> {
+        let ipg_hz = IPG_HZ.load(Ordering::Acquire);
+        critical::free(|_| unsafe {
+            let submodule = submodule(self.output);
+            if submodule.frequency_hz == frequency_hz {
+                Ok(())
+            } else if submodule.users > 1 {
+                Err(Error::FrequencyConflict {
+                    pin: P::PIN,
+                    frequency_hz: submodule.frequency_hz,
+                })
+            } else {
+                flexpwm::set_frequency(self.output, ipg_hz, frequency_hz);
+                submodule.frequency_hz = frequency_hz;
+                Ok(())
+            }
+        })
+    }
+
+    /// Set the duty cycle, a fraction from 0 to 1
+    ///
+    /// `fraction` saturates at 0 and 1.
+    pub fn set_duty(&mut self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let counts = flexpwm::modulo(self.output) as u32 + 1;
+        let compare = (fraction * counts as f32) as u32;
+        embedded_hal::PwmPin::set_duty(self, compare.min(u16::max_value() as u32) as u16);
+    }
+
+    /// Returns the duty cycle, a fraction from 0 to 1
+    pub fn duty(&self) -> f32 {
+        let counts = flexpwm::modulo(self.output) as u32 + 1;
+        flexpwm::compare(self.output) as f32 / counts as f32
+    }
+
+    /// Disable the output, and return the pin
+    ///
+    /// If this was the last `Pwm` on its submodule, other pins may use the submodule
+    /// at a new frequency.
+    pub fn release(self) -> P {
+        critical::free(|_| unsafe {
+            flexpwm::set_enable(self.output, false);
+            submodule(self.output).users -= 1;
+        });
+        self.pin
+    }
+}
+
+impl<P: TeensyPin> embedded_hal::PwmPin for Pwm<P> {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        unsafe { flexpwm::set_enable(self.output, false) };
+    }
+
+    fn enable(&mut self) {
+        unsafe { flexpwm::set_enable(self.output, true) };
+    }
+
+    fn get_duty(&self) -> u16 {
+        flexpwm::compare(self.output)
+    }
+
+    /// Returns the submodule's modulo. The maximum duty cycle keeps the output
+    /// high for all but the last count of the period.
+    fn get_max_duty(&self) -> u16 {
+        flexpwm::modulo(self.output)
+    }
+
+    /// Set the number of counts that the output is high. `duty` saturates at
+    /// `get_max_duty()`.
+    fn set_duty(&mut self, duty: u16) {
+        // Other outputs on the submodule share its load register
+        critical::free(|_| unsafe { flexpwm::set_compare(self.output, duty) });
+    }
+}
@@ -0,0 +1,980 @@
+//! Teensy serial ports, Serial1 through Serial8
+//!
+//! Teensy serial ports don't share numbers with the processor's LPUART modules. For
+//! instance, `Serial1` is LPUART6 on pins 0 and 1. This module maps each serial port
+//! to its LPUART and pins:
+//!
+//! | Port      | LPUART | TX pin | RX pin | Notes            |
+//! | --------- | ------ | ------ | ------ | ---------------- |
+//! | `Serial1` | 6      | 1      | 0      |                  |
+//! | `Serial2` | 4      | 8      | 7      |                  |
+//! | `Serial3` | 2      | 14     | 15     |                  |
+//! | `Serial4` | 3      | 17     | 16     |                  |
+//! | `Serial5` | 8      | 20     | 21     |                  |
+//! | `Serial6` | 1      | 24     | 25     |                  |
+//! | `Serial7` | 7      | 29     | 28     |                  |
+//! | `Serial8` | 5      | 35     | 34     | Teensy 4.1 only  |
+//!
+//! A [`Serial`](struct.Serial.html) buffers received and transmitted bytes. Its LPUART
+//! interrupt moves bytes between the buffers and the LPUART. Enable the `"serial-interrupts"`
+//! feature, and the BSP registers the `LPUARTn` interrupt handlers for you. `new()` then
+//! unmasks the port's interrupt:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::serial::Serial1;
+//! use embedded_hal::serial::Write;
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let mut serial1 = Serial1::new(
+//!     &mut board.uart,
+//!     &mut board.ccm.handle,
+//!     board.pins.p1,
+//!     board.pins.p0,
+//!     115_200,
+//! )
+//! .unwrap();
+//! nb::block!(serial1.write(b'!')).unwrap();
+//! ```
+//!
+//! Without the feature, call [`on_interrupt`](struct.Serial.html#method.on_interrupt) from
+//! your own `LPUARTn` handler, and unmask [`INTERRUPT`](struct.Serial.html#associatedconstant.INTERRUPT)
+//! in the NVIC. `on_interrupt` doesn't need the `Serial` object, so there's nothing to share
+//! with the handler:
+//!
+//! ```ignore
+//! #[bsp::rt::interrupt]
+//! fn LPUART6() {
+//!     bsp::serial::Serial1::on_interrupt();
+//! }
+//! ```
+//!
+//! `Serial` implements the `embedded_hal::serial` traits. Reads return `WouldBlock`
+//! when the receive buffer is empty, and writes return `WouldBlock` when the transmit
+//! buffer is full.
+//!
+//! ## Errors
+//!
+//! The interrupt records receive [`Error`](enum.Error.html)s, like overruns and framing
+//! errors. The next `read()` returns the error, then continues with the buffered bytes.
+//!
+//! ## Idle line
+//!
+//! Packet protocols often mark the end of a message with a quiet line. Call
+//! [`enable_idle_detection`](struct.Serial.html#method.enable_idle_detection) to watch
+//! for an idle line after received data. When the line goes idle, the interrupt sets
+//! a flag that you poll with [`message_received`](struct.Serial.html#method.message_received),
+//! and calls an optional callback.
+//!
+//! ## RS-485
+//!
+//! An RS-485 transceiver needs its driver enabled while the port transmits, and disabled
+//! so that it can receive. [`enable_rs485`](struct.Serial.html#method.enable_rs485) drives
+//! the transceiver's driver-enable (DE) input from a Teensy pin, or from the LPUART's hardware
+//! RTS output. With a Teensy pin, the port asserts DE before the first byte, and releases DE
+//! after the last stop bit. Optional turnaround delays hold DE around each transmission.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::serial::{self, Rs485Config, Serial2, TransmitEnable};
+//!
+//! fn on_frame() {
+//!     // A Modbus RTU frame is complete
+//! }
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let mut serial2 = Serial2::new(
+//!     &mut board.uart,
+//!     &mut board.ccm.handle,
+//!     board.pins.p8,
+//!     board.pins.p7,
+//!     19_200,
+//! )
+//! .unwrap();
+//! let de = bsp::gpio::DynPin::new(board.pins.p6, &mut board.gpr);
+//! serial2.enable_rs485(
+//!     Rs485Config {
+//!         transmit_enable: TransmitEnable::Pin(de),
+//!         pre_transmit_us: 10,
+//!         post_transmit_us: 10,
+//!     },
+//!     board.clocks.arm_hz,
+//! );
+//! serial2.enable_idle_detection(serial::MODBUS_IDLE_CHARACTERS, Some(on_frame));
+//! ```
+//!
+//! ## Breaks and 9-bit addressing
+//!
+//! [`send_break`](struct.Serial.html#method.send_break) queues a break character between
+//! buffered bytes. After [`enable_lin`](struct.Serial.html#method.enable_lin), breaks are
+//! 13 bits long, and a received break is reported in order with the received bytes, as
+//! [`Error::Break`](enum.Error.html#variant.Break). The [`lin`](../lin/index.html) module
+//! builds and parses LIN frames; `Serial` sends them with
+//! [`write_lin_header`](struct.Serial.html#method.write_lin_header) and
+//! [`write_lin_response`](struct.Serial.html#method.write_lin_response):
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::lin::{Checksum, Event, Receiver};
+//! use bsp::serial::{Error, Serial3};
+//! use embedded_hal::serial::Read;
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let mut serial3 = Serial3::new(
+//!     &mut board.uart,
+//!     &mut board.ccm.handle,
+//!     board.pins.p14,
+//!     board.pins.p15,
+//!     19_200,
+//! )
+//! .unwrap();
+//! serial3.enable_lin();
+//!
+//! // A LIN master requests the response for frame 0x10...
+//! nb::block!(serial3.write_lin_header(0x10)).unwrap();
+//! // ...and parses its own header, then the slave's response.
+//! let mut receiver = Receiver::new();
+//! loop {
+//!     let event = match serial3.read() {
+//!         Err(nb::Error::Other(Error::Break)) => {
+//!             receiver.on_break();
+//!             continue;
+//!         }
+//!         Ok(byte) => receiver.push(byte),
+//!         _ => continue,
+//!     };
+//!     match event {
+//!         Ok(Some(Event::Header(id))) => {
+//!             receiver.expect_response(id, 2, Checksum::for_id(id)).unwrap();
+//!         }
+//!         Ok(Some(Event::Frame(frame))) => {
+//!             log::info!("Frame {:#X}: {:?}", frame.id(), frame.data());
+//!             break;
+//!         }
+//!         _ => (),
+//!     }
+//! }
+//! ```
+//!
+//! In 9-bit mode, set with [`set_nine_bit`](struct.Serial.html#method.set_nine_bit), the ninth
+//! bit marks an address. [`write_address`](struct.Serial.html#method.write_address) sends an
+//! address, and [`read_word`](struct.Serial.html#method.read_word) reports the ninth bit.
+//! [`set_address_match`](struct.Serial.html#method.set_address_match) lets the LPUART discard
+//! traffic for other nodes.
+
+use crate::{
+    gpio::DynPin,
+    hal::{self, uart::module},
+    interrupt, lin,
+    setup::Uarts,
+};
+use core::{convert::Infallible, ptr};
+use hal::iomuxc::{gpio::*, Alt5};
+
+/// The size of each receive and transmit buffer, in bytes
+pub const BUFFER_SIZE: usize = 64;
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::module::_1 {}
+    impl Sealed for super::module::_2 {}
+    impl Sealed for super::module::_3 {}
+    impl Sealed for super::module::_4 {}
+    impl Sealed for super::module::_5 {}
+    impl Sealed for super::module::_6 {}
+    impl Sealed for super::module::_7 {}
+    impl Sealed for super::module::_8 {}
+}
+
+/// An LPUART module
+///
+/// Implemented on the HAL's UART module types.
+pub trait Lpuart: private::Sealed {
+    /// The LPUART number, 1 through 8
+    const NUMBER: u8;
+    /// The LPUART's interrupt
+    const INTERRUPT: interrupt;
+}
+
+macro_rules! lpuart {
+    ($($module:ident => $number:literal, $interrupt:ident;)+) => {
+        $(
+            impl Lpuart for module::$module {
+                const NUMBER: u8 = $number;
+                const INTERRUPT: interrupt = interrupt::$interrupt;
+            }
+        )+
+    };
+}
+
+lpuart! {
+    _1 => 1, LPUART1;
+    _2 => 2, LPUART2;
+    _3 => 3, LPUART3;
+    _4 => 4, LPUART4;
+    _5 => 5, LPUART5;
+    _6 => 6, LPUART6;
+    _7 => 7, LPUART7;
+    _8 => 8, LPUART8;
+}
+
+/// `Serial1`: LPUART6, TX on pin 1, RX on pin 0
+pub type Serial1 = Serial<module::_6>;
+/// `Serial2`: LPUART4, TX on pin 8, RX on pin 7
+pub type Serial2 = Serial<module::_4>;
+/// `Serial3`: LPUART2, TX on pin 14, RX on pin 15
+pub type Serial3 = Serial<module::_2>;
+/// `Serial4`: LPUART3, TX on pin 17, RX on pin 16
+pub type Serial4 = Serial<module::_3>;
+/// `Serial5`: LPUART8, TX on pin 20, RX on pin 21
+pub type Serial5 = Serial<module::_8>;
+/// `Serial6`: LPUART1, TX on pin 24, RX on pin 25
+pub type Serial6 = Serial<module::_1>;
+/// `Serial7`: LPUART7, TX on pin 29, RX on pin 28
+pub type Serial7 = Serial<module::_7>;
+/// `Serial8`: LPUART5, TX on pin 35, RX on pin 34. Teensy 4.1 only
+#[cfg(feature = "teensy41")]
+pub type Serial8 = Serial<module::_5>;
+
+const LPUART_BASE: [u32; 8] = [
+    0x4018_4000,
+    0x4018_8000,
+    0x4018_C000,
+    0x4019_0000,
+    0x4019_4000,
+    0x4019_8000,
+    0x4019_C000,
+    0x401A_0000,
+];
+
+const BAUD: u32 = 0x10;
+const STAT: u32 = 0x14;
+const CTRL: u32 = 0x18;
+const DATA: u32 = 0x1C;
+const MATCH: u32 = 0x20;
+const MODIR: u32 = 0x24;
+const WATER: u32 = 0x2C;
+
+const STAT_PF: u32 = 1 << 16;
+const STAT_FE: u32 = 1 << 17;
+const STAT_NF: u32 = 1 << 18;
+const STAT_OR: u32 = 1 << 19;
+const STAT_IDLE: u32 = 1 << 20;
+const STAT_RDRF: u32 = 1 << 21;
+const STAT_TC: u32 = 1 << 22;
+const STAT_TDRE: u32 = 1 << 23;
+const STAT_LBKDE: u32 = 1 << 25;
+const STAT_BRK13: u32 = 1 << 26;
+const STAT_LBKDIF: u32 = 1 << 31;
+/// Write-1-to-clear error, idle and break flags
+const STAT_W1C: u32 = STAT_PF | STAT_FE | STAT_NF | STAT_OR | STAT_IDLE | STAT_LBKDIF;
+/// Read-write configuration bits, which must be preserved when clearing flags
+const STAT_CONFIG: u32 = 0x1F << 25;
+
+const BAUD_LBKDIE: u32 = 1 << 15;
+const BAUD_MAEN1: u32 = 1 << 31;
+
+const CTRL_M: u32 = 1 << 4;
+const CTRL_ILT: u32 = 1 << 2;
+const CTRL_IDLECFG_SHIFT: u32 = 8;
+const CTRL_IDLECFG_MASK: u32 = 0b111 << CTRL_IDLECFG_SHIFT;
+const CTRL_ILIE: u32 = 1 << 20;
+const CTRL_RIE: u32 = 1 << 21;
+const CTRL_TCIE: u32 = 1 << 22;
+const CTRL_TIE: u32 = 1 << 23;
+const CTRL_RE: u32 = 1 << 18;
+const CTRL_TE: u32 = 1 << 19;
+/// Parity, framing, noise and overrun interrupt enables
+const CTRL_EIE: u32 = 0xF << 24;
+
+const MODIR_TXRTSE: u32 = 1 << 1;
+const MODIR_TXRTSPOL: u32 = 1 << 2;
+
+const DATA_MASK: u32 = 0x1FF;
+/// With all-zero data, transmits a break character
+const DATA_FRETSC: u32 = 1 << 13;
+
+/// The ninth bit of a word, which marks an address in 9-bit mode
+const ADDRESS: u16 = 1 << 8;
+/// A buffered break. In the transmit buffer, this is the `DATA` value that sends
+/// a break.
+const BREAK: u16 = DATA_FRETSC as u16;
+
+const WATER_RXCOUNT_SHIFT: u32 = 24;
+const WATER_RXCOUNT_MASK: u32 = 0b111 << WATER_RXCOUNT_SHIFT;
+const WATER_RXWATER_MASK: u32 = 0b11 << 16;
+
+/// A receive error
+///
+/// See the [module-level documentation](index.html) for when errors are reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The LPUART received data before the interrupt could read
+    /// the previous data. Data was lost.
+    Overrun,
+    /// The receive buffer was full. Data was lost.
+    BufferFull,
+    /// A character was missing its stop bit
+    Framing,
+    /// A character had the wrong parity
+    Parity,
+    /// The LPUART detected noise on the line
+    Noise,
+    /// The LPUART received a break. Only reported after
+    /// [`enable_lin`](struct.Serial.html#method.enable_lin).
+    Break,
+}
+
+impl Error {
+    /// All errors recorded by the interrupt, from highest to lowest
+    /// reporting priority. Breaks are buffered with received data.
+    const ALL: [Error; 5] = [
+        Error::Overrun,
+        Error::BufferFull,
+        Error::Framing,
+        Error::Parity,
+        Error::Noise,
+    ];
+
+    const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A fixed-size FIFO of words
+///
+/// Words hold a byte, the ninth bit, and a `BREAK` flag.
+struct RingBuffer {
+    buffer: [u16; BUFFER_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    const fn new() -> Self {
+        RingBuffer {
+            buffer: [0; BUFFER_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, word: u16) -> bool {
+        if self.len == BUFFER_SIZE {
+            return false;
+        }
+        self.buffer[(self.head + self.len) % BUFFER_SIZE] = word;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<u16> {
+        if self.len == 0 {
+            return None;
+        }
+        let word = self.buffer[self.head];
+        self.head = (self.head + 1) % BUFFER_SIZE;
+        self.len -= 1;
+        Some(word)
+    }
+
+    fn free(&self) -> usize {
+        BUFFER_SIZE - self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+/// A callback that runs in interrupt context when the receive line goes idle
+pub type IdleHandler = fn();
+
+/// Modbus RTU frames end after 3.5 idle characters. Pass this to
+/// [`enable_idle_detection`](struct.Serial.html#method.enable_idle_detection),
+/// which rounds idle time to a power of two characters.
+///
+/// Above 19200 baud, Modbus fixes the inter-frame delay at 1.75ms. Four characters
+/// is shorter than that delay, so a frame's end may be signaled early at fast baud rates.
+pub const MODBUS_IDLE_CHARACTERS: u8 = 4;
+
+/// Drives an RS-485 transceiver's driver-enable input
+pub enum TransmitEnable {
+    /// The port drives a Teensy pin high while transmitting
+    Pin(DynPin),
+    /// The LPUART drives its RTS output high while transmitting
+    ///
+    /// You're responsible for muxing the LPUART's RTS signal onto a pad. The
+    /// hardware releases RTS right after the last stop bit, so the turnaround
+    /// delays in [`Rs485Config`](struct.Rs485Config.html) don't apply.
+    Rts,
+}
+
+/// RS-485 configuration
+pub struct Rs485Config {
+    /// Controls the transceiver's driver
+    pub transmit_enable: TransmitEnable,
+    /// Microseconds between asserting the driver enable and sending the first byte
+    pub pre_transmit_us: u32,
+    /// Microseconds between the last stop bit and releasing the driver enable
+    pub post_transmit_us: u32,
+}
+
+/// A driver-enable pin, and its turnaround delays
+struct DriverEnable {
+    pin: DynPin,
+    pre_cycles: u32,
+    post_cycles: u32,
+    /// Set while the pin is asserted
+    active: bool,
+}
+
+/// Busy-wait for `cycles` core clock cycles
+fn delay(cycles: u32) {
+    // Avoid asking the delay loop for zero cycles
+    if cycles > 0 {
+        cortex_m::asm::delay(cycles);
+    }
+}
+
+/// Buffers and events shared between a `Serial` and its interrupt
+struct State {
+    rx: RingBuffer,
+    tx: RingBuffer,
+    /// Pending errors, one bit per `Error`
+    errors: u8,
+    /// Set when the line goes idle, cleared by `message_received()`
+    idle: bool,
+    on_idle: Option<IdleHandler>,
+    /// Set when RS-485 mode uses a driver-enable pin
+    driver_enable: Option<DriverEnable>,
+}
+
+impl State {
+    const fn new() -> Self {
+        State {
+            rx: RingBuffer::new(),
+            tx: RingBuffer::new(),
+            errors: 0,
+            idle: false,
+            on_idle: None,
+            driver_enable: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.rx.clear();
+        self.tx.clear();
+        self.errors = 0;
+        self.idle = false;
+        self.on_idle = None;
+        self.driver_enable = None;
+    }
+}
+
+/// Buffers and events, indexed by LPUART number less one
+///
+/// Only accessed in critical sections.
+static mut STATES: [State; 8] = [EMPTY; 8];
+const EMPTY: State = State::new();
+
+/// A buffered, interrupt-driven serial port
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct Serial<M> {
+    uart: hal::uart::UART<M>,
+}
+
+/// Defines `new()` for a Teensy serial port
+///
+/// `new()` builds the LPUART with the `Uarts` builder for the same pins, so the
+/// builder decides each pad's alternate function. LPUART5's pads use ALT1; the
+/// others use ALT2.
+macro_rules! serial_port {
+    ($($(#[$cfg:meta])* $module:ident, $builder:ident, $tx:ident, $rx:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl Serial<module::$module> {
+                /// Build the serial port's LPUART, and prepare it for buffered I/O
+                ///
+                /// Returns `None` if the LPUART was already built, or if the baud rate
+                /// isn't possible.
+                pub fn new(
+                    uarts: &mut Uarts,
+                    ccm: &mut hal::ccm::Handle,
+                    tx: $tx<Alt5>,
+                    rx: $rx<Alt5>,
+                    baud: u32,
+                ) -> Option<Self> {
+                    uarts.$builder(ccm, tx, rx, baud).map(Serial::from_uart)
+                }
+            }
+        )+
+    };
+}
+
+serial_port! {
+    _6, p1_p0, GPIO_AD_B0_02, GPIO_AD_B0_03;
+    _4, p8_p7, GPIO_B1_00, GPIO_B1_01;
+    _2, p14_p15, GPIO_AD_B1_02, GPIO_AD_B1_03;
+    _3, p17_p16, GPIO_AD_B1_06, GPIO_AD_B1_07;
+    _8, p20_p21, GPIO_AD_B1_10, GPIO_AD_B1_11;
+    _1, p24_p25, GPIO_AD_B0_12, GPIO_AD_B0_13;
+    _7, p29_p28, GPIO_EMC_31, GPIO_EMC_32;
+    #[cfg(feature = "teensy41")] _5, p35_p34, GPIO_B1_12, GPIO_B1_13;
+}
+
+impl<M: Lpuart> Serial<M> {
+    /// The serial port's LPUART interrupt
+    pub const INTERRUPT: interrupt = M::INTERRUPT;
+
+    /// Buffer an LPUART that's already configured
+    ///
+    /// Enables the LPUART's receive and error interrupts. With the `"serial-interrupts"`
+    /// feature, this also unmasks the interrupt in the NVIC. Otherwise, you're responsible
+    /// for unmasking the interrupt.
+    pub fn from_uart(uart: hal::uart::UART<M>) -> Self {
+        let serial = Serial { uart };
+        cortex_m::interrupt::free(|_| unsafe {
+            STATES[M::NUMBER as usize - 1].reset();
+            modify(M::NUMBER, CTRL, |ctrl| ctrl | CTRL_RIE | CTRL_EIE);
+            #[cfg(feature = "serial-interrupts")]
+            cortex_m::peripheral::NVIC::unmask(M::INTERRUPT);
+        });
+        serial
+    }
+
+    /// Disable the LPUART's interrupts, and return the LPUART
+    ///
+    /// Buffered bytes are dropped. Call [`disable_rs485`](#method.disable_rs485) first
+    /// to recover a driver-enable pin.
+    pub fn release(self) -> hal::uart::UART<M> {
+        cortex_m::interrupt::free(|_| unsafe {
+            modify(M::NUMBER, CTRL, |ctrl| {
+                ctrl & !(CTRL_RIE | CTRL_TIE | CTRL_TCIE | CTRL_EIE | CTRL_ILIE)
+            });
+            modify(M::NUMBER, MODIR, |modir| {
+                modir & !(MODIR_TXRTSE | MODIR_TXRTSPOL)
+            });
+            STATES[M::NUMBER as usize - 1].reset();
+        });
+        self.uart
+    }
+
+    /// Signal an idle-line event after the receive line is quiet for at least
+    /// `characters` character times
+    ///
+    /// The LPUART counts idle characters in powers of two, so `characters` rounds
+    /// up to the next power of two, at most 128. Idle time starts after a stop bit.
+    /// The optional `on_idle` callback runs in interrupt context, after the received
+    /// bytes are in the buffer.
+    pub fn enable_idle_detection(&mut self, characters: u8, on_idle: Option<IdleHandler>) {
+        let idlecfg = characters
+            .max(1)
+            .next_power_of_two()
+            .trailing_zeros()
+            .min(7);
+        cortex_m::interrupt::free(|_| unsafe {
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            state.idle = false;
+            state.on_idle = on_idle;
+            modify(M::NUMBER, CTRL, |ctrl| {
+                (ctrl & !CTRL_IDLECFG_MASK) | (idlecfg << CTRL_IDLECFG_SHIFT) | CTRL_ILT | CTRL_ILIE
+            });
+        });
+    }
+
+    /// Stop signaling idle-line events
+    pub fn disable_idle_detection(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            modify(M::NUMBER, CTRL, |ctrl| ctrl & !CTRL_ILIE);
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            state.idle = false;
+            state.on_idle = None;
+        });
+    }
+
+    /// Returns `true` if the receive line went idle since the last call
+    ///
+    /// Use this to learn that a packet is complete. The packet's bytes are available
+    /// through `read()`. Requires [`enable_idle_detection`](#method.enable_idle_detection).
+    pub fn message_received(&mut self) -> bool {
+        cortex_m::interrupt::free(|_| unsafe {
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            let idle = state.idle;
+            state.idle = false;
+            idle
+        })
+    }
+
+    /// Control an RS-485 transceiver's driver while transmitting
+    ///
+    /// `arm_hz` is the ARM core clock frequency, used to time the turnaround delays.
+    /// Call this before writing, or after a [`flush`](#method.flush). A driver-enable
+    /// pin is configured as an output, and driven low.
+    ///
+    /// The turnaround delays busy-wait, partly in the LPUART interrupt. Keep them short.
+    pub fn enable_rs485(&mut self, config: Rs485Config, arm_hz: u32) {
+        let cycles = |us: u32| (arm_hz / 1_000_000).saturating_mul(us);
+        cortex_m::interrupt::free(|_| unsafe {
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            match config.transmit_enable {
+                TransmitEnable::Pin(mut pin) => {
+                    pin.set_output();
+                    pin.clear();
+                    state.driver_enable = Some(DriverEnable {
+                        pin,
+                        pre_cycles: cycles(config.pre_transmit_us),
+                        post_cycles: cycles(config.post_transmit_us),
+                        active: false,
+                    });
+                    modify(M::NUMBER, MODIR, |modir| {
+                        modir & !(MODIR_TXRTSE | MODIR_TXRTSPOL)
+                    });
+                }
+                TransmitEnable::Rts => {
+                    state.driver_enable = None;
+                    modify(M::NUMBER, MODIR, |modir| {
+                        modir | MODIR_TXRTSE | MODIR_TXRTSPOL
+                    });
+                }
+            }
+        });
+    }
+
+    /// Stop controlling an RS-485 transceiver
+    ///
+    /// Returns the driver-enable pin, if there was one. The pin is driven low.
+    pub fn disable_rs485(&mut self) -> Option<DynPin> {
+        cortex_m::interrupt::free(|_| unsafe {
+            modify(M::NUMBER, CTRL, |ctrl| ctrl & !CTRL_TCIE);
+            modify(M::NUMBER, MODIR, |modir| {
+                modir & !(MODIR_TXRTSE | MODIR_TXRTSPOL)
+            });
+            let mut driver_enable = STATES[M::NUMBER as usize - 1].driver_enable.take()?;
+            driver_enable.pin.clear();
+            Some(driver_enable.pin)
+        })
+    }
+
+    /// Queue `words` for transmit, only if they all fit in the transmit buffer
+    fn queue(&mut self, words: &[u16]) -> nb::Result<(), Infallible> {
+        cortex_m::interrupt::free(|_| unsafe {
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            if state.tx.free() < words.len() {
+                return Err(nb::Error::WouldBlock);
+            }
+            for &word in words {
+                state.tx.push(word);
+            }
+            if let Some(driver_enable) = &mut state.driver_enable {
+                if !driver_enable.active {
+                    driver_enable.pin.set();
+                    driver_enable.active = true;
+                    delay(driver_enable.pre_cycles);
+                }
+            }
+            modify(M::NUMBER, CTRL, |ctrl| ctrl | CTRL_TIE);
+            Ok(())
+        })
+    }
+
+    /// Read a received word
+    ///
+    /// Bits 7 through 0 hold the data. In 9-bit mode, bit 8 holds the ninth
+    /// bit, which marks an address. Like `read()`, this reports errors before
+    /// data, and reports breaks in order with the data.
+    pub fn read_word(&mut self) -> nb::Result<u16, Error> {
+        cortex_m::interrupt::free(|_| unsafe {
+            let state = &mut STATES[M::NUMBER as usize - 1];
+            if let Some(&error) = Error::ALL
+                .iter()
+                .find(|error| state.errors & error.mask() != 0)
+            {
+                state.errors &= !error.mask();
+                return Err(nb::Error::Other(error));
+            }
+            match state.rx.pop() {
+                Some(BREAK) => Err(nb::Error::Other(Error::Break)),
+                Some(word) => Ok(word),
+                None => Err(nb::Error::WouldBlock),
+            }
+        })
+    }
+
+    /// Queue an address for transmit, with the ninth bit set
+    ///
+    /// Requires 9-bit mode.
+    pub fn write_address(&mut self, address: u8) -> nb::Result<(), Infallible> {
+        self.queue(&[ADDRESS | address as u16])
+    }
+
+    /// Queue a break character for transmit
+    ///
+    /// The break follows the bytes that are already buffered.
+    pub fn send_break(&mut self) -> nb::Result<(), Infallible> {
+        self.queue(&[BREAK])
+    }
+
+    /// Send 13-bit breaks, and report received breaks as [`Error::Break`](enum.Error.html#variant.Break)
+    ///
+    /// These are the LIN break lengths. While enabled, a received break doesn't
+    /// report a framing error. The receive watermark is set to zero, so that each
+    /// received byte interrupts, and a break is ordered after the bytes before it.
+    pub fn enable_lin(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            modify(M::NUMBER, STAT, |stat| {
+                (stat & STAT_CONFIG) | STAT_LBKDE | STAT_BRK13 | STAT_LBKDIF
+            });
+            modify(M::NUMBER, WATER, |water| water & !WATER_RXWATER_MASK);
+            modify(M::NUMBER, BAUD, |baud| baud | BAUD_LBKDIE);
+        });
+    }
+
+    /// Send 10-bit breaks, and stop detecting breaks
+    pub fn disable_lin(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            modify(M::NUMBER, BAUD, |baud| baud & !BAUD_LBKDIE);
+            modify(M::NUMBER, STAT, |stat| {
+                (stat & STAT_CONFIG & !(STAT_LBKDE | STAT_BRK13)) | STAT_LBKDIF
+            });
+        });
+    }
+
+    /// Queue a LIN header: a break, the sync byte, and the protected identifier for `id`
+    ///
+    /// Returns `WouldBlock` if the header doesn't fit in the transmit buffer.
+    /// Call [`enable_lin`](#method.enable_lin) first.
+    pub fn write_lin_header(&mut self, id: u8) -> nb::Result<(), lin::Error> {
+        let [sync, pid] = lin::header(id).map_err(nb::Error::Other)?;
+        self.queue(&[BREAK, sync as u16, pid as u16])
+            .map_err(|_| nb::Error::WouldBlock)
+    }
+
+    /// Queue a LIN response: the frame's data, and its checksum
+    ///
+    /// Returns `WouldBlock` if the response doesn't fit in the transmit buffer.
+    pub fn write_lin_response(
+        &mut self,
+        frame: &lin::Frame,
+        checksum: lin::Checksum,
+    ) -> nb::Result<(), Infallible> {
+        let mut buffer = [0; lin::MAX_DATA + 1];
+        let response = frame.response(checksum, &mut buffer);
+        let mut words = [0; lin::MAX_DATA + 1];
+        for (word, &byte) in words.iter_mut().zip(response) {
+            *word = byte as u16;
+        }
+        self.queue(&words[..response.len()])
+    }
+
+    /// Select 9-bit data, or return to 8-bit data
+    ///
+    /// The transmitter and receiver are disabled while the data size changes.
+    /// Buffered words may be corrupted, so call this before sending or receiving.
+    pub fn set_nine_bit(&mut self, nine_bit: bool) {
+        cortex_m::interrupt::free(|_| unsafe {
+            let ctrl = ptr::read_volatile(register(M::NUMBER, CTRL));
+            ptr::write_volatile(register(M::NUMBER, CTRL), ctrl & !(CTRL_TE | CTRL_RE));
+            let ctrl = if nine_bit {
+                ctrl | CTRL_M
+            } else {
+                ctrl & !CTRL_M
+            };
+            ptr::write_volatile(register(M::NUMBER, CTRL), ctrl);
+        });
+    }
+
+    /// Only receive traffic that follows `address`, or receive everything if `None`
+    ///
+    /// In 9-bit mode, the LPUART compares each received address to `address`. It
+    /// discards addresses that don't match, and the data that follows them. A matching
+    /// address is received, so that `read_word()` can report it.
+    pub fn set_address_match(&mut self, address: Option<u8>) {
+        cortex_m::interrupt::free(|_| unsafe {
+            match address {
+                Some(address) => {
+                    // The match value includes the address mark
+                    modify(M::NUMBER, MATCH, |ma| {
+                        (ma & !DATA_MASK) | (ADDRESS | address as u16) as u32
+                    });
+                    modify(M::NUMBER, BAUD, |baud| baud | BAUD_MAEN1);
+                }
+                None => modify(M::NUMBER, BAUD, |baud| baud & !BAUD_MAEN1),
+            }
+        });
+    }
+
+    /// Move bytes between the LPUART and the buffers, and record errors and
+    /// idle-line events
+    ///
+    /// The `"serial-interrupts"` feature calls `on_interrupt` for you. Otherwise, call
+    /// `on_interrupt` from the LPUART's interrupt handler.
+    pub fn on_interrupt() {
+        cortex_m::interrupt::free(|_| unsafe { service(M::NUMBER) });
+    }
+}
+
+fn register(number: u8, offset: u32) -> *mut u32 {
+    (LPUART_BASE[number as usize - 1] + offset) as *mut u32
+}
+
+unsafe fn modify(number: u8, offset: u32, f: impl FnOnce(u32) -> u32) {
+    let register = register(number, offset);
+    ptr::write_volatile(register, f(ptr::read_volatile(register)));
+}
+
+/// Service LPUART `number`
+///
+/// # Safety
+///
+/// Call in a critical section, or from the LPUART's interrupt when it can't
+/// be preempted by another user of the LPUART's state.
+unsafe fn service(number: u8) {
+    let state = &mut STATES[number as usize - 1];
+    let stat = ptr::read_volatile(register(number, STAT));
+    ptr::write_volatile(register(number, STAT), stat & (STAT_W1C | STAT_CONFIG));
+    for &(flag, error) in &[
+        (STAT_OR, Error::Overrun),
+        (STAT_FE, Error::Framing),
+        (STAT_PF, Error::Parity),
+        (STAT_NF, Error::Noise),
+    ] {
+        if stat & flag != 0 {
+            state.errors |= error.mask();
+        }
+    }
+
+    // With LIN enabled, each byte received before the break interrupted on its
+    // own, and was drained then, unless this interrupt was held off for longer
+    // than the break. The FIFO holds the bytes received after the break, so push
+    // the break first.
+    if stat & STAT_LBKDIF != 0 && !state.rx.push(BREAK) {
+        state.errors |= Error::BufferFull.mask();
+    }
+
+    // Drain the FIFO, even if it's below the watermark, so that an idle line
+    // sees the whole message.
+    while ptr::read_volatile(register(number, STAT)) & STAT_RDRF != 0
+        || ptr::read_volatile(register(number, WATER)) & WATER_RXCOUNT_MASK != 0
+    {
+        let data = ptr::read_volatile(register(number, DATA));
+        if !state.rx.push((data & DATA_MASK) as u16) {
+            state.errors |= Error::BufferFull.mask();
+        }
+    }
+
+    while ptr::read_volatile(register(number, STAT)) & STAT_TDRE != 0 {
+        match state.tx.pop() {
+            Some(word) => ptr::write_volatile(register(number, DATA), word as u32),
+            None => {
+                modify(number, CTRL, |ctrl| ctrl & !CTRL_TIE);
+                break;
+            }
+        }
+    }
+
+    if let Some(driver_enable) = &mut state.driver_enable {
+        if driver_enable.active && state.tx.is_empty() {
+            if ptr::read_volatile(register(number, STAT)) & STAT_TC != 0 {
+                delay(driver_enable.post_cycles);
+                driver_enable.pin.clear();
+                driver_enable.active = false;
+                modify(number, CTRL, |ctrl| ctrl & !CTRL_TCIE);
+            } else {
+                // Wait for the last stop bit
+                modify(number, CTRL, |ctrl| ctrl | CTRL_TCIE);
+            }
+        }
+    }
+
+    if stat & STAT_IDLE != 0 && ptr::read_volatile(register(number, CTRL)) & CTRL_ILIE != 0 {
+        state.idle = true;
+        if let Some(on_idle) = state.on_idle {
+            on_idle();
+        }
+    }
+}
+
+impl<M: Lpuart> embedded_hal::serial::Read<u8> for Serial<M> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.read_word().map(|word| word as u8)
+    }
+}
+
+impl<M: Lpuart> embedded_hal::serial::Write<u8> for Serial<M> {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.queue(&[byte as u16])
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        // With a driver-enable pin, also wait for the interrupt to release the pin
+        let done = cortex_m::interrupt::free(|_| unsafe {
+            let state = &STATES[M::NUMBER as usize - 1];
+            state.tx.is_empty()
+                && state
+                    .driver_enable
+                    .as_ref()
+                    .map_or(true, |driver_enable| !driver_enable.active)
+        }) && unsafe { ptr::read_volatile(register(M::NUMBER, STAT)) } & STAT_TC != 0;
+        if done {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+#[cfg(feature = "serial-interrupts")]
+mod handlers {
+    use crate::interrupt;
+
+    #[crate::rt::interrupt]
+    fn LPUART1() {
+        unsafe { super::service(1) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART2() {
+        unsafe { super::service(2) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART3() {
+        unsafe { super::service(3) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART4() {
+        unsafe { super::service(4) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART5() {
+        unsafe { super::service(5) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART6() {
+        unsafe { super::service(6) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART7() {
+        unsafe { super::service(7) }
+    }
+
+    #[crate::rt::interrupt]
+    fn LPUART8() {
+        unsafe { super::service(8) }
+    }
+}
@@ -0,0 +1,196 @@
+//! Hobby servos and ESCs on FlexPWM outputs
+//!
+//! A [`Servo`](struct.Servo.html) drives a hobby servo, or an electronic speed
+//! controller (ESC), from a Teensy pin's FlexPWM output. Set the servo's position with
+//! an angle or a pulse width, or set an ESC's throttle. Each `Servo` has its own
+//! [`Calibration`](struct.Calibration.html), which limits the pulses that it produces.
+//!
+//! The BSP re-exports the `teensy4-servo` crate's calibration and timing functions in
+//! this module. See that crate for the pulse math.
+//!
+//! `Servo` builds on the [`pwm`](../pwm/index.html) module, so start the PWM modules with
+//! [`pwm::init`](../pwm/fn.init.html) before creating a `Servo`.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::pwm;
+//! use bsp::servo::{Calibration, Servo};
+//!
+//! let mut p = bsp::Peripherals::take().unwrap();
+//! let clocks = bsp::clocks::Profile::default().configure(&mut p.ccm, &mut p.dcdc);
+//! pwm::init(
+//!     (p.pwm1, p.pwm2, p.pwm3, p.pwm4),
+//!     &mut p.ccm.handle,
+//!     clocks.ipg_hz,
+//! );
+//!
+//! // A 180 degree servo, refreshed at 50Hz
+//! let mut servo = Servo::on_pin(p.pins.p2, 50, Calibration::STANDARD).unwrap();
+//! servo.set_angle(90.0);
+//!
+//! // An ESC that idles at 1.1ms, and runs at full throttle at 1.9ms
+//! let calibration = Calibration::new(1_100, 1_900, 180.0).unwrap();
+//! let mut esc = Servo::on_pin(p.pins.p4, 400, calibration).unwrap();
+//! esc.set_throttle(0.0);
+//! ```
+//!
+//! Any pin that has a FlexPWM output can drive a servo; see
+//! [`flexpwm::output`](../flexpwm/fn.output.html). Servos on pins that share a FlexPWM
+//! submodule, like pins 2 and 3, must use the same frequency.
+
+use crate::{
+    flexpwm,
+    gpio::TeensyPin,
+    pwm::{self, Pwm},
+};
+use embedded_hal::PwmPin;
+
+pub use teensy4_servo::{
+    check_frequency, compare, period_us, pulse_us, Calibration, MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ,
+};
+
+/// Servo errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The PWM output couldn't be created, or its frequency couldn't change
+    Pwm(pwm::Error),
+    /// The frequency doesn't suit the calibration
+    Servo(teensy4_servo::Error),
+}
+
+impl From<pwm::Error> for Error {
+    fn from(error: pwm::Error) -> Self {
+        Error::Pwm(error)
+    }
+}
+
+impl From<teensy4_servo::Error> for Error {
+    fn from(error: teensy4_servo::Error) -> Self {
+        Error::Servo(error)
+    }
+}
+
+/// A servo or ESC on a Teensy pin
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct Servo<P> {
+    pwm: Pwm<P>,
+    frequency_hz: u32,
+    calibration: Calibration,
+    /// The pulse that we're sending, or zero if we're not sending pulses
+    pulse_us: u32,
+}
+
+impl<P: TeensyPin> Servo<P> {
+    /// Drive a servo on `pin`, sending a pulse at `frequency_hz`
+    ///
+    /// The frequency must be between `MIN_FREQUENCY_HZ` and `MAX_FREQUENCY_HZ`, and the
+    /// period must be longer than the calibration's longest pulse. The servo doesn't
+    /// receive pulses until you set its angle, pulse, or throttle.
+    pub fn on_pin(pin: P, frequency_hz: u32, calibration: Calibration) -> Result<Self, Error> {
+        check_frequency(frequency_hz, &calibration)?;
+        let pwm = Pwm::on_pin(pin, frequency_hz)?;
+        Ok(Servo {
+            pwm,
+            frequency_hz,
+            calibration,
+            pulse_us: 0,
+        })
+    }
+
+    /// Returns the Teensy pin number
+    pub fn pin(&self) -> u8 {
+        self.pwm.pin()
+    }
+
+    /// Returns the servo's calibration
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Change the servo's calibration
+    ///
+    /// If the servo is receiving pulses, the pulse is clamped to the new calibration.
+    pub fn set_calibration(&mut self, calibration: Calibration) -> Result<(), Error> {
+        check_frequency(self.frequency_hz, &calibration)?;
+        self.calibration = calibration;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Returns the switching frequency, in Hz
+    pub fn frequency_hz(&self) -> u32 {
+        self.frequency_hz
+    }
+
+    /// Change the switching frequency
+    ///
+    /// Returns an error if the frequency doesn't suit the calibration, or if another
+    /// `Pwm` or `Servo` on the same submodule uses a different frequency.
+    pub fn set_frequency(&mut self, frequency_hz: u32) -> Result<(), Error> {
+        check_frequency(frequency_hz, &self.calibration)?;
+        self.pwm.set_frequency(frequency_hz)?;
+        self.frequency_hz = frequency_hz;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Send pulses that are `pulse_us` microseconds wide
+    ///
+    /// `pulse_us` is clamped to the calibration.
+    pub fn set_pulse_us(&mut self, pulse_us: u32) {
+        self.pulse_us = self.calibration.clamp(pulse_us);
+        let modulo = flexpwm::modulo(self.pwm.output());
+        let duty = compare(self.pulse_us, self.frequency_hz, modulo);
+        PwmPin::set_duty(&mut self.pwm, duty);
+    }
+
+    /// Returns the width of the pulses, in microseconds, or `None` if the
+    /// servo isn't receiving pulses
+    pub fn pulse_us(&self) -> Option<u32> {
+        Some(self.pulse_us).filter(|&pulse_us| pulse_us != 0)
+    }
+
+    /// Move the servo to `angle_deg`
+    ///
+    /// The angle saturates at zero and the calibration's range.
+    pub fn set_angle(&mut self, angle_deg: f32) {
+        self.set_pulse_us(self.calibration.angle_to_pulse_us(angle_deg));
+    }
+
+    /// Returns the servo's angle, in degrees, or `None` if the servo isn't
+    /// receiving pulses
+    pub fn angle(&self) -> Option<f32> {
+        self.pulse_us()
+            .map(|pulse_us| self.calibration.pulse_us_to_angle(pulse_us))
+    }
+
+    /// Run an ESC at `throttle`, a fraction from 0 (idle) to 1 (full throttle)
+    ///
+    /// The throttle saturates at 0 and 1. Many ESCs arm after they receive an
+    /// idle throttle for a few seconds.
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.set_pulse_us(self.calibration.throttle_to_pulse_us(throttle));
+    }
+
+    /// Stop sending pulses
+    ///
+    /// The output stays low. Most servos stop holding their position, and most
+    /// ESCs disarm.
+    pub fn stop(&mut self) {
+        self.pulse_us = 0;
+        PwmPin::set_duty(&mut self.pwm, 0);
+    }
+
+    /// Stop sending pulses, and return the pin
+    pub fn release(self) -> P {
+        self.pwm.release()
+    }
+
+    /// Re-send the current pulse after a calibration or frequency change
+    fn refresh(&mut self) {
+        if let Some(pulse_us) = self.pulse_us() {
+            self.set_pulse_us(pulse_us);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Single-call board initialization
+//!
+//! [`Board::init`](struct.Board.html#method.init) takes the BSP peripherals, sets the
+//! core clock, optionally starts USB logging, and prepares the LED. It returns a
+//! [`Board`](struct.Board.html) with the remaining peripherals, and builders for the
+//! UART, SPI, and I2C peripherals. The builders are named by the Teensy pins that they
+//! use, and they enable each peripheral's clock when it's first built.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let mut uart = board
+//!     .uart
+//!     .p14_p15(&mut board.ccm.handle, board.pins.p14, board.pins.p15, 115_200)
+//!     .unwrap();
+//! let mut i2c = board
+//!     .i2c
+//!     .p19_p18(
+//!         &mut board.ccm.handle,
+//!         board.pins.p19,
+//!         board.pins.p18,
+//!         bsp::hal::i2c::ClockSpeed::KHz400,
+//!     )
+//!     .unwrap();
+//! loop {
+//!     board.led.toggle();
+//!     board.delay.delay(250);
+//! }
+//! ```
+//!
+//! The UART, SPI, and I2C clock selections match the BSP examples. If you need other
+//! clock selections, construct the peripherals from [`Peripherals`](../struct.Peripherals.html).
+
+use crate::{clocks, gpio::DynPin, hal, BoardPins, Peripherals};
+use hal::iomuxc::{gpio::*, Alt5};
+
+/// Board configuration
+pub struct BoardConfig {
+    /// The core clock profile. Defaults to 600MHz.
+    pub clock: clocks::Profile,
+    /// USB logging configuration. If `None`, the BSP does not start USB logging.
+    /// Defaults to `Some(Default::default())`.
+    #[cfg(feature = "usb-logging")]
+    pub usb_logging: Option<crate::usb::LoggingConfig>,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            clock: clocks::Profile::default(),
+            #[cfg(feature = "usb-logging")]
+            usb_logging: Some(Default::default()),
+        }
+    }
+}
+
+/// An initialized board
+///
+/// See the [module-level documentation](index.html) for more information.
+#[non_exhaustive]
+pub struct Board {
+    /// The clock frequencies selected by `BoardConfig::clock`
+    pub clocks: clocks::Clocks,
+    /// The LED on pin 13, configured as an output
+    ///
+    /// The LED shares pin 13 with SPI4's clock. To use SPI4, give the LED to
+    /// [`Spis::p11_p12_p13`](struct.Spis.html#method.p11_p12_p13).
+    pub led: DynPin,
+    /// The SysTick delay timer
+    #[cfg(feature = "systick")]
+    pub delay: crate::SysTick,
+    /// The USB serial reader, if USB logging was enabled
+    #[cfg(feature = "usb-logging")]
+    pub usb_reader: Option<crate::usb::Reader>,
+    /// Teensy pins, except for pin 13, which is the LED
+    pub pins: BoardPins,
+    /// Teensy 4.1 Ethernet PHY pads
+    #[cfg(feature = "teensy41")]
+    pub enet_pins: crate::EthernetPins,
+    /// UART builders
+    pub uart: Uarts,
+    /// SPI builders
+    pub spi: Spis,
+    /// I2C builders
+    pub i2c: I2cs,
+    /// Clock control module. The ARM clock is already set.
+    pub ccm: hal::ccm::CCM,
+    /// PIT timers
+    pub pit: hal::pit::UnclockedPIT,
+    /// DCDC converters
+    pub dcdc: hal::dcdc::DCDC,
+    /// PWM1 controller
+    pub pwm1: hal::pwm::Unclocked<hal::pwm::module::_1>,
+    /// PWM2 controller
+    pub pwm2: hal::pwm::Unclocked<hal::pwm::module::_2>,
+    /// PWM3 controller
+    pub pwm3: hal::pwm::Unclocked<hal::pwm::module::_3>,
+    /// PWM4 controller
+    pub pwm4: hal::pwm::Unclocked<hal::pwm::module::_4>,
+    /// ADC1
+    pub adc1: crate::adc::Unclocked<crate::adc::ADC1>,
+    /// ADC2
+    pub adc2: crate::adc::Unclocked<crate::adc::ADC2>,
+    /// General purpose registers, used when configuring GPIO pins.
+    pub gpr: hal::iomuxc::GPR,
+    /// General purpose timer 1
+    pub gpt1: hal::gpt::Unclocked,
+    /// General purpose timer 2
+    ///
+    /// With the `"rtic"` feature, GPT2 is the `monotonic` timer.
+    #[cfg(not(feature = "rtic"))]
+    pub gpt2: hal::gpt::Unclocked,
+    /// DMA channels
+    pub dma: hal::dma::Unclocked,
+}
+
+impl Board {
+    /// Take and initialize the board's peripherals. This may only be called once!
+    ///
+    /// Returns `None` if the peripherals were already taken, either by this
+    /// function or by [`Peripherals::take`](../struct.Peripherals.html#method.take).
+    pub fn init(config: BoardConfig) -> Option<Self> {
+        let mut p = Peripherals::take()?;
+        let clocks = config.clock.configure(&mut p.ccm, &mut p.dcdc);
+        #[cfg(feature = "usb-logging")]
+        let usb_reader = config.usb_logging.map(|logging| p.usb.init(logging));
+        let (p13, pins) = p.pins.split_led();
+        // Safety: we own the GPR, so nothing else is changing the fast GPIO
+        // selection. `Spis` keeps the pin 13 pad, and only hands it to SPI4
+        // in exchange for the LED.
+        let mut led = unsafe { DynPin::steal(13) }.unwrap();
+        led.set_output();
+        Some(Board {
+            clocks,
+            led,
+            #[cfg(feature = "systick")]
+            delay: p.systick,
+            #[cfg(feature = "usb-logging")]
+            usb_reader,
+            pins,
+            #[cfg(feature = "teensy41")]
+            enet_pins: p.enet_pins,
+            uart: Uarts::new(p.uart),
+            spi: Spis::new(p.spi, p13),
+            i2c: I2cs::new(p.i2c),
+            ccm: p.ccm,
+            pit: p.pit,
+            dcdc: p.dcdc,
+            pwm1: p.pwm1,
+            pwm2: p.pwm2,
+            pwm3: p.pwm3,
+            pwm4: p.pwm4,
+            adc1: p.adc1,
+            adc2: p.adc2,
+            gpr: p.gpr,
+            gpt1: p.gpt1,
+            #[cfg(not(feature = "rtic"))]
+            gpt2: p.gpt2,
+            dma: p.dma,
+        })
+    }
+}
+
+/// UART builders, named by their TX and RX pins
+///
+/// The first build enables the UART clocks, using the 24MHz oscillator. Each
+/// builder returns `None` if its UART was already built, or if the baud rate
+/// isn't possible.
+pub struct Uarts {
+    unclocked: Option<hal::uart::Unclocked>,
+    uart1: Option<hal::uart::Uninit<hal::uart::module::_1>>,
+    uart2: Option<hal::uart::Uninit<hal::uart::module::_2>>,
+    uart3: Option<hal::uart::Uninit<hal::uart::module::_3>>,
+    uart4: Option<hal::uart::Uninit<hal::uart::module::_4>>,
+    #[cfg(feature = "teensy41")]
+    uart5: Option<hal::uart::Uninit<hal::uart::module::_5>>,
+    uart6: Option<hal::uart::Uninit<hal::uart::module::_6>>,
+    uart7: Option<hal::uart::Uninit<hal::uart::module::_7>>,
+    uart8: Option<hal::uart::Uninit<hal::uart::module::_8>>,
+}
+
+/// Defines a `Uarts` builder for a TX and RX pin pair, muxed to the UART with `$alt`
+macro_rules! uart_builder {
+    ($(#[$doc:meta] $method:ident => $uart:ident ($module:ident), $tx:ident, $rx:ident, $alt:ident;)+) => {
+        $(
+            #[$doc]
+            pub fn $method(
+                &mut self,
+                ccm: &mut hal::ccm::Handle,
+                tx: $tx<Alt5>,
+                rx: $rx<Alt5>,
+                baud: u32,
+            ) -> Option<hal::uart::UART<hal::uart::module::$module>> {
+                self.clock(ccm);
+                self.$uart.take()?.init(tx.$alt(), rx.$alt(), baud).ok()
+            }
+        )+
+    };
+}
+
+impl Uarts {
+    fn new(unclocked: hal::uart::Unclocked) -> Self {
+        Uarts {
+            unclocked: Some(unclocked),
+            uart1: None,
+            uart2: None,
+            uart3: None,
+            uart4: None,
+            #[cfg(feature = "teensy41")]
+            uart5: None,
+            uart6: None,
+            uart7: None,
+            uart8: None,
+        }
+    }
+
+    fn clock(&mut self, ccm: &mut hal::ccm::Handle) {
+        if let Some(unclocked) = self.unclocked.take() {
+            let uarts = unclocked.clock(
+                ccm,
+                hal::ccm::uart::ClockSelect::OSC,
+                hal::ccm::uart::PrescalarSelect::DIVIDE_1,
+            );
+            self.uart1 = Some(uarts.uart1);
+            self.uart2 = Some(uarts.uart2);
+            self.uart3 = Some(uarts.uart3);
+            self.uart4 = Some(uarts.uart4);
+            #[cfg(feature = "teensy41")]
+            {
+                self.uart5 = Some(uarts.uart5);
+            }
+            self.uart6 = Some(uarts.uart6);
+            self.uart7 = Some(uarts.uart7);
+            self.uart8 = Some(uarts.uart8);
+        }
+    }
+
+    uart_builder! {
+        /// UART6, transmitting on pin 1 and receiving on pin 0
+        p1_p0 => uart6 (_6), GPIO_AD_B0_02, GPIO_AD_B0_03, alt2;
+        /// UART4, transmitting on pin 8 and receiving on pin 7
+        p8_p7 => uart4 (_4), GPIO_B1_00, GPIO_B1_01, alt2;
+        /// UART2, transmitting on pin 14 and receiving on pin 15
+        p14_p15 => uart2 (_2), GPIO_AD_B1_02, GPIO_AD_B1_03, alt2;
+        /// UART3, transmitting on pin 17 and receiving on pin 16
+        p17_p16 => uart3 (_3), GPIO_AD_B1_06, GPIO_AD_B1_07, alt2;
+        /// UART8, transmitting on pin 20 and receiving on pin 21
+        p20_p21 => uart8 (_8), GPIO_AD_B1_10, GPIO_AD_B1_11, alt2;
+        /// UART1, transmitting on pin 24 and receiving on pin 25
+        p24_p25 => uart1 (_1), GPIO_AD_B0_12, GPIO_AD_B0_13, alt2;
+        /// UART7, transmitting on pin 29 and receiving on pin 28
+        p29_p28 => uart7 (_7), GPIO_EMC_31, GPIO_EMC_32, alt2;
+    }
+
+    #[cfg(feature = "teensy41")]
+    uart_builder! {
+        /// UART5, transmitting on pin 35 and receiving on pin 34. Teensy 4.1 only
+        p35_p34 => uart5 (_5), GPIO_B1_12, GPIO_B1_13, alt1;
+    }
+}
+
+/// The SPI source clock: PLL2 (528MHz), divided by 6
+const SPI_CLOCK_HZ: u32 = 528_000_000 / 6;
+/// SCK divides the source clock by `SCKDIV + 2`, and `SCKDIV` is 8 bits
+const SPI_SCK_HZ: core::ops::RangeInclusive<u32> = (SPI_CLOCK_HZ / 257)..=(SPI_CLOCK_HZ / 2);
+
+/// SPI builders, named by their SDO, SDI, and SCK pins
+///
+/// The first build enables the SPI clocks, using PLL2. SPI4 is the only SPI
+/// peripheral with a builder. SPI3, on pins 26, 1, and 27, is out of scope; to
+/// use it, construct it from [`Peripherals`](../struct.Peripherals.html).
+pub struct Spis {
+    unclocked: Option<hal::spi::Unclocked>,
+    spi4: Option<hal::spi::Builder<hal::spi::module::_4>>,
+    /// The pin 13 pad, owned by the LED until SPI4 is built
+    p13: Option<GPIO_B0_03<Alt5>>,
+}
+
+impl Spis {
+    fn new(unclocked: hal::spi::Unclocked, p13: GPIO_B0_03<Alt5>) -> Self {
+        Spis {
+            unclocked: Some(unclocked),
+            spi4: None,
+            p13: Some(p13),
+        }
+    }
+
+    fn clock(&mut self, ccm: &mut hal::ccm::Handle) {
+        if let Some(unclocked) = self.unclocked.take() {
+            let (_, _, _, spi4) = unclocked.clock(
+                ccm,
+                hal::ccm::spi::ClockSelect::Pll2,
+                hal::ccm::spi::PrescalarSelect::LPSPI_PODF_5,
+            );
+            self.spi4 = Some(spi4);
+        }
+    }
+
+    /// SPI4, with SDO on pin 11, SDI on pin 12, and SCK on pin 13
+    ///
+    /// Pin 13 is also the LED, so this consumes the board's LED. Once SPI4 is built,
+    /// the LED shows SPI4's clock.
+    ///
+    /// Returns the LED if `led` isn't pin 13, if SPI4 was already built, or if the
+    /// clock speed isn't possible. The LED is still an output, and SPI4 is still
+    /// available.
+    pub fn p11_p12_p13(
+        &mut self,
+        ccm: &mut hal::ccm::Handle,
+        sdo: GPIO_B0_02<Alt5>,
+        sdi: GPIO_B0_01<Alt5>,
+        led: DynPin,
+        clock_hz: u32,
+    ) -> Result<hal::spi::SPI<hal::spi::module::_4>, DynPin> {
+        if led.pin() != 13 || self.p13.is_none() || !SPI_SCK_HZ.contains(&clock_hz) {
+            return Err(led);
+        }
+        self.clock(ccm);
+        let (sck, spi4) = match (self.p13.take(), self.spi4.take()) {
+            (Some(sck), Some(spi4)) => (sck, spi4),
+            (sck, spi4) => {
+                self.p13 = sck;
+                self.spi4 = spi4;
+                return Err(led);
+            }
+        };
+        let mut spi = spi4.build(sdo.alt3(), sdi.alt3(), sck.alt3());
+        // SPI4 was just built, so it is idle, and the speed is in range
+        let _ = spi.set_clock_speed(hal::spi::ClockSpeed(clock_hz));
+        Ok(spi)
+    }
+}
+
+/// I2C builders, named by their SCL and SDA pins
+///
+/// The first build enables the I2C clocks, using the 24MHz oscillator. Each
+/// builder returns `None` if its I2C peripheral was already built, or if the
+/// clock speed isn't possible.
+pub struct I2cs {
+    unclocked: Option<hal::i2c::Unclocked>,
+    i2c1: Option<hal::i2c::Builder<hal::i2c::module::_1>>,
+    i2c3: Option<hal::i2c::Builder<hal::i2c::module::_3>>,
+    i2c4: Option<hal::i2c::Builder<hal::i2c::module::_4>>,
+}
+
+impl I2cs {
+    fn new(unclocked: hal::i2c::Unclocked) -> Self {
+        I2cs {
+            unclocked: Some(unclocked),
+            i2c1: None,
+            i2c3: None,
+            i2c4: None,
+        }
+    }
+
+    fn clock(&mut self, ccm: &mut hal::ccm::Handle) {
+        if let Some(unclocked) = self.unclocked.take() {
+            let (i2c1, _, i2c3, i2c4) = unclocked.clock(
+                ccm,
+                hal::ccm::i2c::ClockSelect::OSC,
+                hal::ccm::i2c::PrescalarSelect::DIVIDE_3,
+            );
+            self.i2c1 = Some(i2c1);
+            self.i2c3 = Some(i2c3);
+            self.i2c4 = Some(i2c4);
+        }
+    }
+
+    /// I2C1, with SCL on pin 19 and SDA on pin 18
+    pub fn p19_p18(
+        &mut self,
+        ccm: &mut hal::ccm::Handle,
+        scl: GPIO_AD_B1_00<Alt5>,
+        sda: GPIO_AD_B1_01<Alt5>,
+        speed: hal::i2c::ClockSpeed,
+    ) -> Option<hal::i2c::I2C<hal::i2c::module::_1>> {
+        self.clock(ccm);
+        let mut i2c = self.i2c1.take()?.build(scl.alt3(), sda.alt3());
+        i2c.set_clock_speed(speed).ok()?;
+        Some(i2c)
+    }
+
+    /// I2C3, with SCL on pin 16 and SDA on pin 17
+    pub fn p16_p17(
+        &mut self,
+        ccm: &mut hal::ccm::Handle,
+        scl: GPIO_AD_B1_07<Alt5>,
+        sda: GPIO_AD_B1_06<Alt5>,
+        speed: hal::i2c::ClockSpeed,
+    ) -> Option<hal::i2c::I2C<hal::i2c::module::_3>> {
+        self.clock(ccm);
+        let mut i2c = self.i2c3.take()?.build(scl.alt1(), sda.alt1());
+        i2c.set_clock_speed(speed).ok()?;
+        Some(i2c)
+    }
+
+    /// I2C4, with SCL on pin 24 and SDA on pin 25
+    pub fn p24_p25(
+        &mut self,
+        ccm: &mut hal::ccm::Handle,
+        scl: GPIO_AD_B0_12<Alt5>,
+        sda: GPIO_AD_B0_13<Alt5>,
+        speed: hal::i2c::ClockSpeed,
+    ) -> Option<hal::i2c::I2C<hal::i2c::module::_4>> {
+        self.clock(ccm);
+        let mut i2c = self.i2c4.take()?.build(scl.alt0(), sda.alt0());
+        i2c.set_clock_speed(speed).ok()?;
+        Some(i2c)
+    }
+}
@@ -0,0 +1,422 @@
+//! Shared SPI buses
+//!
+//! A [`SpiBus`](struct.SpiBus.html) owns an SPI peripheral, and hands out a
+//! [`SpiDevice`](struct.SpiDevice.html) for each device on the bus. Each device has its
+//! own GPIO chip select, SPI mode, and clock speed. A device reconfigures the bus, if
+//! needed, and asserts its chip select for each transfer.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::{gpio::DynPin, spi::SpiBus};
+//! use embedded_hal::{blocking::spi::Transfer, spi::MODE_0, spi::MODE_3};
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let spi4 = board
+//!     .spi
+//!     .p11_p12_p13(
+//!         &mut board.ccm.handle,
+//!         board.pins.p11,
+//!         board.pins.p12,
+//!         board.led,
+//!         1_000_000,
+//!     )
+//!     .unwrap();
+//! let bus: &'static SpiBus<_> =
+//!     cortex_m::singleton!(: SpiBus<bsp::hal::spi::module::_4> = SpiBus::new(spi4)).unwrap();
+//!
+//! let mut imu = bus.device(DynPin::new(board.pins.p10, &mut board.gpr), MODE_3, 1_000_000);
+//! let mut flash = bus.device(DynPin::new(board.pins.p9, &mut board.gpr), MODE_0, 10_000_000);
+//!
+//! let mut who_am_i = [0x75 | 0x80, 0];
+//! imu.transfer(&mut who_am_i).unwrap();
+//! let mut jedec_id = [0x9F, 0, 0, 0];
+//! flash.transfer(&mut jedec_id).unwrap();
+//! ```
+//!
+//! Use [`transaction`](struct.SpiDevice.html#method.transaction) to hold the chip select
+//! across more than one transfer.
+//!
+//! ## Sharing
+//!
+//! The bus lock is a critical section. Devices on a `&'static SpiBus` may move into
+//! interrupt handlers, or into RTIC resources owned by tasks of any priority, and each
+//! transfer is safe from preemption. Interrupts are disabled for the whole transfer, so
+//! keep transfers short when there are time-critical interrupts.
+//!
+//! ## Target mode
+//!
+//! A [`SpiTarget`](struct.SpiTarget.html) lets the Teensy act as an SPI target (slave) on
+//! SPI4, so that another controller can use it as a co-processor. Wire the controller to
+//!
+//! | Teensy pin | Controller |
+//! | ---------- | ---------- |
+//! | 10         | CS         |
+//! | 11         | MOSI       |
+//! | 12         | MISO       |
+//! | 13         | SCK        |
+//!
+//! `SpiTarget` is a DMA source and destination. Use it with `hal::dma::Peripheral`, and
+//! start the receive and transfer before the controller asserts CS. The LPSPI4 interrupt
+//! signals the end of each CS-framed transaction; see
+//! [`on_interrupt`](struct.SpiTarget.html#method.on_interrupt).
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::{hal::dma, spi::SpiTarget};
+//!
+//! static RX: dma::Buffer<[u8; 64]> = dma::Buffer::new([0; 64]);
+//! static TX: dma::Buffer<[u8; 64]> = dma::Buffer::new([0; 64]);
+//!
+//! let mut board = bsp::Board::init(bsp::BoardConfig::default()).unwrap();
+//! let spi4 = board
+//!     .spi
+//!     .p11_p12_p13(
+//!         &mut board.ccm.handle,
+//!         board.pins.p11,
+//!         board.pins.p12,
+//!         board.led,
+//!         1_000_000,
+//!     )
+//!     .unwrap();
+//! let target = SpiTarget::new(spi4, board.pins.p10, embedded_hal::spi::MODE_0);
+//!
+//! let mut channels = board.dma.clock(&mut board.ccm.handle);
+//! let config = dma::ConfigBuilder::new().build();
+//! let mut peripheral = dma::Peripheral::new_bidirectional(
+//!     target,
+//!     (channels[8].take().unwrap(), config),
+//!     (channels[24].take().unwrap(), config),
+//! );
+//! peripheral.start_receive(dma::Linear::new(&RX).unwrap()).unwrap();
+//! peripheral.start_transfer(dma::Linear::new(&TX).unwrap()).unwrap();
+//! unsafe { cortex_m::peripheral::NVIC::unmask(SpiTarget::INTERRUPT) };
+//! ```
+
+use crate::{gpio::DynPin, hal, interrupt};
+use core::{cell::RefCell, convert::Infallible, ptr};
+use cortex_m::interrupt::Mutex;
+use embedded_hal::{blocking::spi, spi::Mode};
+
+/// A shared-bus error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The SPI peripheral doesn't support the device's mode
+    Mode,
+    /// The SPI peripheral couldn't produce the device's clock speed
+    ClockSpeed,
+    /// The transfer failed
+    Spi(E),
+}
+
+/// The SPI peripheral, and its current configuration
+struct Inner<M> {
+    spi: hal::spi::SPI<M>,
+    /// `None` until a device selects a mode
+    mode: Option<Mode>,
+    clock_hz: Option<u32>,
+}
+
+/// An SPI bus that's shared by devices
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct SpiBus<M> {
+    inner: Mutex<RefCell<Inner<M>>>,
+}
+
+impl<M> SpiBus<M> {
+    /// Share an SPI peripheral
+    pub fn new(spi: hal::spi::SPI<M>) -> Self {
+        SpiBus {
+            inner: Mutex::new(RefCell::new(Inner {
+                spi,
+                mode: None,
+                clock_hz: None,
+            })),
+        }
+    }
+
+    /// Create a device on the bus, selected by the `cs` pin
+    ///
+    /// The chip select is active low. `device` configures `cs` as an output, and
+    /// drives it high. The device's `mode` and `clock_hz` apply to each of its transfers.
+    pub fn device(&self, mut cs: DynPin, mode: Mode, clock_hz: u32) -> SpiDevice<'_, M> {
+        cs.set();
+        cs.set_output();
+        SpiDevice {
+            bus: self,
+            cs,
+            mode,
+            clock_hz,
+        }
+    }
+
+    /// Return the SPI peripheral
+    ///
+    /// Requires that all devices were released, since they borrow the bus.
+    pub fn release(self) -> hal::spi::SPI<M> {
+        self.inner.into_inner().into_inner().spi
+    }
+}
+
+/// A device on a shared SPI bus
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct SpiDevice<'a, M> {
+    bus: &'a SpiBus<M>,
+    cs: DynPin,
+    mode: Mode,
+    clock_hz: u32,
+}
+
+impl<'a, M> SpiDevice<'a, M> {
+    /// Lock the bus, configure it for this device, and assert the chip select
+    /// while `f` uses the SPI peripheral
+    ///
+    /// The chip select is released after `f` returns, even if `f` fails.
+    pub fn transaction<R, E>(
+        &mut self,
+        f: impl FnOnce(&mut hal::spi::SPI<M>) -> Result<R, E>,
+    ) -> Result<R, Error<E>> {
+        let (bus, cs, mode, clock_hz) = (self.bus, &mut self.cs, self.mode, self.clock_hz);
+        cortex_m::interrupt::free(|token| {
+            let mut inner = bus.inner.borrow(token).borrow_mut();
+            if inner.mode != Some(mode) {
+                inner.mode = None;
+                inner.spi.set_mode(mode).map_err(|_| Error::Mode)?;
+                inner.mode = Some(mode);
+            }
+            if inner.clock_hz != Some(clock_hz) {
+                inner.clock_hz = None;
+                inner
+                    .spi
+                    .set_clock_speed(hal::spi::ClockSpeed(clock_hz))
+                    .map_err(|_| Error::ClockSpeed)?;
+                inner.clock_hz = Some(clock_hz);
+            }
+            cs.clear();
+            let result = f(&mut inner.spi).map_err(Error::Spi);
+            cs.set();
+            result
+        })
+    }
+
+    /// Returns the device's SPI mode
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Change the device's SPI mode, used for its next transfer
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Returns the device's clock speed, in Hz
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    /// Change the device's clock speed, used for its next transfer
+    pub fn set_clock_hz(&mut self, clock_hz: u32) {
+        self.clock_hz = clock_hz;
+    }
+
+    /// Return the chip select pin
+    pub fn release(self) -> DynPin {
+        self.cs
+    }
+}
+
+impl<'a, M> spi::Transfer<u8> for SpiDevice<'a, M>
+where
+    hal::spi::SPI<M>: spi::Transfer<u8>,
+{
+    type Error = Error<<hal::spi::SPI<M> as spi::Transfer<u8>>::Error>;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.transaction(|spi| spi.transfer(words).map(|_| ()))?;
+        Ok(words)
+    }
+}
+
+impl<'a, M> spi::Write<u8> for SpiDevice<'a, M>
+where
+    hal::spi::SPI<M>: spi::Write<u8>,
+{
+    type Error = Error<<hal::spi::SPI<M> as spi::Write<u8>>::Error>;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.transaction(|spi| spi.write(words))
+    }
+}
+
+const LPSPI4_BASE: u32 = 0x403A_0000;
+const CR: u32 = 0x10;
+const SR: u32 = 0x14;
+const IER: u32 = 0x18;
+const DER: u32 = 0x1C;
+const CFGR1: u32 = 0x24;
+const FCR: u32 = 0x58;
+const TCR: u32 = 0x60;
+const TDR: u32 = 0x64;
+const RDR: u32 = 0x74;
+
+const CR_MEN: u32 = 1 << 0;
+const CR_RST: u32 = 1 << 1;
+const CR_RTF: u32 = 1 << 8;
+const CR_RRF: u32 = 1 << 9;
+
+const SR_FCF: u32 = 1 << 9;
+const SR_TEF: u32 = 1 << 11;
+const SR_REF: u32 = 1 << 12;
+/// Write-1-to-clear status flags
+const SR_W1C: u32 = 0x3F << 8;
+
+const DER_TDDE: u32 = 1 << 0;
+const DER_RDDE: u32 = 1 << 1;
+
+/// SOUT (pin 11) is the input, and SIN (pin 12) is the output
+const CFGR1_PINCFG_SWAPPED: u32 = 0b11 << 24;
+
+const TCR_CPOL: u32 = 1 << 31;
+const TCR_CPHA: u32 = 1 << 30;
+/// Eight bit frames
+const TCR_FRAMESZ_8: u32 = 7;
+
+const LPSPI4_DMA_RX: u32 = 79;
+const LPSPI4_DMA_TX: u32 = 80;
+
+/// Events at the end of an SPI target transaction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TargetStatus {
+    /// The controller negated CS, ending a transaction
+    pub frame_complete: bool,
+    /// The controller clocked data while the transmit FIFO was empty
+    pub underrun: bool,
+    /// The receive FIFO overflowed, and data was lost
+    pub overrun: bool,
+}
+
+/// An SPI target (slave) on SPI4
+///
+/// See the [module-level documentation](index.html) for more information.
+pub struct SpiTarget {
+    spi: hal::spi::SPI<hal::spi::module::_4>,
+}
+
+impl SpiTarget {
+    /// The LPSPI4 interrupt, which signals the end of a transaction
+    pub const INTERRUPT: interrupt = interrupt::LPSPI4;
+
+    /// Turn SPI4 into a target, selected by pin 10, using SPI `mode`
+    ///
+    /// The target uses eight-bit frames. Enables the end-of-transaction and error
+    /// interrupts. You're responsible for unmasking `INTERRUPT` in the NVIC.
+    pub fn new(
+        mut spi: hal::spi::SPI<hal::spi::module::_4>,
+        pcs0: hal::iomuxc::gpio::GPIO_B0_00<hal::iomuxc::Alt5>,
+        mode: Mode,
+    ) -> Self {
+        spi.enable_chip_select_0(pcs0.alt3());
+        let mut tcr = TCR_FRAMESZ_8;
+        if mode.polarity == embedded_hal::spi::Polarity::IdleHigh {
+            tcr |= TCR_CPOL;
+        }
+        if mode.phase == embedded_hal::spi::Phase::CaptureOnSecondTransition {
+            tcr |= TCR_CPHA;
+        }
+        cortex_m::interrupt::free(|_| unsafe {
+            // Reset clears the controller configuration
+            write(CR, CR_RST);
+            write(CR, 0);
+            write(CFGR1, CFGR1_PINCFG_SWAPPED);
+            write(TCR, tcr);
+            // Request DMA for each received byte, and whenever there's room to transmit
+            write(FCR, 0);
+            write(SR, SR_W1C);
+            write(IER, SR_FCF | SR_TEF | SR_REF);
+            write(CR, CR_MEN | CR_RTF | CR_RRF);
+        });
+        SpiTarget { spi }
+    }
+
+    /// Read and clear the target's status flags
+    ///
+    /// Call `on_interrupt` from the LPSPI4 interrupt handler. When a frame completes,
+    /// the DMA receive holds the transaction's data. Restart the receive and transfer
+    /// for the next transaction.
+    pub fn on_interrupt() -> TargetStatus {
+        unsafe {
+            let sr = read(SR);
+            write(SR, sr & SR_W1C);
+            TargetStatus {
+                frame_complete: sr & SR_FCF != 0,
+                underrun: sr & SR_TEF != 0,
+                overrun: sr & SR_REF != 0,
+            }
+        }
+    }
+
+    /// Flush the transmit and receive FIFOs
+    ///
+    /// Use this between transactions, to discard bytes that were queued but not sent.
+    pub fn clear_fifos(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            write(CR, read(CR) | CR_RTF | CR_RRF);
+        });
+    }
+
+    /// Disable the target, and return the SPI peripheral
+    ///
+    /// The SPI peripheral is reset. Rebuild it to use it as a controller.
+    pub fn release(self) -> hal::spi::SPI<hal::spi::module::_4> {
+        cortex_m::interrupt::free(|_| unsafe {
+            write(IER, 0);
+            write(DER, 0);
+            write(CR, CR_RST);
+            write(CR, 0);
+        });
+        self.spi
+    }
+}
+
+unsafe fn read(offset: u32) -> u32 {
+    ptr::read_volatile((LPSPI4_BASE + offset) as *const u32)
+}
+
+unsafe fn write(offset: u32, value: u32) {
+    ptr::write_volatile((LPSPI4_BASE + offset) as *mut u32, value)
+}
+
+unsafe fn modify(offset: u32, f: impl FnOnce(u32) -> u32) {
+    write(offset, f(read(offset)))
+}
+
+unsafe impl hal::dma::peripheral::Source<u8> for SpiTarget {
+    type Error = Infallible;
+    const SOURCE_REQUEST_SIGNAL: u32 = LPSPI4_DMA_RX;
+    fn source(&self) -> *const u8 {
+        (LPSPI4_BASE + RDR) as *const u8
+    }
+    fn enable_source(&mut self) -> Result<(), Self::Error> {
+        cortex_m::interrupt::free(|_| unsafe { modify(DER, |der| der | DER_RDDE) });
+        Ok(())
+    }
+    fn disable_source(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe { modify(DER, |der| der & !DER_RDDE) });
+    }
+}
+
+unsafe impl hal::dma::peripheral::Destination<u8> for SpiTarget {
+    type Error = Infallible;
+    const DESTINATION_REQUEST_SIGNAL: u32 = LPSPI4_DMA_TX;
+    fn destination(&self) -> *const u8 {
+        (LPSPI4_BASE + TDR) as *const u8
+    }
+    fn enable_destination(&mut self) -> Result<(), Self::Error> {
+        cortex_m::interrupt::free(|_| unsafe { modify(DER, |der| der | DER_TDDE) });
+        Ok(())
+    }
+    fn disable_destination(&mut self) {
+        cortex_m::interrupt::free(|_| unsafe { modify(DER, |der| der & !DER_TDDE) });
+    }
+}
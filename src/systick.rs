@@ -1,20 +1,36 @@
 //! System tick and delay support
 //!
 //! If we're compiling this module, it's because the `"systick"` feature
-//! is enabled.
+//! is enabled. With the `"rtic"` feature, RTIC owns the `SysTick` exception,
+//! and the GPT2 monotonic timer advances the millisecond counter.
 
+use crate::executor::MAX_TASKS;
 use crate::rt::exception;
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use cortex_m::interrupt::{self, Mutex};
 
 #[no_mangle]
 static mut systick_millis_count: u32 = 0;
 
+#[cfg(not(feature = "rtic"))]
 #[exception]
 fn SysTick() {
+    tick();
+}
+
+/// Advance the millisecond counter, and wake the sleepers
+pub(crate) fn tick() {
     unsafe {
         let ms = core::ptr::read_volatile(&systick_millis_count);
         let ms = ms.wrapping_add(1);
         core::ptr::write_volatile(&mut systick_millis_count, ms);
     }
+    wake_sleepers();
 }
 
 /// Read the systick counter. Returns an absolute value describing
@@ -78,3 +94,68 @@ impl embedded_hal::blocking::delay::DelayMs<u8> for SysTick {
         self::delay(ms.into());
     }
 }
+
+/// A sleeping task's start time, duration, and waker
+type Sleeper = Option<(u32, u32, Waker)>;
+const AWAKE: Sleeper = None;
+static SLEEPERS: Mutex<RefCell<[Sleeper; MAX_TASKS]>> =
+    Mutex::new(RefCell::new([AWAKE; MAX_TASKS]));
+
+/// Wake the sleepers whose durations elapsed
+fn wake_sleepers() {
+    let now = read();
+    interrupt::free(|cs| {
+        for sleeper in SLEEPERS.borrow(cs).borrow_mut().iter_mut() {
+            let elapsed =
+                matches!(sleeper, Some((start, millis, _)) if now.wrapping_sub(*start) >= *millis);
+            if elapsed {
+                if let Some((_, _, waker)) = sleeper.take() {
+                    waker.wake();
+                }
+            }
+        }
+    })
+}
+
+/// Wait for at least `millis` milliseconds, without blocking other tasks
+///
+/// Like a blocking `SysTick` delay, the minimal sleep is around 1ms.
+pub fn sleep(millis: u32) -> Sleep {
+    Sleep {
+        start: read(),
+        millis,
+    }
+}
+
+/// A future that waits for the SysTick counter
+///
+/// Use [`sleep`](fn.sleep.html) to create a `Sleep`.
+pub struct Sleep {
+    start: u32,
+    millis: u32,
+}
+
+impl Future for Sleep {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if read().wrapping_sub(self.start) >= self.millis {
+            return Poll::Ready(());
+        }
+        interrupt::free(|cs| {
+            let mut sleepers = SLEEPERS.borrow(cs).borrow_mut();
+            let sleeper = (self.start, self.millis, cx.waker().clone());
+            if let Some(slot) = sleepers
+                .iter_mut()
+                .find(|slot| matches!(slot, Some((_, _, waker)) if waker.will_wake(cx.waker())))
+            {
+                *slot = Some(sleeper);
+            } else if let Some(slot) = sleepers.iter_mut().find(|slot| slot.is_none()) {
+                *slot = Some(sleeper);
+            } else {
+                // No room; poll again
+                cx.waker().wake_by_ref();
+            }
+        });
+        Poll::Pending
+    }
+}
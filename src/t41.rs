@@ -0,0 +1,613 @@
+//! Teensy 4.1 pins
+//!
+//! Enable the `"teensy41"` feature to select the Teensy 4.1. The feature also
+//! selects the Teensy 4.1's 8MiB flash configuration and memory map.
+//!
+//! Pins 0 through 33 are the same as the Teensy 4.0. See the
+//! [crate-level documentation](../index.html) for those pins. The table below
+//! describes the Teensy 4.1's remaining pins.
+//!
+//! | Pin  | Pad ID   |  Alt0    |  Alt1        |  Alt2        |  Alt3     |  Alt4        |  Alt5            |  Alt6        |  Alt7   |  Alt8          |  Alt9   |
+//! | ---- | -------- | -------- | ------------ | ------------ | --------- | ------------ | ---------------- | ------------ | ------- | -------------- | ------- |
+//! |  34  |`B1_13`   |          |  `UART5_RX`  |              |           |              |                  |              |         |                |         |
+//! |  35  |`B1_12`   |          |  `UART5_TX`  |              |           |              |                  |              |         |                |         |
+//! |  36  |`B1_02`   |          |              |              |           |              |                  |`FlexPWM2_3_A`|         |                |         |
+//! |  37  |`B1_03`   |          |              |              |           |              |                  |`FlexPWM2_3_B`|         |                |         |
+//! |  38  |`AD_B1_12`|          |              |  `SPI3_PCS0` |           |              |                  |              |         |                |         |
+//! |  39  |`AD_B1_13`|          |              |  `SPI3_SDI`  |           |              |                  |              |         |                |         |
+//! |  40  |`AD_B1_04`|          |              |              |           |              |                  |              |         |                |         |
+//! |  41  |`AD_B1_05`|          |              |              |           |              |                  |              |         |                |         |
+//! |  42  |`SD_B0_03`|          |`FlexPWM1_1_B`|              |           |  `SPI1_SDI`  |                  |              |         |                |         |
+//! |  43  |`SD_B0_02`|          |`FlexPWM1_1_A`|              |           |  `SPI1_SDO`  |                  |              |         |                |         |
+//! |  44  |`SD_B0_01`|          |`FlexPWM1_0_B`|  `I2C3_SDA`  |           |  `SPI1_PCS0` |                  |              |         |                |         |
+//! |  45  |`SD_B0_00`|          |`FlexPWM1_0_A`|  `I2C3_SCL`  |           |  `SPI1_SCK`  |                  |              |         |                |         |
+//! |  46  |`SD_B0_05`|          |`FlexPWM1_2_B`|  `UART8_RX`  |           |              |                  |              |         |                |         |
+//! |  47  |`SD_B0_04`|          |`FlexPWM1_2_A`|  `UART8_TX`  |           |              |                  |              |         |                |         |
+//! |  48  |`EMC_24`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_SS0`|         |
+//! |  49  |`EMC_27`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_D1` |         |
+//! |  50  |`EMC_28`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_D2` |         |
+//! |  51  |`EMC_22`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_SS1`|         |
+//! |  52  |`EMC_26`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_D0` |         |
+//! |  53  |`EMC_25`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_CLK`|         |
+//! |  54  |`EMC_29`  |          |              |              |           |              |                  |              |         |`FLEXSPI2_A_D3` |         |
+//!
+//! Pins 42 through 47 are wired to the on-board SD card slot. Pins 48 through 54 are
+//! the pads for the optional PSRAM and flash chips on the bottom of the board. The
+//! runtime maps the FlexSPI2 memory region, `0x7000_0000`, when the `"teensy41"` feature
+//! is enabled.
+//!
+//! The Ethernet PHY pads are not broken out to pins. They're available in
+//! [`EthernetPins`](struct.EthernetPins.html).
+//!
+//! References:
+//! - [Teensy 4.1 Schematic Diagram](https://www.pjrc.com/teensy/schematic.html)
+
+use crate::flexpwm::{Channel, Output};
+use crate::hal;
+
+/// Teensy pins that do not yet have a function
+///
+/// `Pins` includes all of the Teensy 4.1's pins. See the [module-level documentation](index.html)
+/// for each pin's pad and alternate functions.
+///
+/// Pin 13 can be used for several things; one common usage is for the on-board LED.
+pub struct Pins {
+    /// Pin 0
+    pub p0: hal::iomuxc::gpio::GPIO_AD_B0_03<hal::iomuxc::Alt5>,
+    /// Pin 1
+    pub p1: hal::iomuxc::gpio::GPIO_AD_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 2
+    pub p2: hal::iomuxc::gpio::GPIO_EMC_04<hal::iomuxc::Alt5>,
+    /// Pin 3
+    pub p3: hal::iomuxc::gpio::GPIO_EMC_05<hal::iomuxc::Alt5>,
+    /// Pin 4
+    pub p4: hal::iomuxc::gpio::GPIO_EMC_06<hal::iomuxc::Alt5>,
+    /// Pin 5
+    pub p5: hal::iomuxc::gpio::GPIO_EMC_08<hal::iomuxc::Alt5>,
+    /// Pin 6
+    pub p6: hal::iomuxc::gpio::GPIO_B0_10<hal::iomuxc::Alt5>,
+    /// Pin 7
+    pub p7: hal::iomuxc::gpio::GPIO_B1_01<hal::iomuxc::Alt5>,
+    /// Pin 8
+    pub p8: hal::iomuxc::gpio::GPIO_B1_00<hal::iomuxc::Alt5>,
+    /// Pin 9
+    pub p9: hal::iomuxc::gpio::GPIO_B0_11<hal::iomuxc::Alt5>,
+    /// Pin 10
+    pub p10: hal::iomuxc::gpio::GPIO_B0_00<hal::iomuxc::Alt5>,
+    /// Pin 11
+    pub p11: hal::iomuxc::gpio::GPIO_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 12
+    pub p12: hal::iomuxc::gpio::GPIO_B0_01<hal::iomuxc::Alt5>,
+    /// Pin 13
+    pub p13: hal::iomuxc::gpio::GPIO_B0_03<hal::iomuxc::Alt5>,
+    /// Pin 14
+    pub p14: hal::iomuxc::gpio::GPIO_AD_B1_02<hal::iomuxc::Alt5>,
+    /// Pin 15
+    pub p15: hal::iomuxc::gpio::GPIO_AD_B1_03<hal::iomuxc::Alt5>,
+    /// Pin 16
+    pub p16: hal::iomuxc::gpio::GPIO_AD_B1_07<hal::iomuxc::Alt5>,
+    /// Pin 17
+    pub p17: hal::iomuxc::gpio::GPIO_AD_B1_06<hal::iomuxc::Alt5>,
+    /// Pin 18
+    pub p18: hal::iomuxc::gpio::GPIO_AD_B1_01<hal::iomuxc::Alt5>,
+    /// Pin 19
+    pub p19: hal::iomuxc::gpio::GPIO_AD_B1_00<hal::iomuxc::Alt5>,
+    /// Pin 20
+    pub p20: hal::iomuxc::gpio::GPIO_AD_B1_10<hal::iomuxc::Alt5>,
+    /// Pin 21
+    pub p21: hal::iomuxc::gpio::GPIO_AD_B1_11<hal::iomuxc::Alt5>,
+    /// Pin 22
+    pub p22: hal::iomuxc::gpio::GPIO_AD_B1_08<hal::iomuxc::Alt5>,
+    /// Pin 23
+    pub p23: hal::iomuxc::gpio::GPIO_AD_B1_09<hal::iomuxc::Alt5>,
+    /// Pin 24
+    pub p24: hal::iomuxc::gpio::GPIO_AD_B0_12<hal::iomuxc::Alt5>,
+    /// Pin 25
+    pub p25: hal::iomuxc::gpio::GPIO_AD_B0_13<hal::iomuxc::Alt5>,
+    /// Pin 26
+    pub p26: hal::iomuxc::gpio::GPIO_AD_B1_14<hal::iomuxc::Alt5>,
+    /// Pin 27
+    pub p27: hal::iomuxc::gpio::GPIO_AD_B1_15<hal::iomuxc::Alt5>,
+    /// Pin 28
+    pub p28: hal::iomuxc::gpio::GPIO_EMC_32<hal::iomuxc::Alt5>,
+    /// Pin 29
+    pub p29: hal::iomuxc::gpio::GPIO_EMC_31<hal::iomuxc::Alt5>,
+    /// Pin 30
+    pub p30: hal::iomuxc::gpio::GPIO_EMC_37<hal::iomuxc::Alt5>,
+    /// Pin 31
+    pub p31: hal::iomuxc::gpio::GPIO_EMC_36<hal::iomuxc::Alt5>,
+    /// Pin 32
+    pub p32: hal::iomuxc::gpio::GPIO_B0_12<hal::iomuxc::Alt5>,
+    /// Pin 33
+    pub p33: hal::iomuxc::gpio::GPIO_EMC_07<hal::iomuxc::Alt5>,
+    /// Pin 34
+    pub p34: hal::iomuxc::gpio::GPIO_B1_13<hal::iomuxc::Alt5>,
+    /// Pin 35
+    pub p35: hal::iomuxc::gpio::GPIO_B1_12<hal::iomuxc::Alt5>,
+    /// Pin 36
+    pub p36: hal::iomuxc::gpio::GPIO_B1_02<hal::iomuxc::Alt5>,
+    /// Pin 37
+    pub p37: hal::iomuxc::gpio::GPIO_B1_03<hal::iomuxc::Alt5>,
+    /// Pin 38
+    pub p38: hal::iomuxc::gpio::GPIO_AD_B1_12<hal::iomuxc::Alt5>,
+    /// Pin 39
+    pub p39: hal::iomuxc::gpio::GPIO_AD_B1_13<hal::iomuxc::Alt5>,
+    /// Pin 40
+    pub p40: hal::iomuxc::gpio::GPIO_AD_B1_04<hal::iomuxc::Alt5>,
+    /// Pin 41
+    pub p41: hal::iomuxc::gpio::GPIO_AD_B1_05<hal::iomuxc::Alt5>,
+    /// Pin 42
+    pub p42: hal::iomuxc::gpio::GPIO_SD_B0_03<hal::iomuxc::Alt5>,
+    /// Pin 43
+    pub p43: hal::iomuxc::gpio::GPIO_SD_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 44
+    pub p44: hal::iomuxc::gpio::GPIO_SD_B0_01<hal::iomuxc::Alt5>,
+    /// Pin 45
+    pub p45: hal::iomuxc::gpio::GPIO_SD_B0_00<hal::iomuxc::Alt5>,
+    /// Pin 46
+    pub p46: hal::iomuxc::gpio::GPIO_SD_B0_05<hal::iomuxc::Alt5>,
+    /// Pin 47
+    pub p47: hal::iomuxc::gpio::GPIO_SD_B0_04<hal::iomuxc::Alt5>,
+    /// Pin 48
+    pub p48: hal::iomuxc::gpio::GPIO_EMC_24<hal::iomuxc::Alt5>,
+    /// Pin 49
+    pub p49: hal::iomuxc::gpio::GPIO_EMC_27<hal::iomuxc::Alt5>,
+    /// Pin 50
+    pub p50: hal::iomuxc::gpio::GPIO_EMC_28<hal::iomuxc::Alt5>,
+    /// Pin 51
+    pub p51: hal::iomuxc::gpio::GPIO_EMC_22<hal::iomuxc::Alt5>,
+    /// Pin 52
+    pub p52: hal::iomuxc::gpio::GPIO_EMC_26<hal::iomuxc::Alt5>,
+    /// Pin 53
+    pub p53: hal::iomuxc::gpio::GPIO_EMC_25<hal::iomuxc::Alt5>,
+    /// Pin 54
+    pub p54: hal::iomuxc::gpio::GPIO_EMC_29<hal::iomuxc::Alt5>,
+}
+
+/// The Teensy 4.1 pins, without pin 13
+///
+/// [`Board`](../setup/struct.Board.html) configures pin 13 as the LED, and it
+/// returns the rest of the pins in this struct.
+pub struct BoardPins {
+    /// Pin 0
+    pub p0: hal::iomuxc::gpio::GPIO_AD_B0_03<hal::iomuxc::Alt5>,
+    /// Pin 1
+    pub p1: hal::iomuxc::gpio::GPIO_AD_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 2
+    pub p2: hal::iomuxc::gpio::GPIO_EMC_04<hal::iomuxc::Alt5>,
+    /// Pin 3
+    pub p3: hal::iomuxc::gpio::GPIO_EMC_05<hal::iomuxc::Alt5>,
+    /// Pin 4
+    pub p4: hal::iomuxc::gpio::GPIO_EMC_06<hal::iomuxc::Alt5>,
+    /// Pin 5
+    pub p5: hal::iomuxc::gpio::GPIO_EMC_08<hal::iomuxc::Alt5>,
+    /// Pin 6
+    pub p6: hal::iomuxc::gpio::GPIO_B0_10<hal::iomuxc::Alt5>,
+    /// Pin 7
+    pub p7: hal::iomuxc::gpio::GPIO_B1_01<hal::iomuxc::Alt5>,
+    /// Pin 8
+    pub p8: hal::iomuxc::gpio::GPIO_B1_00<hal::iomuxc::Alt5>,
+    /// Pin 9
+    pub p9: hal::iomuxc::gpio::GPIO_B0_11<hal::iomuxc::Alt5>,
+    /// Pin 10
+    pub p10: hal::iomuxc::gpio::GPIO_B0_00<hal::iomuxc::Alt5>,
+    /// Pin 11
+    pub p11: hal::iomuxc::gpio::GPIO_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 12
+    pub p12: hal::iomuxc::gpio::GPIO_B0_01<hal::iomuxc::Alt5>,
+    /// Pin 14
+    pub p14: hal::iomuxc::gpio::GPIO_AD_B1_02<hal::iomuxc::Alt5>,
+    /// Pin 15
+    pub p15: hal::iomuxc::gpio::GPIO_AD_B1_03<hal::iomuxc::Alt5>,
+    /// Pin 16
+    pub p16: hal::iomuxc::gpio::GPIO_AD_B1_07<hal::iomuxc::Alt5>,
+    /// Pin 17
+    pub p17: hal::iomuxc::gpio::GPIO_AD_B1_06<hal::iomuxc::Alt5>,
+    /// Pin 18
+    pub p18: hal::iomuxc::gpio::GPIO_AD_B1_01<hal::iomuxc::Alt5>,
+    /// Pin 19
+    pub p19: hal::iomuxc::gpio::GPIO_AD_B1_00<hal::iomuxc::Alt5>,
+    /// Pin 20
+    pub p20: hal::iomuxc::gpio::GPIO_AD_B1_10<hal::iomuxc::Alt5>,
+    /// Pin 21
+    pub p21: hal::iomuxc::gpio::GPIO_AD_B1_11<hal::iomuxc::Alt5>,
+    /// Pin 22
+    pub p22: hal::iomuxc::gpio::GPIO_AD_B1_08<hal::iomuxc::Alt5>,
+    /// Pin 23
+    pub p23: hal::iomuxc::gpio::GPIO_AD_B1_09<hal::iomuxc::Alt5>,
+    /// Pin 24
+    pub p24: hal::iomuxc::gpio::GPIO_AD_B0_12<hal::iomuxc::Alt5>,
+    /// Pin 25
+    pub p25: hal::iomuxc::gpio::GPIO_AD_B0_13<hal::iomuxc::Alt5>,
+    /// Pin 26
+    pub p26: hal::iomuxc::gpio::GPIO_AD_B1_14<hal::iomuxc::Alt5>,
+    /// Pin 27
+    pub p27: hal::iomuxc::gpio::GPIO_AD_B1_15<hal::iomuxc::Alt5>,
+    /// Pin 28
+    pub p28: hal::iomuxc::gpio::GPIO_EMC_32<hal::iomuxc::Alt5>,
+    /// Pin 29
+    pub p29: hal::iomuxc::gpio::GPIO_EMC_31<hal::iomuxc::Alt5>,
+    /// Pin 30
+    pub p30: hal::iomuxc::gpio::GPIO_EMC_37<hal::iomuxc::Alt5>,
+    /// Pin 31
+    pub p31: hal::iomuxc::gpio::GPIO_EMC_36<hal::iomuxc::Alt5>,
+    /// Pin 32
+    pub p32: hal::iomuxc::gpio::GPIO_B0_12<hal::iomuxc::Alt5>,
+    /// Pin 33
+    pub p33: hal::iomuxc::gpio::GPIO_EMC_07<hal::iomuxc::Alt5>,
+    /// Pin 34
+    pub p34: hal::iomuxc::gpio::GPIO_B1_13<hal::iomuxc::Alt5>,
+    /// Pin 35
+    pub p35: hal::iomuxc::gpio::GPIO_B1_12<hal::iomuxc::Alt5>,
+    /// Pin 36
+    pub p36: hal::iomuxc::gpio::GPIO_B1_02<hal::iomuxc::Alt5>,
+    /// Pin 37
+    pub p37: hal::iomuxc::gpio::GPIO_B1_03<hal::iomuxc::Alt5>,
+    /// Pin 38
+    pub p38: hal::iomuxc::gpio::GPIO_AD_B1_12<hal::iomuxc::Alt5>,
+    /// Pin 39
+    pub p39: hal::iomuxc::gpio::GPIO_AD_B1_13<hal::iomuxc::Alt5>,
+    /// Pin 40
+    pub p40: hal::iomuxc::gpio::GPIO_AD_B1_04<hal::iomuxc::Alt5>,
+    /// Pin 41
+    pub p41: hal::iomuxc::gpio::GPIO_AD_B1_05<hal::iomuxc::Alt5>,
+    /// Pin 42
+    pub p42: hal::iomuxc::gpio::GPIO_SD_B0_03<hal::iomuxc::Alt5>,
+    /// Pin 43
+    pub p43: hal::iomuxc::gpio::GPIO_SD_B0_02<hal::iomuxc::Alt5>,
+    /// Pin 44
+    pub p44: hal::iomuxc::gpio::GPIO_SD_B0_01<hal::iomuxc::Alt5>,
+    /// Pin 45
+    pub p45: hal::iomuxc::gpio::GPIO_SD_B0_00<hal::iomuxc::Alt5>,
+    /// Pin 46
+    pub p46: hal::iomuxc::gpio::GPIO_SD_B0_05<hal::iomuxc::Alt5>,
+    /// Pin 47
+    pub p47: hal::iomuxc::gpio::GPIO_SD_B0_04<hal::iomuxc::Alt5>,
+    /// Pin 48
+    pub p48: hal::iomuxc::gpio::GPIO_EMC_24<hal::iomuxc::Alt5>,
+    /// Pin 49
+    pub p49: hal::iomuxc::gpio::GPIO_EMC_27<hal::iomuxc::Alt5>,
+    /// Pin 50
+    pub p50: hal::iomuxc::gpio::GPIO_EMC_28<hal::iomuxc::Alt5>,
+    /// Pin 51
+    pub p51: hal::iomuxc::gpio::GPIO_EMC_22<hal::iomuxc::Alt5>,
+    /// Pin 52
+    pub p52: hal::iomuxc::gpio::GPIO_EMC_26<hal::iomuxc::Alt5>,
+    /// Pin 53
+    pub p53: hal::iomuxc::gpio::GPIO_EMC_25<hal::iomuxc::Alt5>,
+    /// Pin 54
+    pub p54: hal::iomuxc::gpio::GPIO_EMC_29<hal::iomuxc::Alt5>,
+}
+
+impl Pins {
+    /// Split pin 13, the LED, from the rest of the pins
+    pub(crate) fn split_led(self) -> (hal::iomuxc::gpio::GPIO_B0_03<hal::iomuxc::Alt5>, BoardPins) {
+        let Pins {
+            p0,
+            p1,
+            p2,
+            p3,
+            p4,
+            p5,
+            p6,
+            p7,
+            p8,
+            p9,
+            p10,
+            p11,
+            p12,
+            p13,
+            p14,
+            p15,
+            p16,
+            p17,
+            p18,
+            p19,
+            p20,
+            p21,
+            p22,
+            p23,
+            p24,
+            p25,
+            p26,
+            p27,
+            p28,
+            p29,
+            p30,
+            p31,
+            p32,
+            p33,
+            p34,
+            p35,
+            p36,
+            p37,
+            p38,
+            p39,
+            p40,
+            p41,
+            p42,
+            p43,
+            p44,
+            p45,
+            p46,
+            p47,
+            p48,
+            p49,
+            p50,
+            p51,
+            p52,
+            p53,
+            p54,
+        } = self;
+        (
+            p13,
+            BoardPins {
+                p0,
+                p1,
+                p2,
+                p3,
+                p4,
+                p5,
+                p6,
+                p7,
+                p8,
+                p9,
+                p10,
+                p11,
+                p12,
+                p14,
+                p15,
+                p16,
+                p17,
+                p18,
+                p19,
+                p20,
+                p21,
+                p22,
+                p23,
+                p24,
+                p25,
+                p26,
+                p27,
+                p28,
+                p29,
+                p30,
+                p31,
+                p32,
+                p33,
+                p34,
+                p35,
+                p36,
+                p37,
+                p38,
+                p39,
+                p40,
+                p41,
+                p42,
+                p43,
+                p44,
+                p45,
+                p46,
+                p47,
+                p48,
+                p49,
+                p50,
+                p51,
+                p52,
+                p53,
+                p54,
+            },
+        )
+    }
+}
+
+/// The number of pins on the board
+pub const NUM_PINS: usize = teensy4_pads::t41::NUM_PINS;
+
+teensy_pins! {
+    teensy4_pads::t41::PADS;
+    0 => GPIO_AD_B0_03 (AdB0, 3),
+    1 => GPIO_AD_B0_02 (AdB0, 2),
+    2 => GPIO_EMC_04 (Emc, 4),
+    3 => GPIO_EMC_05 (Emc, 5),
+    4 => GPIO_EMC_06 (Emc, 6),
+    5 => GPIO_EMC_08 (Emc, 8),
+    6 => GPIO_B0_10 (B0, 10),
+    7 => GPIO_B1_01 (B1, 1),
+    8 => GPIO_B1_00 (B1, 0),
+    9 => GPIO_B0_11 (B0, 11),
+    10 => GPIO_B0_00 (B0, 0),
+    11 => GPIO_B0_02 (B0, 2),
+    12 => GPIO_B0_01 (B0, 1),
+    13 => GPIO_B0_03 (B0, 3),
+    14 => GPIO_AD_B1_02 (AdB1, 2),
+    15 => GPIO_AD_B1_03 (AdB1, 3),
+    16 => GPIO_AD_B1_07 (AdB1, 7),
+    17 => GPIO_AD_B1_06 (AdB1, 6),
+    18 => GPIO_AD_B1_01 (AdB1, 1),
+    19 => GPIO_AD_B1_00 (AdB1, 0),
+    20 => GPIO_AD_B1_10 (AdB1, 10),
+    21 => GPIO_AD_B1_11 (AdB1, 11),
+    22 => GPIO_AD_B1_08 (AdB1, 8),
+    23 => GPIO_AD_B1_09 (AdB1, 9),
+    24 => GPIO_AD_B0_12 (AdB0, 12),
+    25 => GPIO_AD_B0_13 (AdB0, 13),
+    26 => GPIO_AD_B1_14 (AdB1, 14),
+    27 => GPIO_AD_B1_15 (AdB1, 15),
+    28 => GPIO_EMC_32 (Emc, 32),
+    29 => GPIO_EMC_31 (Emc, 31),
+    30 => GPIO_EMC_37 (Emc, 37),
+    31 => GPIO_EMC_36 (Emc, 36),
+    32 => GPIO_B0_12 (B0, 12),
+    33 => GPIO_EMC_07 (Emc, 7),
+    34 => GPIO_B1_13 (B1, 13),
+    35 => GPIO_B1_12 (B1, 12),
+    36 => GPIO_B1_02 (B1, 2),
+    37 => GPIO_B1_03 (B1, 3),
+    38 => GPIO_AD_B1_12 (AdB1, 12),
+    39 => GPIO_AD_B1_13 (AdB1, 13),
+    40 => GPIO_AD_B1_04 (AdB1, 4),
+    41 => GPIO_AD_B1_05 (AdB1, 5),
+    42 => GPIO_SD_B0_03 (SdB0, 3),
+    43 => GPIO_SD_B0_02 (SdB0, 2),
+    44 => GPIO_SD_B0_01 (SdB0, 1),
+    45 => GPIO_SD_B0_00 (SdB0, 0),
+    46 => GPIO_SD_B0_05 (SdB0, 5),
+    47 => GPIO_SD_B0_04 (SdB0, 4),
+    48 => GPIO_EMC_24 (Emc, 24),
+    49 => GPIO_EMC_27 (Emc, 27),
+    50 => GPIO_EMC_28 (Emc, 28),
+    51 => GPIO_EMC_22 (Emc, 22),
+    52 => GPIO_EMC_26 (Emc, 26),
+    53 => GPIO_EMC_25 (Emc, 25),
+    54 => GPIO_EMC_29 (Emc, 29),
+}
+
+/// FlexPWM outputs, keyed by Teensy pin
+///
+/// Pins that are only connected to a QuadTimer are not listed.
+pub(crate) static PWM_OUTPUTS: &[(u8, Output)] = &[
+    (0, Output::new(1, 1, Channel::X, 4)),
+    (1, Output::new(1, 0, Channel::X, 4)),
+    (2, Output::new(4, 2, Channel::A, 1)),
+    (3, Output::new(4, 2, Channel::B, 1)),
+    (4, Output::new(2, 0, Channel::A, 1)),
+    (5, Output::new(2, 1, Channel::A, 1)),
+    (6, Output::new(2, 2, Channel::A, 2)),
+    (7, Output::new(1, 3, Channel::B, 6)),
+    (8, Output::new(1, 3, Channel::A, 6)),
+    (9, Output::new(2, 2, Channel::B, 2)),
+    (22, Output::new(4, 0, Channel::A, 1)),
+    (23, Output::new(4, 1, Channel::A, 1)),
+    (24, Output::new(1, 2, Channel::X, 4)),
+    (25, Output::new(1, 3, Channel::X, 4)),
+    (28, Output::new(3, 1, Channel::B, 1)),
+    (29, Output::new(3, 1, Channel::A, 1)),
+    (33, Output::new(2, 0, Channel::B, 1)),
+    (36, Output::new(2, 3, Channel::A, 6)),
+    (37, Output::new(2, 3, Channel::B, 6)),
+    (42, Output::new(1, 1, Channel::B, 1)),
+    (43, Output::new(1, 1, Channel::A, 1)),
+    (44, Output::new(1, 0, Channel::B, 1)),
+    (45, Output::new(1, 0, Channel::A, 1)),
+    (46, Output::new(1, 2, Channel::B, 1)),
+    (47, Output::new(1, 2, Channel::A, 1)),
+    (51, Output::new(3, 3, Channel::B, 1)),
+    (54, Output::new(3, 0, Channel::A, 1)),
+];
+
+/// Pads connected to the Teensy 4.1's Ethernet PHY
+///
+/// The Teensy 4.1 connects a DP83825I PHY to the ENET peripheral through
+/// an RMII interface.
+pub struct EthernetPins {
+    /// RMII receive data 0 (`B1_04`)
+    pub rxd0: hal::iomuxc::gpio::GPIO_B1_04<hal::iomuxc::Alt5>,
+    /// RMII receive data 1 (`B1_05`)
+    pub rxd1: hal::iomuxc::gpio::GPIO_B1_05<hal::iomuxc::Alt5>,
+    /// RMII carrier sense / receive data valid (`B1_06`)
+    pub crs_dv: hal::iomuxc::gpio::GPIO_B1_06<hal::iomuxc::Alt5>,
+    /// RMII transmit data 0 (`B1_07`)
+    pub txd0: hal::iomuxc::gpio::GPIO_B1_07<hal::iomuxc::Alt5>,
+    /// RMII transmit data 1 (`B1_08`)
+    pub txd1: hal::iomuxc::gpio::GPIO_B1_08<hal::iomuxc::Alt5>,
+    /// RMII transmit enable (`B1_09`)
+    pub tx_en: hal::iomuxc::gpio::GPIO_B1_09<hal::iomuxc::Alt5>,
+    /// RMII 50MHz reference clock (`B1_10`)
+    pub ref_clk: hal::iomuxc::gpio::GPIO_B1_10<hal::iomuxc::Alt5>,
+    /// RMII receive error (`B1_11`)
+    pub rx_er: hal::iomuxc::gpio::GPIO_B1_11<hal::iomuxc::Alt5>,
+    /// Management data clock (`B1_14`)
+    pub mdc: hal::iomuxc::gpio::GPIO_B1_14<hal::iomuxc::Alt5>,
+    /// Management data I/O (`B1_15`)
+    pub mdio: hal::iomuxc::gpio::GPIO_B1_15<hal::iomuxc::Alt5>,
+    /// PHY reset, active low (`B0_14`)
+    pub reset: hal::iomuxc::gpio::GPIO_B0_14<hal::iomuxc::Alt5>,
+    /// PHY power enable (`B0_15`)
+    pub power: hal::iomuxc::gpio::GPIO_B0_15<hal::iomuxc::Alt5>,
+}
+
+/// Constructs the Teensy 4.1 [`Pins`](struct.Pins.html) by moving pads
+/// out of the HAL peripherals, `$p`
+macro_rules! pins {
+    ($p:ident) => {
+        $crate::t41::Pins {
+            p0: $p.iomuxc.gpio_ad_b0_03,
+            p1: $p.iomuxc.gpio_ad_b0_02,
+            p2: $p.iomuxc.gpio_emc_04,
+            p3: $p.iomuxc.gpio_emc_05,
+            p4: $p.iomuxc.gpio_emc_06,
+            p5: $p.iomuxc.gpio_emc_08,
+            p6: $p.iomuxc.gpio_b0_10,
+            p7: $p.iomuxc.gpio_b1_01,
+            p8: $p.iomuxc.gpio_b1_00,
+            p9: $p.iomuxc.gpio_b0_11,
+            p10: $p.iomuxc.gpio_b0_00,
+            p11: $p.iomuxc.gpio_b0_02,
+            p12: $p.iomuxc.gpio_b0_01,
+            p13: $p.iomuxc.gpio_b0_03,
+            p14: $p.iomuxc.gpio_ad_b1_02,
+            p15: $p.iomuxc.gpio_ad_b1_03,
+            p16: $p.iomuxc.gpio_ad_b1_07,
+            p17: $p.iomuxc.gpio_ad_b1_06,
+            p18: $p.iomuxc.gpio_ad_b1_01,
+            p19: $p.iomuxc.gpio_ad_b1_00,
+            p20: $p.iomuxc.gpio_ad_b1_10,
+            p21: $p.iomuxc.gpio_ad_b1_11,
+            p22: $p.iomuxc.gpio_ad_b1_08,
+            p23: $p.iomuxc.gpio_ad_b1_09,
+            p24: $p.iomuxc.gpio_ad_b0_12,
+            p25: $p.iomuxc.gpio_ad_b0_13,
+            p26: $p.iomuxc.gpio_ad_b1_14,
+            p27: $p.iomuxc.gpio_ad_b1_15,
+            p28: $p.iomuxc.gpio_emc_32,
+            p29: $p.iomuxc.gpio_emc_31,
+            p30: $p.iomuxc.gpio_emc_37,
+            p31: $p.iomuxc.gpio_emc_36,
+            p32: $p.iomuxc.gpio_b0_12,
+            p33: $p.iomuxc.gpio_emc_07,
+            p34: $p.iomuxc.gpio_b1_13,
+            p35: $p.iomuxc.gpio_b1_12,
+            p36: $p.iomuxc.gpio_b1_02,
+            p37: $p.iomuxc.gpio_b1_03,
+            p38: $p.iomuxc.gpio_ad_b1_12,
+            p39: $p.iomuxc.gpio_ad_b1_13,
+            p40: $p.iomuxc.gpio_ad_b1_04,
+            p41: $p.iomuxc.gpio_ad_b1_05,
+            p42: $p.iomuxc.gpio_sd_b0_03,
+            p43: $p.iomuxc.gpio_sd_b0_02,
+            p44: $p.iomuxc.gpio_sd_b0_01,
+            p45: $p.iomuxc.gpio_sd_b0_00,
+            p46: $p.iomuxc.gpio_sd_b0_05,
+            p47: $p.iomuxc.gpio_sd_b0_04,
+            p48: $p.iomuxc.gpio_emc_24,
+            p49: $p.iomuxc.gpio_emc_27,
+            p50: $p.iomuxc.gpio_emc_28,
+            p51: $p.iomuxc.gpio_emc_22,
+            p52: $p.iomuxc.gpio_emc_26,
+            p53: $p.iomuxc.gpio_emc_25,
+            p54: $p.iomuxc.gpio_emc_29,
+        }
+    };
+}
+
+/// Constructs the Teensy 4.1 [`EthernetPins`](struct.EthernetPins.html) by
+/// moving pads out of the HAL peripherals, `$p`
+macro_rules! enet_pins {
+    ($p:ident) => {
+        $crate::t41::EthernetPins {
+            rxd0: $p.iomuxc.gpio_b1_04,
+            rxd1: $p.iomuxc.gpio_b1_05,
+            crs_dv: $p.iomuxc.gpio_b1_06,
+            txd0: $p.iomuxc.gpio_b1_07,
+            txd1: $p.iomuxc.gpio_b1_08,
+            tx_en: $p.iomuxc.gpio_b1_09,
+            ref_clk: $p.iomuxc.gpio_b1_10,
+            rx_er: $p.iomuxc.gpio_b1_11,
+            mdc: $p.iomuxc.gpio_b1_14,
+            mdio: $p.iomuxc.gpio_b1_15,
+            reset: $p.iomuxc.gpio_b0_14,
+            power: $p.iomuxc.gpio_b0_15,
+        }
+    };
+}
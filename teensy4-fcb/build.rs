@@ -89,6 +89,21 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+/// Size of the serial NOR flash for the selected board, in bytes
+///
+/// The Teensy 4.0 has a 2MiB Winbond W25Q16JV. The Teensy 4.1 has
+/// an 8MiB Winbond W25Q64JV. The Teensy MicroMod has a 16MiB Winbond
+/// W25Q128JV. All understand the same commands.
+fn flash_size() -> u32 {
+    if env::var_os("CARGO_FEATURE_MICROMOD").is_some() {
+        0x0100_0000
+    } else if env::var_os("CARGO_FEATURE_TEENSY41").is_some() {
+        0x0080_0000
+    } else {
+        0x0020_0000
+    }
+}
+
 fn main() {
     let nor_cb = nor::ConfigurationBlock {
         page_size: 256,
@@ -115,7 +130,7 @@ fn main() {
         .column_address_width(ColumnAddressWidth::OtherDevices)
         .device_mode_configuration(DeviceModeConfiguration::Disabled)
         .wait_time_cfg_commands(WaitTimeConfigurationCommands::disable())
-        .flash_size(SerialFlashRegion::A1, 0x0020_0000)
+        .flash_size(SerialFlashRegion::A1, flash_size())
         .serial_clk_freq(SerialClockFrequency::MHz60)
         .serial_flash_pad_type(FlashPadType::Quad)
         .build()
@@ -2,6 +2,11 @@
 //!
 //! See the `imxrt-boot-gen` crate for details on how
 //! this was generated.
+//!
+//! By default, the FCB describes the Teensy 4.0's 2MiB flash.
+//! Enable the `"teensy41"` feature to describe the Teensy 4.1's
+//! 8MiB flash, or the `"micromod"` feature to describe the Teensy
+//! MicroMod's 16MiB flash.
 
 #![no_std]
 
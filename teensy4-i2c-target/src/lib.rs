@@ -0,0 +1,327 @@
+//! An I2C target (slave) state machine, for register-map devices
+//!
+//! Many I2C devices expose a register map. A controller writes a register address,
+//! then writes register values, or reads them back after a repeated start. Addresses
+//! increment after each byte. This crate implements that protocol without touching
+//! hardware, so it builds and tests on the host. The `teensy4-bsp` crate re-exports it
+//! as `i2c::target`, and its `i2c` module feeds it events from an LPI2C interrupt.
+//!
+//! Implement [`Registers`](trait.Registers.html) for your register map, and pass each
+//! bus [`Event`](enum.Event.html) to a [`Target`](struct.Target.html):
+//!
+//! ```
+//! use teensy4_i2c_target::{Event, Registers, Target};
+//!
+//! struct Map([u8; 4]);
+//!
+//! impl Registers for Map {
+//!     fn read(&mut self, register: u8) -> u8 {
+//!         self.0[register as usize % 4]
+//!     }
+//!     fn write(&mut self, register: u8, value: u8) {
+//!         self.0[register as usize % 4] = value;
+//!     }
+//! }
+//!
+//! let mut target = Target::new(Map([0; 4]));
+//!
+//! // Controller writes 0xAA, 0xBB starting at register 1
+//! target.on_event(Event::Address { read: false, general_call: false });
+//! target.on_event(Event::Received(1));
+//! target.on_event(Event::Received(0xAA));
+//! target.on_event(Event::Received(0xBB));
+//! target.on_event(Event::Stop);
+//! assert_eq!(target.registers().0, [0, 0xAA, 0xBB, 0]);
+//!
+//! // Controller writes register 2, then reads after a repeated start
+//! target.on_event(Event::Address { read: false, general_call: false });
+//! target.on_event(Event::Received(2));
+//! target.on_event(Event::Address { read: true, general_call: false });
+//! assert_eq!(target.on_event(Event::TransmitRequest), Some(0xBB));
+//! assert_eq!(target.on_event(Event::TransmitRequest), Some(0));
+//! target.on_event(Event::Stop);
+//! ```
+
+#![no_std]
+
+/// A register map
+pub trait Registers {
+    /// Returns the value of `register`, for the controller to read
+    fn read(&mut self, register: u8) -> u8;
+    /// Store a `value` that the controller wrote to `register`
+    fn write(&mut self, register: u8, value: u8);
+    /// Handle a byte that the controller wrote to the general call address
+    ///
+    /// The default implementation ignores the byte.
+    fn general_call(&mut self, byte: u8) {
+        let _ = byte;
+    }
+}
+
+/// An event on the bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The controller addressed this target, after a start or repeated start
+    Address {
+        /// `true` if the controller will read
+        read: bool,
+        /// `true` if the controller used the general call address
+        general_call: bool,
+    },
+    /// The controller wrote a byte
+    Received(u8),
+    /// The controller is reading the next byte
+    TransmitRequest,
+    /// The controller issued a STOP
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Not addressed
+    Idle,
+    /// Addressed for write; the next byte is the register address
+    Pointer,
+    /// Writing registers
+    Write,
+    /// Reading registers
+    Read,
+    /// Receiving general call bytes
+    GeneralCall,
+}
+
+/// An I2C target that exposes a register map
+///
+/// See the [crate-level documentation](index.html) for more information.
+pub struct Target<R> {
+    registers: R,
+    state: State,
+    /// The next register to read or write
+    pointer: u8,
+}
+
+impl<R: Registers> Target<R> {
+    /// Create a target that exposes `registers`, starting at register 0
+    pub fn new(registers: R) -> Self {
+        Target {
+            registers,
+            state: State::Idle,
+            pointer: 0,
+        }
+    }
+
+    /// Handle a bus event
+    ///
+    /// Returns the byte to transmit for `TransmitRequest`, and `None` for other events.
+    /// If the controller didn't address this target for a read, `TransmitRequest`
+    /// returns `None`, and it doesn't read a register. The register address persists
+    /// after a STOP, so that a controller may read without first writing the address.
+    pub fn on_event(&mut self, event: Event) -> Option<u8> {
+        match event {
+            Event::Address {
+                general_call: true, ..
+            } => self.state = State::GeneralCall,
+            Event::Address { read: true, .. } => self.state = State::Read,
+            Event::Address { read: false, .. } => self.state = State::Pointer,
+            Event::Received(byte) => match self.state {
+                State::Pointer => {
+                    self.pointer = byte;
+                    self.state = State::Write;
+                }
+                State::Write => {
+                    self.registers.write(self.pointer, byte);
+                    self.pointer = self.pointer.wrapping_add(1);
+                }
+                State::GeneralCall => self.registers.general_call(byte),
+                State::Idle | State::Read => (),
+            },
+            Event::TransmitRequest if self.state == State::Read => {
+                let byte = self.registers.read(self.pointer);
+                self.pointer = self.pointer.wrapping_add(1);
+                return Some(byte);
+            }
+            Event::TransmitRequest => (),
+            Event::Stop => self.state = State::Idle,
+        }
+        None
+    }
+
+    /// Returns the register address for the next read or write
+    pub fn pointer(&self) -> u8 {
+        self.pointer
+    }
+
+    /// Returns `true` if the controller is addressing this target
+    pub fn is_addressed(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    /// Access the register map
+    pub fn registers(&self) -> &R {
+        &self.registers
+    }
+
+    /// Mutably access the register map
+    pub fn registers_mut(&mut self) -> &mut R {
+        &mut self.registers
+    }
+
+    /// Return the register map
+    pub fn release(self) -> R {
+        self.registers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Registers, Target};
+
+    /// A full register map, that records general call bytes
+    struct Map {
+        registers: [u8; 256],
+        reads: usize,
+        general_calls: [u8; 4],
+        general_call_count: usize,
+    }
+
+    impl Map {
+        fn new() -> Self {
+            let mut registers = [0; 256];
+            for (register, value) in registers.iter_mut().enumerate() {
+                *value = register as u8;
+            }
+            Map {
+                registers,
+                reads: 0,
+                general_calls: [0; 4],
+                general_call_count: 0,
+            }
+        }
+    }
+
+    impl Registers for Map {
+        fn read(&mut self, register: u8) -> u8 {
+            self.reads += 1;
+            self.registers[register as usize]
+        }
+        fn write(&mut self, register: u8, value: u8) {
+            self.registers[register as usize] = value;
+        }
+        fn general_call(&mut self, byte: u8) {
+            self.general_calls[self.general_call_count] = byte;
+            self.general_call_count += 1;
+        }
+    }
+
+    const WRITE: Event = Event::Address {
+        read: false,
+        general_call: false,
+    };
+    const READ: Event = Event::Address {
+        read: true,
+        general_call: false,
+    };
+    const GENERAL_CALL: Event = Event::Address {
+        read: false,
+        general_call: true,
+    };
+
+    #[test]
+    fn repeated_start() {
+        let mut target = Target::new(Map::new());
+        target.on_event(WRITE);
+        target.on_event(Event::Received(0x10));
+        target.on_event(Event::Received(0xAA));
+        // Repeated start, then read back from the next register
+        target.on_event(READ);
+        assert_eq!(target.on_event(Event::TransmitRequest), Some(0x11));
+        // Repeated start, and write a new pointer
+        target.on_event(WRITE);
+        target.on_event(Event::Received(0x10));
+        target.on_event(READ);
+        assert_eq!(target.on_event(Event::TransmitRequest), Some(0xAA));
+        assert_eq!(target.on_event(Event::TransmitRequest), Some(0x11));
+        target.on_event(Event::Stop);
+        assert!(!target.is_addressed());
+        assert_eq!(target.pointer(), 0x12);
+    }
+
+    #[test]
+    fn general_call() {
+        let mut target = Target::new(Map::new());
+        target.on_event(WRITE);
+        target.on_event(Event::Received(0x20));
+        target.on_event(Event::Stop);
+
+        target.on_event(GENERAL_CALL);
+        target.on_event(Event::Received(0x06));
+        target.on_event(Event::Received(0x07));
+        target.on_event(Event::Stop);
+
+        let map = target.registers();
+        assert_eq!(map.general_call_count, 2);
+        assert_eq!(&map.general_calls[..2], &[0x06, 0x07]);
+        // General call bytes aren't register writes, and they keep the pointer
+        assert_eq!(map.registers[0x20], 0x20);
+        assert_eq!(map.registers[0x06], 0x06);
+        assert_eq!(target.pointer(), 0x20);
+    }
+
+    #[test]
+    fn pointer_wraps() {
+        let mut target = Target::new(Map::new());
+        target.on_event(WRITE);
+        target.on_event(Event::Received(0xFF));
+        target.on_event(Event::Received(0x55));
+        target.on_event(Event::Received(0x66));
+        target.on_event(Event::Stop);
+        assert_eq!(target.registers().registers[0xFF], 0x55);
+        assert_eq!(target.registers().registers[0x00], 0x66);
+        assert_eq!(target.pointer(), 0x01);
+
+        target.on_event(WRITE);
+        target.on_event(Event::Received(0xFF));
+        target.on_event(READ);
+        assert_eq!(target.on_event(Event::TransmitRequest), Some(0x55));
+        assert_eq!(target.on_event(Event::TransmitRequest), Some(0x66));
+        assert_eq!(target.pointer(), 0x01);
+    }
+
+    #[test]
+    fn transmit_request_out_of_state() {
+        let mut target = Target::new(Map::new());
+        // Idle
+        assert_eq!(target.on_event(Event::TransmitRequest), None);
+        // Waiting for the pointer
+        target.on_event(WRITE);
+        assert_eq!(target.on_event(Event::TransmitRequest), None);
+        // Writing registers
+        target.on_event(Event::Received(0x30));
+        assert_eq!(target.on_event(Event::TransmitRequest), None);
+        // General call
+        target.on_event(GENERAL_CALL);
+        assert_eq!(target.on_event(Event::TransmitRequest), None);
+        target.on_event(Event::Stop);
+
+        assert_eq!(target.registers().reads, 0);
+        assert_eq!(target.pointer(), 0x30);
+    }
+
+    #[test]
+    fn received_out_of_state() {
+        let mut target = Target::new(Map::new());
+        // Idle
+        target.on_event(Event::Received(0x40));
+        assert_eq!(target.pointer(), 0);
+        // Reading registers
+        target.on_event(READ);
+        target.on_event(Event::Received(0x41));
+        assert_eq!(target.pointer(), 0);
+        assert_eq!(target.registers().registers[0], 0);
+        target.on_event(Event::Stop);
+        // After a STOP
+        target.on_event(Event::Received(0x42));
+        assert_eq!(target.pointer(), 0);
+        assert_eq!(target.registers().registers[0], 0);
+    }
+}
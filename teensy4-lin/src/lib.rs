@@ -0,0 +1,477 @@
+//! LIN frames, identifiers, and checksums
+//!
+//! This crate describes Local Interconnect Network (LIN) frames without touching
+//! hardware, so it builds and tests on the host. The `teensy4-bsp` crate re-exports
+//! it as `lin`, and its `serial` module sends breaks and bytes on the bus.
+//!
+//! A LIN frame is a header from the master, followed by a response from one node:
+//!
+//! ```text
+//! | break | sync (0x55) | protected ID | data (1 to 8 bytes) | checksum |
+//! |<--------- header (master) ------->|<-------- response --------->|
+//! ```
+//!
+//! Use [`header`](fn.header.html) and [`Frame::response`](struct.Frame.html#method.response)
+//! to produce the bytes that follow a break:
+//!
+//! ```
+//! use teensy4_lin::{header, Checksum, Frame};
+//!
+//! let frame = Frame::new(0x10, &[0x01, 0x02]).unwrap();
+//! assert_eq!(header(frame.id()).unwrap(), [0x55, 0x50]);
+//!
+//! let mut buffer = [0; 9];
+//! let response = frame.response(Checksum::Enhanced, &mut buffer);
+//! assert_eq!(response, &[0x01, 0x02, 0xAC]);
+//! ```
+//!
+//! Use a [`Receiver`](struct.Receiver.html) to parse the bytes that follow a break.
+
+#![no_std]
+
+/// The sync byte that follows a break
+pub const SYNC: u8 = 0x55;
+/// The largest frame identifier
+pub const MAX_ID: u8 = 0x3F;
+/// The most data bytes in a frame
+pub const MAX_DATA: usize = 8;
+
+/// LIN errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The identifier is larger than `MAX_ID`
+    InvalidId,
+    /// The frame has no data, or more than `MAX_DATA` bytes
+    InvalidLength,
+    /// The byte after a break wasn't `SYNC`
+    Sync,
+    /// The protected identifier's parity bits are wrong
+    Parity,
+    /// The response's checksum is wrong
+    Checksum,
+}
+
+/// Compute the protected identifier for `id`
+///
+/// The protected identifier carries two parity bits above the six identifier bits.
+///
+/// ```
+/// use teensy4_lin::protected_id;
+///
+/// assert_eq!(protected_id(0x00), Ok(0x80));
+/// assert_eq!(protected_id(0x01), Ok(0xC1));
+/// assert_eq!(protected_id(0x3C), Ok(0x3C));
+/// assert!(protected_id(0x40).is_err());
+/// ```
+pub fn protected_id(id: u8) -> Result<u8, Error> {
+    if id > MAX_ID {
+        return Err(Error::InvalidId);
+    }
+    let bit = |n: u8| (id >> n) & 1;
+    let p0 = bit(0) ^ bit(1) ^ bit(2) ^ bit(4);
+    let p1 = !(bit(1) ^ bit(3) ^ bit(4) ^ bit(5)) & 1;
+    Ok(id | (p0 << 6) | (p1 << 7))
+}
+
+/// Check a protected identifier's parity, and return the identifier
+///
+/// ```
+/// use teensy4_lin::{identifier, Error};
+///
+/// assert_eq!(identifier(0xC1), Ok(0x01));
+/// assert_eq!(identifier(0x01), Err(Error::Parity));
+/// ```
+pub fn identifier(pid: u8) -> Result<u8, Error> {
+    let id = pid & MAX_ID;
+    if protected_id(id)? == pid {
+        Ok(id)
+    } else {
+        Err(Error::Parity)
+    }
+}
+
+/// Returns the sync byte and protected identifier that follow a break
+pub fn header(id: u8) -> Result<[u8; 2], Error> {
+    Ok([SYNC, protected_id(id)?])
+}
+
+/// A LIN checksum model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// LIN 1.x checksum, over the data bytes
+    Classic,
+    /// LIN 2.x checksum, over the protected identifier and data bytes
+    Enhanced,
+}
+
+impl Checksum {
+    /// Returns the LIN 2.x checksum model for `id`
+    ///
+    /// Diagnostic frames, `0x3C` and `0x3D`, always use the classic checksum.
+    pub fn for_id(id: u8) -> Self {
+        match id {
+            0x3C | 0x3D => Checksum::Classic,
+            _ => Checksum::Enhanced,
+        }
+    }
+
+    /// Compute the checksum of `data`, sent with the protected identifier `pid`
+    ///
+    /// The checksum is the inverted eight-bit sum with carry.
+    ///
+    /// ```
+    /// use teensy4_lin::Checksum;
+    ///
+    /// assert_eq!(Checksum::Classic.compute(0x50, &[0x01, 0x02]), 0xFC);
+    /// assert_eq!(Checksum::Enhanced.compute(0x50, &[0x01, 0x02]), 0xAC);
+    /// ```
+    pub fn compute(self, pid: u8, data: &[u8]) -> u8 {
+        let first = match self {
+            Checksum::Classic => 0,
+            Checksum::Enhanced => pid as u16,
+        };
+        let sum = data.iter().fold(first, |sum, &byte| {
+            let sum = sum + byte as u16;
+            if sum > 0xFF {
+                sum - 0xFF
+            } else {
+                sum
+            }
+        });
+        !(sum as u8)
+    }
+}
+
+/// A LIN frame's identifier and data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    id: u8,
+    data: [u8; MAX_DATA],
+    len: u8,
+}
+
+impl Frame {
+    /// Create a frame with identifier `id`, and 1 to 8 bytes of `data`
+    pub fn new(id: u8, data: &[u8]) -> Result<Self, Error> {
+        if id > MAX_ID {
+            return Err(Error::InvalidId);
+        }
+        if data.is_empty() || data.len() > MAX_DATA {
+            return Err(Error::InvalidLength);
+        }
+        let mut frame = Frame {
+            id,
+            data: [0; MAX_DATA],
+            len: data.len() as u8,
+        };
+        frame.data[..data.len()].copy_from_slice(data);
+        Ok(frame)
+    }
+
+    /// Returns the frame identifier
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Returns the protected identifier
+    pub fn pid(&self) -> u8 {
+        // Identifier checked in new()
+        protected_id(self.id).unwrap()
+    }
+
+    /// Returns the frame's data
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    /// Write the frame's response, the data and checksum, into `buffer`
+    ///
+    /// Returns the part of `buffer` that holds the response.
+    pub fn response<'a>(&self, checksum: Checksum, buffer: &'a mut [u8; MAX_DATA + 1]) -> &'a [u8] {
+        let len = self.len as usize;
+        buffer[..len].copy_from_slice(self.data());
+        buffer[len] = checksum.compute(self.pid(), self.data());
+        &buffer[..=len]
+    }
+}
+
+/// An event from a [`Receiver`](struct.Receiver.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Received a header with this identifier
+    ///
+    /// Respond, or call [`expect_response`](struct.Receiver.html#method.expect_response)
+    /// to receive another node's response.
+    Header(u8),
+    /// Received a complete response
+    Frame(Frame),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Waiting for a break
+    Idle,
+    /// Waiting for the sync byte
+    Sync,
+    /// Waiting for the protected identifier
+    Pid,
+    /// Receiving `len` data bytes and a checksum
+    Response {
+        id: u8,
+        len: u8,
+        checksum: Checksum,
+        received: u8,
+        data: [u8; MAX_DATA],
+    },
+}
+
+/// Parses the bytes that follow a break
+///
+/// Call [`on_break`](#method.on_break) when the bus signals a break, then [`push`](#method.push)
+/// each received byte. A slave learns the frame identifier from the `Header` event. The node
+/// that expects a response calls [`expect_response`](#method.expect_response), since the
+/// response length depends on the identifier.
+///
+/// ```
+/// use teensy4_lin::{Checksum, Event, Frame, Receiver};
+///
+/// let mut receiver = Receiver::new();
+/// receiver.on_break();
+/// assert_eq!(receiver.push(0x55), Ok(None));
+/// assert_eq!(receiver.push(0x50), Ok(Some(Event::Header(0x10))));
+///
+/// receiver.expect_response(0x10, 2, Checksum::Enhanced).unwrap();
+/// assert_eq!(receiver.push(0x01), Ok(None));
+/// assert_eq!(receiver.push(0x02), Ok(None));
+/// let frame = Frame::new(0x10, &[0x01, 0x02]).unwrap();
+/// assert_eq!(receiver.push(0xAC), Ok(Some(Event::Frame(frame))));
+/// ```
+///
+/// An error returns the receiver to waiting for a break.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Receiver {
+    state: State,
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        Receiver::new()
+    }
+}
+
+impl Receiver {
+    /// Create a receiver that waits for a break
+    pub const fn new() -> Self {
+        Receiver { state: State::Idle }
+    }
+
+    /// Start a new frame
+    pub fn on_break(&mut self) {
+        self.state = State::Sync;
+    }
+
+    /// Receive a `len` byte response for the frame `id`
+    ///
+    /// Call after the `Header` event, or after a master sends a header that requests
+    /// a slave's response.
+    pub fn expect_response(&mut self, id: u8, len: usize, checksum: Checksum) -> Result<(), Error> {
+        if id > MAX_ID {
+            return Err(Error::InvalidId);
+        }
+        if len == 0 || len > MAX_DATA {
+            return Err(Error::InvalidLength);
+        }
+        self.state = State::Response {
+            id,
+            len: len as u8,
+            checksum,
+            received: 0,
+            data: [0; MAX_DATA],
+        };
+        Ok(())
+    }
+
+    /// Parse a received byte
+    ///
+    /// Returns an event when the byte completes a header or a response. Bytes
+    /// received while waiting for a break are ignored.
+    pub fn push(&mut self, byte: u8) -> Result<Option<Event>, Error> {
+        match &mut self.state {
+            State::Idle => Ok(None),
+            State::Sync => {
+                if byte == SYNC {
+                    self.state = State::Pid;
+                    Ok(None)
+                } else {
+                    self.state = State::Idle;
+                    Err(Error::Sync)
+                }
+            }
+            State::Pid => {
+                self.state = State::Idle;
+                identifier(byte).map(|id| Some(Event::Header(id)))
+            }
+            State::Response {
+                id,
+                len,
+                checksum,
+                received,
+                data,
+            } => {
+                if *received < *len {
+                    data[*received as usize] = byte;
+                    *received += 1;
+                    return Ok(None);
+                }
+                let frame = Frame {
+                    id: *id,
+                    data: *data,
+                    len: *len,
+                };
+                let expected = checksum.compute(frame.pid(), frame.data());
+                self.state = State::Idle;
+                if byte == expected {
+                    Ok(Some(Event::Frame(frame)))
+                } else {
+                    Err(Error::Checksum)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identifier, protected_id, Checksum, Error, Event, Frame, Receiver, MAX_ID};
+
+    /// Sum `bytes`, then fold the carries back into the low byte
+    fn reference_checksum(bytes: &[u8]) -> u8 {
+        let mut sum: u32 = bytes.iter().map(|&byte| byte as u32).sum();
+        while sum > 0xFF {
+            sum = (sum & 0xFF) + (sum >> 8);
+        }
+        !(sum as u8)
+    }
+
+    #[test]
+    fn classic_checksum_carries() {
+        assert_eq!(Checksum::Classic.compute(0xC1, &[0xFF, 0x01]), 0xFE);
+        assert_eq!(Checksum::Classic.compute(0xC1, &[0xFF, 0xFF]), 0x00);
+        assert_eq!(Checksum::Classic.compute(0xC1, &[0xFF; 8]), 0x00);
+        assert_eq!(Checksum::Classic.compute(0xC1, &[0x80; 3]), 0x7E);
+        let data = [0xF0, 0xE1, 0xD2, 0xC3, 0xB4, 0xA5, 0x96, 0x87];
+        assert_eq!(
+            Checksum::Classic.compute(0xC1, &data),
+            reference_checksum(&data)
+        );
+    }
+
+    #[test]
+    fn enhanced_checksum_carries() {
+        assert_eq!(Checksum::Enhanced.compute(0xC1, &[0x80, 0x80]), 0x3D);
+        assert_eq!(Checksum::Enhanced.compute(0xC0, &[0xFF; 8]), 0x3F);
+        for id in 0..=MAX_ID {
+            let pid = protected_id(id).unwrap();
+            let data = [0xFF, 0x80, 0x7F, 0x01, id, pid, 0xAA, 0x55];
+            let mut bytes = [pid; 9];
+            bytes[1..].copy_from_slice(&data);
+            assert_eq!(
+                Checksum::Enhanced.compute(pid, &data),
+                reference_checksum(&bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn parity() {
+        let mut accepted = 0;
+        for pid in 0..=0xFF {
+            match identifier(pid) {
+                Ok(id) => {
+                    assert_eq!(protected_id(id), Ok(pid));
+                    accepted += 1;
+                }
+                Err(error) => assert_eq!(error, Error::Parity),
+            }
+        }
+        assert_eq!(accepted, MAX_ID as usize + 1);
+        for id in 0..=MAX_ID {
+            let pid = protected_id(id).unwrap();
+            assert_eq!(identifier(pid ^ 0x40), Err(Error::Parity));
+            assert_eq!(identifier(pid ^ 0x80), Err(Error::Parity));
+        }
+    }
+
+    #[test]
+    fn receiver_rejects_parity() {
+        let mut receiver = Receiver::new();
+        receiver.on_break();
+        assert_eq!(receiver.push(0x55), Ok(None));
+        assert_eq!(receiver.push(0x10), Err(Error::Parity));
+        // Waits for the next break
+        assert_eq!(receiver.push(0x55), Ok(None));
+        assert_eq!(receiver.push(0x50), Ok(None));
+    }
+
+    #[test]
+    fn receiver_rejects_sync() {
+        let mut receiver = Receiver::new();
+        receiver.on_break();
+        assert_eq!(receiver.push(0x54), Err(Error::Sync));
+        assert_eq!(receiver.push(0x50), Ok(None));
+    }
+
+    #[test]
+    fn receiver_rejects_checksum() {
+        let mut receiver = Receiver::new();
+        receiver
+            .expect_response(0x10, 2, Checksum::Enhanced)
+            .unwrap();
+        assert_eq!(receiver.push(0x01), Ok(None));
+        assert_eq!(receiver.push(0x02), Ok(None));
+        // The classic checksum, instead of the enhanced checksum
+        assert_eq!(receiver.push(0xFC), Err(Error::Checksum));
+        assert_eq!(receiver.push(0xAC), Ok(None));
+    }
+
+    #[test]
+    fn short_frame() {
+        let mut receiver = Receiver::new();
+        receiver
+            .expect_response(0x10, 4, Checksum::Enhanced)
+            .unwrap();
+        assert_eq!(receiver.push(0x01), Ok(None));
+        assert_eq!(receiver.push(0x02), Ok(None));
+        // A break cuts the response short, and the next frame parses
+        receiver.on_break();
+        assert_eq!(receiver.push(0x55), Ok(None));
+        assert_eq!(receiver.push(0x50), Ok(Some(Event::Header(0x10))));
+        receiver
+            .expect_response(0x10, 2, Checksum::Enhanced)
+            .unwrap();
+        assert_eq!(receiver.push(0x01), Ok(None));
+        assert_eq!(receiver.push(0x02), Ok(None));
+        let frame = Frame::new(0x10, &[0x01, 0x02]).unwrap();
+        assert_eq!(receiver.push(0xAC), Ok(Some(Event::Frame(frame))));
+    }
+
+    #[test]
+    fn invalid_lengths_and_ids() {
+        assert_eq!(Frame::new(0x10, &[]), Err(Error::InvalidLength));
+        assert_eq!(Frame::new(0x10, &[0; 9]), Err(Error::InvalidLength));
+        assert_eq!(Frame::new(0x40, &[0]), Err(Error::InvalidId));
+        let mut receiver = Receiver::new();
+        assert_eq!(
+            receiver.expect_response(0x10, 0, Checksum::Classic),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            receiver.expect_response(0x10, 9, Checksum::Classic),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            receiver.expect_response(0x40, 1, Checksum::Classic),
+            Err(Error::InvalidId)
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Teensy 4 pin to pad tables, and pad to GPIO mappings
+//!
+//! Each Teensy pin connects to a processor pad. A pad has IOMUXC registers that select
+//! its function, and a GPIO port and bit that it uses in its GPIO alternate. This crate
+//! describes those mappings without touching hardware, so it builds and tests on the
+//! host. The `teensy4-bsp` crate re-exports the pad types in its `gpio` module, and it
+//! uses the table for the selected board.
+//!
+//! ```
+//! use teensy4_pads::{t40, Group, Pad};
+//!
+//! // Pin 13, the LED, is GPIO_B0_03, or GPIO2_IO03
+//! let led = t40::PADS[13];
+//! assert_eq!(led, Pad::new(Group::B0, 3));
+//! assert_eq!(led.gpio(), (2, 3));
+//! assert_eq!(led.mux_ctl(), 0x401F_8148);
+//! ```
+
+#![no_std]
+
+/// A group of processor pads
+///
+/// The pad's group determines its IOMUXC registers, and its GPIO port.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    /// `GPIO_EMC_00` through `GPIO_EMC_41`
+    Emc,
+    /// `GPIO_AD_B0_00` through `GPIO_AD_B0_15`
+    AdB0,
+    /// `GPIO_AD_B1_00` through `GPIO_AD_B1_15`
+    AdB1,
+    /// `GPIO_B0_00` through `GPIO_B0_15`
+    B0,
+    /// `GPIO_B1_00` through `GPIO_B1_15`
+    B1,
+    /// `GPIO_SD_B0_00` through `GPIO_SD_B0_05`
+    SdB0,
+    /// `GPIO_SD_B1_00` through `GPIO_SD_B1_11`
+    SdB1,
+}
+
+/// A processor pad, identified by its group and offset
+///
+/// `Pad { group: Group::AdB0, offset: 3 }` is the `GPIO_AD_B0_03` pad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pad {
+    /// The pad's group
+    pub group: Group,
+    /// The pad's offset within the group
+    pub offset: u8,
+}
+
+/// Offset of the first mux control register, `SW_MUX_CTL_PAD_GPIO_EMC_00`,
+/// from the IOMUXC base address
+const SW_MUX_CTL_PAD_BASE: u32 = 0x014;
+/// The pad control registers are a fixed distance from the mux control registers
+const SW_PAD_CTL_PAD_DISTANCE: u32 = 0x1F0;
+const IOMUXC_BASE: u32 = 0x401F_8000;
+
+impl Pad {
+    /// Creates a pad from a group and an offset
+    pub const fn new(group: Group, offset: u8) -> Self {
+        Pad { group, offset }
+    }
+
+    /// Returns the index of the pad, counting all pads from `GPIO_EMC_00`
+    const fn index(self) -> u32 {
+        let first = match self.group {
+            Group::Emc => 0,
+            Group::AdB0 => 42,
+            Group::AdB1 => 58,
+            Group::B0 => 74,
+            Group::B1 => 90,
+            Group::SdB0 => 106,
+            Group::SdB1 => 112,
+        };
+        first + self.offset as u32
+    }
+
+    /// Returns the address of the pad's `SW_MUX_CTL_PAD` register
+    pub const fn mux_ctl(self) -> u32 {
+        IOMUXC_BASE + SW_MUX_CTL_PAD_BASE + 4 * self.index()
+    }
+
+    /// Returns the address of the pad's `SW_PAD_CTL_PAD` register
+    pub const fn pad_ctl(self) -> u32 {
+        self.mux_ctl() + SW_PAD_CTL_PAD_DISTANCE
+    }
+
+    /// Returns the pad's GPIO port and bit, when the pad is in its GPIO
+    /// alternate (`Alt5`)
+    ///
+    /// The port is one of the 'normal' GPIO ports, 1 through 4. Add 5 to
+    /// find the fast GPIO port.
+    pub const fn gpio(self) -> (u8, u8) {
+        let offset = self.offset;
+        match self.group {
+            Group::Emc if offset < 32 => (4, offset),
+            Group::Emc => (3, offset - 14),
+            Group::AdB0 => (1, offset),
+            Group::AdB1 => (1, offset + 16),
+            Group::B0 => (2, offset),
+            Group::B1 => (2, offset + 16),
+            Group::SdB0 => (3, offset + 12),
+            Group::SdB1 => (3, offset),
+        }
+    }
+}
+
+/// Teensy 4.0 pads
+pub mod t40 {
+    use super::{Group::*, Pad};
+
+    /// The number of pins on the Teensy 4.0
+    pub const NUM_PINS: usize = 40;
+
+    /// Pads indexed by Teensy pin number
+    pub const PADS: [Pad; NUM_PINS] = [
+        Pad::new(AdB0, 3),  // 0: GPIO_AD_B0_03
+        Pad::new(AdB0, 2),  // 1: GPIO_AD_B0_02
+        Pad::new(Emc, 4),   // 2: GPIO_EMC_04
+        Pad::new(Emc, 5),   // 3: GPIO_EMC_05
+        Pad::new(Emc, 6),   // 4: GPIO_EMC_06
+        Pad::new(Emc, 8),   // 5: GPIO_EMC_08
+        Pad::new(B0, 10),   // 6: GPIO_B0_10
+        Pad::new(B1, 1),    // 7: GPIO_B1_01
+        Pad::new(B1, 0),    // 8: GPIO_B1_00
+        Pad::new(B0, 11),   // 9: GPIO_B0_11
+        Pad::new(B0, 0),    // 10: GPIO_B0_00
+        Pad::new(B0, 2),    // 11: GPIO_B0_02
+        Pad::new(B0, 1),    // 12: GPIO_B0_01
+        Pad::new(B0, 3),    // 13: GPIO_B0_03
+        Pad::new(AdB1, 2),  // 14: GPIO_AD_B1_02
+        Pad::new(AdB1, 3),  // 15: GPIO_AD_B1_03
+        Pad::new(AdB1, 7),  // 16: GPIO_AD_B1_07
+        Pad::new(AdB1, 6),  // 17: GPIO_AD_B1_06
+        Pad::new(AdB1, 1),  // 18: GPIO_AD_B1_01
+        Pad::new(AdB1, 0),  // 19: GPIO_AD_B1_00
+        Pad::new(AdB1, 10), // 20: GPIO_AD_B1_10
+        Pad::new(AdB1, 11), // 21: GPIO_AD_B1_11
+        Pad::new(AdB1, 8),  // 22: GPIO_AD_B1_08
+        Pad::new(AdB1, 9),  // 23: GPIO_AD_B1_09
+        Pad::new(AdB0, 12), // 24: GPIO_AD_B0_12
+        Pad::new(AdB0, 13), // 25: GPIO_AD_B0_13
+        Pad::new(AdB1, 14), // 26: GPIO_AD_B1_14
+        Pad::new(AdB1, 15), // 27: GPIO_AD_B1_15
+        Pad::new(Emc, 32),  // 28: GPIO_EMC_32
+        Pad::new(Emc, 31),  // 29: GPIO_EMC_31
+        Pad::new(Emc, 37),  // 30: GPIO_EMC_37
+        Pad::new(Emc, 36),  // 31: GPIO_EMC_36
+        Pad::new(B0, 12),   // 32: GPIO_B0_12
+        Pad::new(Emc, 7),   // 33: GPIO_EMC_07
+        Pad::new(SdB0, 3),  // 34: GPIO_SD_B0_03
+        Pad::new(SdB0, 2),  // 35: GPIO_SD_B0_02
+        Pad::new(SdB0, 1),  // 36: GPIO_SD_B0_01
+        Pad::new(SdB0, 0),  // 37: GPIO_SD_B0_00
+        Pad::new(SdB0, 5),  // 38: GPIO_SD_B0_05
+        Pad::new(SdB0, 4),  // 39: GPIO_SD_B0_04
+    ];
+}
+
+/// Teensy 4.1 pads
+pub mod t41 {
+    use super::{Group::*, Pad};
+
+    /// The number of pins on the Teensy 4.1
+    pub const NUM_PINS: usize = 55;
+
+    /// Pads indexed by Teensy pin number
+    pub const PADS: [Pad; NUM_PINS] = [
+        Pad::new(AdB0, 3),  // 0: GPIO_AD_B0_03
+        Pad::new(AdB0, 2),  // 1: GPIO_AD_B0_02
+        Pad::new(Emc, 4),   // 2: GPIO_EMC_04
+        Pad::new(Emc, 5),   // 3: GPIO_EMC_05
+        Pad::new(Emc, 6),   // 4: GPIO_EMC_06
+        Pad::new(Emc, 8),   // 5: GPIO_EMC_08
+        Pad::new(B0, 10),   // 6: GPIO_B0_10
+        Pad::new(B1, 1),    // 7: GPIO_B1_01
+        Pad::new(B1, 0),    // 8: GPIO_B1_00
+        Pad::new(B0, 11),   // 9: GPIO_B0_11
+        Pad::new(B0, 0),    // 10: GPIO_B0_00
+        Pad::new(B0, 2),    // 11: GPIO_B0_02
+        Pad::new(B0, 1),    // 12: GPIO_B0_01
+        Pad::new(B0, 3),    // 13: GPIO_B0_03
+        Pad::new(AdB1, 2),  // 14: GPIO_AD_B1_02
+        Pad::new(AdB1, 3),  // 15: GPIO_AD_B1_03
+        Pad::new(AdB1, 7),  // 16: GPIO_AD_B1_07
+        Pad::new(AdB1, 6),  // 17: GPIO_AD_B1_06
+        Pad::new(AdB1, 1),  // 18: GPIO_AD_B1_01
+        Pad::new(AdB1, 0),  // 19: GPIO_AD_B1_00
+        Pad::new(AdB1, 10), // 20: GPIO_AD_B1_10
+        Pad::new(AdB1, 11), // 21: GPIO_AD_B1_11
+        Pad::new(AdB1, 8),  // 22: GPIO_AD_B1_08
+        Pad::new(AdB1, 9),  // 23: GPIO_AD_B1_09
+        Pad::new(AdB0, 12), // 24: GPIO_AD_B0_12
+        Pad::new(AdB0, 13), // 25: GPIO_AD_B0_13
+        Pad::new(AdB1, 14), // 26: GPIO_AD_B1_14
+        Pad::new(AdB1, 15), // 27: GPIO_AD_B1_15
+        Pad::new(Emc, 32),  // 28: GPIO_EMC_32
+        Pad::new(Emc, 31),  // 29: GPIO_EMC_31
+        Pad::new(Emc, 37),  // 30: GPIO_EMC_37
+        Pad::new(Emc, 36),  // 31: GPIO_EMC_36
+        Pad::new(B0, 12),   // 32: GPIO_B0_12
+        Pad::new(Emc, 7),   // 33: GPIO_EMC_07
+        Pad::new(B1, 13),   // 34: GPIO_B1_13
+        Pad::new(B1, 12),   // 35: GPIO_B1_12
+        Pad::new(B1, 2),    // 36: GPIO_B1_02
+        Pad::new(B1, 3),    // 37: GPIO_B1_03
+        Pad::new(AdB1, 12), // 38: GPIO_AD_B1_12
+        Pad::new(AdB1, 13), // 39: GPIO_AD_B1_13
+        Pad::new(AdB1, 4),  // 40: GPIO_AD_B1_04
+        Pad::new(AdB1, 5),  // 41: GPIO_AD_B1_05
+        Pad::new(SdB0, 3),  // 42: GPIO_SD_B0_03
+        Pad::new(SdB0, 2),  // 43: GPIO_SD_B0_02
+        Pad::new(SdB0, 1),  // 44: GPIO_SD_B0_01
+        Pad::new(SdB0, 0),  // 45: GPIO_SD_B0_00
+        Pad::new(SdB0, 5),  // 46: GPIO_SD_B0_05
+        Pad::new(SdB0, 4),  // 47: GPIO_SD_B0_04
+        Pad::new(Emc, 24),  // 48: GPIO_EMC_24
+        Pad::new(Emc, 27),  // 49: GPIO_EMC_27
+        Pad::new(Emc, 28),  // 50: GPIO_EMC_28
+        Pad::new(Emc, 22),  // 51: GPIO_EMC_22
+        Pad::new(Emc, 26),  // 52: GPIO_EMC_26
+        Pad::new(Emc, 25),  // 53: GPIO_EMC_25
+        Pad::new(Emc, 29),  // 54: GPIO_EMC_29
+    ];
+}
+
+/// Teensy MicroMod pads
+pub mod micromod {
+    use super::{Group::*, Pad};
+
+    /// The number of pins on the Teensy MicroMod
+    pub const NUM_PINS: usize = 46;
+
+    /// Pads indexed by Teensy pin number
+    pub const PADS: [Pad; NUM_PINS] = [
+        Pad::new(AdB0, 3),  // 0: GPIO_AD_B0_03
+        Pad::new(AdB0, 2),  // 1: GPIO_AD_B0_02
+        Pad::new(Emc, 4),   // 2: GPIO_EMC_04
+        Pad::new(Emc, 5),   // 3: GPIO_EMC_05
+        Pad::new(Emc, 6),   // 4: GPIO_EMC_06
+        Pad::new(Emc, 8),   // 5: GPIO_EMC_08
+        Pad::new(B0, 10),   // 6: GPIO_B0_10
+        Pad::new(B1, 1),    // 7: GPIO_B1_01
+        Pad::new(B1, 0),    // 8: GPIO_B1_00
+        Pad::new(B0, 11),   // 9: GPIO_B0_11
+        Pad::new(B0, 0),    // 10: GPIO_B0_00
+        Pad::new(B0, 2),    // 11: GPIO_B0_02
+        Pad::new(B0, 1),    // 12: GPIO_B0_01
+        Pad::new(B0, 3),    // 13: GPIO_B0_03
+        Pad::new(AdB1, 2),  // 14: GPIO_AD_B1_02
+        Pad::new(AdB1, 3),  // 15: GPIO_AD_B1_03
+        Pad::new(AdB1, 7),  // 16: GPIO_AD_B1_07
+        Pad::new(AdB1, 6),  // 17: GPIO_AD_B1_06
+        Pad::new(AdB1, 1),  // 18: GPIO_AD_B1_01
+        Pad::new(AdB1, 0),  // 19: GPIO_AD_B1_00
+        Pad::new(AdB1, 10), // 20: GPIO_AD_B1_10
+        Pad::new(AdB1, 11), // 21: GPIO_AD_B1_11
+        Pad::new(AdB1, 8),  // 22: GPIO_AD_B1_08
+        Pad::new(AdB1, 9),  // 23: GPIO_AD_B1_09
+        Pad::new(AdB0, 12), // 24: GPIO_AD_B0_12
+        Pad::new(AdB0, 13), // 25: GPIO_AD_B0_13
+        Pad::new(AdB1, 14), // 26: GPIO_AD_B1_14
+        Pad::new(AdB1, 15), // 27: GPIO_AD_B1_15
+        Pad::new(Emc, 32),  // 28: GPIO_EMC_32
+        Pad::new(Emc, 31),  // 29: GPIO_EMC_31
+        Pad::new(Emc, 37),  // 30: GPIO_EMC_37
+        Pad::new(Emc, 36),  // 31: GPIO_EMC_36
+        Pad::new(B0, 12),   // 32: GPIO_B0_12
+        Pad::new(Emc, 7),   // 33: GPIO_EMC_07
+        Pad::new(B1, 13),   // 34: GPIO_B1_13
+        Pad::new(B1, 12),   // 35: GPIO_B1_12
+        Pad::new(B1, 2),    // 36: GPIO_B1_02
+        Pad::new(B1, 3),    // 37: GPIO_B1_03
+        Pad::new(AdB1, 12), // 38: GPIO_AD_B1_12
+        Pad::new(AdB1, 13), // 39: GPIO_AD_B1_13
+        Pad::new(AdB1, 4),  // 40: GPIO_AD_B1_04
+        Pad::new(AdB1, 5),  // 41: GPIO_AD_B1_05
+        Pad::new(SdB0, 3),  // 42: GPIO_SD_B0_03
+        Pad::new(SdB0, 2),  // 43: GPIO_SD_B0_02
+        Pad::new(SdB0, 1),  // 44: GPIO_SD_B0_01
+        Pad::new(SdB0, 0),  // 45: GPIO_SD_B0_00
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{micromod, t40, t41, Group, Pad};
+
+    /// Register addresses from the reference manual's IOMUXC chapter
+    #[test]
+    fn pad_registers() {
+        assert_eq!(Pad::new(Group::Emc, 0).mux_ctl(), 0x401F_8014);
+        assert_eq!(Pad::new(Group::Emc, 0).pad_ctl(), 0x401F_8204);
+        assert_eq!(Pad::new(Group::Emc, 41).mux_ctl(), 0x401F_80B8);
+        assert_eq!(Pad::new(Group::AdB0, 0).mux_ctl(), 0x401F_80BC);
+        assert_eq!(Pad::new(Group::AdB1, 0).mux_ctl(), 0x401F_80FC);
+        assert_eq!(Pad::new(Group::B0, 3).mux_ctl(), 0x401F_8148);
+        assert_eq!(Pad::new(Group::B1, 0).mux_ctl(), 0x401F_817C);
+        assert_eq!(Pad::new(Group::SdB0, 0).mux_ctl(), 0x401F_81BC);
+        assert_eq!(Pad::new(Group::SdB1, 0).mux_ctl(), 0x401F_81D4);
+        assert_eq!(Pad::new(Group::SdB1, 11).pad_ctl(), 0x401F_83F0);
+    }
+
+    /// GPIO ports and bits from the reference manual's pad tables
+    #[test]
+    fn pad_gpio() {
+        assert_eq!(Pad::new(Group::Emc, 4).gpio(), (4, 4));
+        assert_eq!(Pad::new(Group::Emc, 31).gpio(), (4, 31));
+        assert_eq!(Pad::new(Group::Emc, 32).gpio(), (3, 18));
+        assert_eq!(Pad::new(Group::Emc, 41).gpio(), (3, 27));
+        assert_eq!(Pad::new(Group::AdB0, 3).gpio(), (1, 3));
+        assert_eq!(Pad::new(Group::AdB1, 2).gpio(), (1, 18));
+        assert_eq!(Pad::new(Group::B0, 3).gpio(), (2, 3));
+        assert_eq!(Pad::new(Group::B1, 0).gpio(), (2, 16));
+        assert_eq!(Pad::new(Group::SdB0, 0).gpio(), (3, 12));
+        assert_eq!(Pad::new(Group::SdB1, 0).gpio(), (3, 0));
+    }
+
+    /// Pins 0 through 33 are the same on every board
+    #[test]
+    fn common_pins() {
+        for pads in [&t40::PADS[..], &t41::PADS[..], &micromod::PADS[..]].iter() {
+            assert_eq!(pads[..34], t40::PADS[..34]);
+        }
+        let pads = &t40::PADS;
+        assert_eq!(pads[0], Pad::new(Group::AdB0, 3));
+        assert_eq!(pads[2], Pad::new(Group::Emc, 4));
+        assert_eq!(pads[7], Pad::new(Group::B1, 1));
+        assert_eq!(pads[13], Pad::new(Group::B0, 3));
+        assert_eq!(pads[14], Pad::new(Group::AdB1, 2));
+        assert_eq!(pads[28], Pad::new(Group::Emc, 32));
+        assert_eq!(pads[29], Pad::new(Group::Emc, 31));
+        assert_eq!(pads[33], Pad::new(Group::Emc, 7));
+    }
+
+    /// Sample pins and their fast GPIO port and bit, from the board schematics
+    #[test]
+    fn fast_gpio() {
+        let fast = |pad: Pad| {
+            let (port, bit) = pad.gpio();
+            (port + 5, bit)
+        };
+        assert_eq!(fast(t40::PADS[0]), (6, 3));
+        assert_eq!(fast(t40::PADS[2]), (9, 4));
+        assert_eq!(fast(t40::PADS[13]), (7, 3));
+        assert_eq!(fast(t40::PADS[28]), (8, 18));
+        assert_eq!(fast(t40::PADS[37]), (8, 12));
+        assert_eq!(fast(t41::PADS[34]), (7, 29));
+        assert_eq!(fast(t41::PADS[45]), (8, 12));
+        assert_eq!(fast(t41::PADS[54]), (9, 29));
+        assert_eq!(fast(micromod::PADS[40]), (6, 20));
+    }
+
+    #[test]
+    fn teensy40_pins() {
+        assert_eq!(t40::NUM_PINS, 40);
+        assert_eq!(t40::PADS[34], Pad::new(Group::SdB0, 3));
+        assert_eq!(t40::PADS[37], Pad::new(Group::SdB0, 0));
+        assert_eq!(t40::PADS[39], Pad::new(Group::SdB0, 4));
+    }
+
+    #[test]
+    fn teensy41_pins() {
+        assert_eq!(t41::NUM_PINS, 55);
+        assert_eq!(t41::PADS[34], Pad::new(Group::B1, 13));
+        assert_eq!(t41::PADS[40], Pad::new(Group::AdB1, 4));
+        assert_eq!(t41::PADS[45], Pad::new(Group::SdB0, 0));
+        assert_eq!(t41::PADS[54], Pad::new(Group::Emc, 29));
+    }
+
+    #[test]
+    fn micromod_pins() {
+        assert_eq!(micromod::NUM_PINS, 46);
+        assert_eq!(micromod::PADS[34], Pad::new(Group::B1, 13));
+        assert_eq!(micromod::PADS[40], Pad::new(Group::AdB1, 4));
+        assert_eq!(micromod::PADS[45], Pad::new(Group::SdB0, 0));
+    }
+
+    /// No pad is connected to two pins
+    #[test]
+    fn pads_are_unique() {
+        for pads in [&t40::PADS[..], &t41::PADS[..], &micromod::PADS[..]].iter() {
+            for (pin, pad) in pads.iter().enumerate() {
+                assert_eq!(pads.iter().position(|other| other == pad), Some(pin));
+            }
+        }
+    }
+}
@@ -6,6 +6,21 @@ use std::path::PathBuf;
 /// Files to watch for changes
 static RERUN_IF_CHANGED: &[&str] = &["build.rs", "link.x"];
 
+/// Length of the FLASH region, in KiB, for the selected board
+///
+/// The region is smaller than the board's flash chip. The Teensy
+/// bootloader reserves the tail of flash for EEPROM emulation and
+/// the restore program.
+fn flash_length_kib() -> u32 {
+    if env::var_os("CARGO_FEATURE_MICROMOD").is_some() {
+        16128
+    } else if env::var_os("CARGO_FEATURE_TEENSY41").is_some() {
+        7936
+    } else {
+        1984
+    }
+}
+
 fn main() {
     for &rerun_if_changed in RERUN_IF_CHANGED.iter() {
         println!("cargo:rerun-if-changed={}", rerun_if_changed);
@@ -17,6 +32,20 @@ fn main() {
     let mut script = File::create(out_dir.join("link.x")).unwrap();
     script.write_all(link_x).unwrap();
 
+    let mut memory = File::create(out_dir.join("memory.x")).unwrap();
+    writeln!(
+        memory,
+        "MEMORY
+{{
+    ITCM    (rwx): ORIGIN = 0x00000000, LENGTH = 512K
+    DTCM    (rwx): ORIGIN = 0x20000000, LENGTH = 512K
+    RAM     (rwx): ORIGIN = 0x20200000, LENGTH = 512K
+    FLASH   (rwx): ORIGIN = 0x60000000, LENGTH = {}K
+}}",
+        flash_length_kib()
+    )
+    .unwrap();
+
     fs::copy("./bin/libt4boot.a", out_dir.join("libt4boot.a")).unwrap();
     println!("cargo:rustc-link-lib=static=t4boot");
     println!("cargo:rustc-link-search={}", out_dir.display());
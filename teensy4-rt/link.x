@@ -1,12 +1,6 @@
 INCLUDE device.x
-
-MEMORY
-{
-    ITCM    (rwx): ORIGIN = 0x00000000, LENGTH = 512K
-    DTCM    (rwx): ORIGIN = 0x20000000, LENGTH = 512K
-    RAM     (rwx): ORIGIN = 0x20200000, LENGTH = 512K
-    FLASH   (rwx): ORIGIN = 0x60000000, LENGTH = 1984K
-}
+/* Board-specific memory map, generated by the build script */
+INCLUDE memory.x
 
 /* Symbol provided by Rust */
 EXTERN(_reset);
@@ -116,10 +110,13 @@ SECTIONS
         __ebss = .;
     } > DTCM
 
+    /* The MPU marks the first 64K of RAM as non-cacheable, for DMA buffers */
     .dma (NOLOAD) :
     {
+        __sdma = .;
         *(.dmabuffers); /* Compat with USB */
         . = ALIGN(16);
+        __edma = .;
     } > RAM
 
     /DISCARD/ :
@@ -161,6 +158,9 @@ ERROR(teensy4-rt): the LMA of .data is not 4-byte aligned");
 ASSERT(__sbss % 4 == 0 && __ebss % 4 == 0, "
 ERROR(teensy4-rt): .bss is not 4-byte aligned");
 
+ASSERT(__sdma == ORIGIN(RAM) && __edma - __sdma <= 64K, "
+ERROR(teensy4-rt): .dma must start at RAM, and fit in the 64K non-cacheable MPU region");
+
 ASSERT(__stext % 4 == 0 && __etext % 4 == 0, "
 ERROR(teensy4-rt): .text is not 4-byte aligned");
 
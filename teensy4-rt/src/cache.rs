@@ -42,6 +42,7 @@ const _MEM_CACHE_WB: u32 = scb_mpu_rasr_tex(0) | SCB_MPU_RASR_C | SCB_MPU_RASR_B
 const MEM_CACHE_WBWA: u32 = scb_mpu_rasr_tex(1) | SCB_MPU_RASR_C | SCB_MPU_RASR_B;
 const MEM_NOCACHE: u32 = scb_mpu_rasr_tex(1);
 const DEV_NOCACHE: u32 = scb_mpu_rasr_tex(2);
+const SIZE_64K: u32 = scb_mpu_rasr_size(15) | SCB_MPU_RASR_ENABLE;
 const SIZE_128K: u32 = scb_mpu_rasr_size(16) | SCB_MPU_RASR_ENABLE;
 const _SIZE_256K: u32 = scb_mpu_rasr_size(17) | SCB_MPU_RASR_ENABLE;
 const SIZE_512K: u32 = scb_mpu_rasr_size(18) | SCB_MPU_RASR_ENABLE;
@@ -78,6 +79,17 @@ pub unsafe fn init() {
     ptr::write_volatile(SCB_MPU_RBAR, 0x6000_0000 | region(5)); // QSPI Flash
     ptr::write_volatile(SCB_MPU_RASR, MEM_CACHE_WBWA | READONLY | SIZE_16M);
 
+    // Overlaps the start of RAM, which holds the .dma section. The higher region number
+    // takes precedence, so that DMA buffers are coherent without cache maintenance.
+    ptr::write_volatile(SCB_MPU_RBAR, 0x2020_0000 | region(7)); // DMA buffers
+    ptr::write_volatile(SCB_MPU_RASR, MEM_NOCACHE | READWRITE | NOEXEC | SIZE_64K);
+
+    #[cfg(feature = "teensy41")]
+    {
+        ptr::write_volatile(SCB_MPU_RBAR, 0x7000_0000 | region(6)); // FlexSPI2 (PSRAM / flash pads)
+        ptr::write_volatile(SCB_MPU_RASR, MEM_CACHE_WBWA | READWRITE | NOEXEC | SIZE_16M);
+    }
+
     ptr::write_volatile(SCB_MPU_CTRL, SCB_MPU_CTRL_ENABLE);
 
     asm::dsb();
@@ -0,0 +1,268 @@
+//! Hobby servo and ESC pulse calibration and timing
+//!
+//! Hobby servos and electronic speed controllers (ESCs) read the width of a periodic
+//! pulse. A 1ms pulse is one end of the range, and a 2ms pulse is the other. Servos
+//! expect a new pulse every 2.5ms to 20ms, which is a 50Hz to 400Hz switching frequency.
+//!
+//! This crate converts angles and throttles to pulse widths, and pulse widths to PWM
+//! compare values, without touching hardware, so it builds and tests on the host. The
+//! `teensy4-bsp` crate re-exports it in its `servo` module, which drives servos from the
+//! FlexPWM outputs.
+//!
+//! A [`Calibration`](struct.Calibration.html) describes the pulse widths that a
+//! servo accepts, and the angle that the servo sweeps:
+//!
+//! ```
+//! use teensy4_servo::{compare, Calibration};
+//!
+//! // A 270 degree servo that takes 500us to 2500us pulses
+//! let calibration = Calibration::new(500, 2_500, 270.0).unwrap();
+//! assert_eq!(calibration.angle_to_pulse_us(135.0), 1_500);
+//!
+//! // At 50Hz, the PWM period is 46875 counts. The
+//! // submodule's modulo is the last count.
+//! assert_eq!(compare(1_500, 50, 46_874), 3_516);
+//! ```
+
+#![no_std]
+
+/// The lowest switching frequency for servos and ESCs
+pub const MIN_FREQUENCY_HZ: u32 = 50;
+/// The highest switching frequency for servos and ESCs
+pub const MAX_FREQUENCY_HZ: u32 = 400;
+
+const MICROS_PER_SEC: u64 = 1_000_000;
+
+/// Servo errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The calibration's minimum pulse is zero, or it isn't less than the
+    /// maximum pulse, or the angle range isn't positive
+    InvalidCalibration,
+    /// The frequency is outside of `MIN_FREQUENCY_HZ` and `MAX_FREQUENCY_HZ`,
+    /// or the period is too short for the calibration's maximum pulse
+    Frequency,
+}
+
+/// Pulse widths and angles for a servo or ESC
+///
+/// The minimum pulse moves the servo to zero degrees, or idles the ESC. The maximum
+/// pulse moves the servo to `range_deg` degrees, or runs the ESC at full throttle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    min_pulse_us: u32,
+    max_pulse_us: u32,
+    range_deg: f32,
+}
+
+impl Calibration {
+    /// A typical 180 degree servo, or an ESC, that takes 1ms to 2ms pulses
+    pub const STANDARD: Calibration = Calibration {
+        min_pulse_us: 1_000,
+        max_pulse_us: 2_000,
+        range_deg: 180.0,
+    };
+
+    /// Create a calibration from the shortest and longest pulses, in microseconds, and
+    /// the angle between them, in degrees
+    ///
+    /// ```
+    /// use teensy4_servo::{Calibration, Error};
+    ///
+    /// assert!(Calibration::new(900, 2_100, 180.0).is_ok());
+    /// assert_eq!(Calibration::new(2_000, 1_000, 180.0), Err(Error::InvalidCalibration));
+    /// assert_eq!(Calibration::new(0, 1_000, 180.0), Err(Error::InvalidCalibration));
+    /// assert_eq!(Calibration::new(1_000, 2_000, 0.0), Err(Error::InvalidCalibration));
+    /// ```
+    pub fn new(min_pulse_us: u32, max_pulse_us: u32, range_deg: f32) -> Result<Self, Error> {
+        if min_pulse_us == 0
+            || min_pulse_us >= max_pulse_us
+            || range_deg.is_nan()
+            || range_deg <= 0.0
+        {
+            return Err(Error::InvalidCalibration);
+        }
+        Ok(Calibration {
+            min_pulse_us,
+            max_pulse_us,
+            range_deg,
+        })
+    }
+
+    /// Returns the shortest pulse, in microseconds
+    pub fn min_pulse_us(&self) -> u32 {
+        self.min_pulse_us
+    }
+
+    /// Returns the longest pulse, in microseconds
+    pub fn max_pulse_us(&self) -> u32 {
+        self.max_pulse_us
+    }
+
+    /// Returns the angle between the shortest and longest pulses, in degrees
+    pub fn range_deg(&self) -> f32 {
+        self.range_deg
+    }
+
+    /// Limit `pulse_us` to the calibrated pulses
+    ///
+    /// ```
+    /// use teensy4_servo::Calibration;
+    ///
+    /// let calibration = Calibration::STANDARD;
+    /// assert_eq!(calibration.clamp(500), 1_000);
+    /// assert_eq!(calibration.clamp(1_250), 1_250);
+    /// assert_eq!(calibration.clamp(2_500), 2_000);
+    /// ```
+    pub fn clamp(&self, pulse_us: u32) -> u32 {
+        pulse_us.clamp(self.min_pulse_us, self.max_pulse_us)
+    }
+
+    /// Returns the pulse that's `fraction` of the way from the shortest to the
+    /// longest pulse. `fraction` saturates at 0 and 1.
+    fn interpolate(&self, fraction: f32) -> u32 {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let span = (self.max_pulse_us - self.min_pulse_us) as f32;
+        self.min_pulse_us + (fraction * span + 0.5) as u32
+    }
+
+    /// Returns the pulse, in microseconds, that moves a servo to `angle_deg`
+    ///
+    /// Angles saturate at zero and `range_deg`.
+    ///
+    /// ```
+    /// use teensy4_servo::Calibration;
+    ///
+    /// let calibration = Calibration::STANDARD;
+    /// assert_eq!(calibration.angle_to_pulse_us(0.0), 1_000);
+    /// assert_eq!(calibration.angle_to_pulse_us(45.0), 1_250);
+    /// assert_eq!(calibration.angle_to_pulse_us(180.0), 2_000);
+    /// assert_eq!(calibration.angle_to_pulse_us(-10.0), 1_000);
+    /// assert_eq!(calibration.angle_to_pulse_us(200.0), 2_000);
+    /// ```
+    pub fn angle_to_pulse_us(&self, angle_deg: f32) -> u32 {
+        self.interpolate(angle_deg / self.range_deg)
+    }
+
+    /// Returns the angle, in degrees, for a pulse
+    ///
+    /// Pulses are clamped to the calibrated pulses.
+    ///
+    /// ```
+    /// use teensy4_servo::Calibration;
+    ///
+    /// let calibration = Calibration::STANDARD;
+    /// assert_eq!(calibration.pulse_us_to_angle(1_500), 90.0);
+    /// assert_eq!(calibration.pulse_us_to_angle(3_000), 180.0);
+    /// ```
+    pub fn pulse_us_to_angle(&self, pulse_us: u32) -> f32 {
+        let offset = (self.clamp(pulse_us) - self.min_pulse_us) as f32;
+        let span = (self.max_pulse_us - self.min_pulse_us) as f32;
+        offset / span * self.range_deg
+    }
+
+    /// Returns the pulse, in microseconds, that runs an ESC at `throttle`
+    ///
+    /// `throttle` is a fraction from 0 (idle) to 1 (full throttle). It saturates
+    /// at 0 and 1.
+    ///
+    /// ```
+    /// use teensy4_servo::Calibration;
+    ///
+    /// let calibration = Calibration::STANDARD;
+    /// assert_eq!(calibration.throttle_to_pulse_us(0.0), 1_000);
+    /// assert_eq!(calibration.throttle_to_pulse_us(0.5), 1_500);
+    /// assert_eq!(calibration.throttle_to_pulse_us(1.5), 2_000);
+    /// ```
+    pub fn throttle_to_pulse_us(&self, throttle: f32) -> u32 {
+        self.interpolate(throttle)
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration::STANDARD
+    }
+}
+
+/// Returns the switching period, in microseconds
+///
+/// ```
+/// use teensy4_servo::period_us;
+///
+/// assert_eq!(period_us(50), 20_000);
+/// assert_eq!(period_us(400), 2_500);
+/// ```
+pub fn period_us(frequency_hz: u32) -> u32 {
+    (MICROS_PER_SEC / frequency_hz.max(1) as u64) as u32
+}
+
+/// Check that a servo with `calibration` can switch at `frequency_hz`
+///
+/// The frequency must be between `MIN_FREQUENCY_HZ` and `MAX_FREQUENCY_HZ`, and the
+/// longest pulse must be shorter than the period.
+///
+/// ```
+/// use teensy4_servo::{check_frequency, Calibration, Error};
+///
+/// let calibration = Calibration::STANDARD;
+/// assert_eq!(check_frequency(50, &calibration), Ok(()));
+/// assert_eq!(check_frequency(400, &calibration), Ok(()));
+/// assert_eq!(check_frequency(1_000, &calibration), Err(Error::Frequency));
+///
+/// // 2.5ms pulses don't fit in a 400Hz period
+/// let wide = Calibration::new(500, 2_500, 270.0).unwrap();
+/// assert_eq!(check_frequency(400, &wide), Err(Error::Frequency));
+/// assert_eq!(check_frequency(333, &wide), Ok(()));
+/// ```
+pub fn check_frequency(frequency_hz: u32, calibration: &Calibration) -> Result<(), Error> {
+    if (MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ).contains(&frequency_hz)
+        && calibration.max_pulse_us < period_us(frequency_hz)
+    {
+        Ok(())
+    } else {
+        Err(Error::Frequency)
+    }
+}
+
+/// Returns the PWM compare value for a pulse
+///
+/// `modulo` is the last count of the PWM period, so the period is `modulo + 1` counts
+/// long. The compare value is the number of counts that the output is high. It's
+/// rounded to the nearest count, and it saturates at `modulo`.
+///
+/// ```
+/// use teensy4_servo::compare;
+///
+/// // 150MHz IPG clock, divided by 64, at 50Hz
+/// assert_eq!(compare(1_000, 50, 46_874), 2_344);
+/// assert_eq!(compare(2_000, 50, 46_874), 4_688);
+/// // 150MHz IPG clock, divided by 8, at 400Hz
+/// assert_eq!(compare(1_000, 400, 46_874), 18_750);
+/// assert_eq!(compare(2_000, 400, 46_874), 37_500);
+/// // A pulse that's longer than the period
+/// assert_eq!(compare(3_000, 400, 46_874), 46_874);
+/// ```
+pub fn compare(pulse_us: u32, frequency_hz: u32, modulo: u16) -> u16 {
+    let counts = modulo as u64 + 1;
+    let product = pulse_us as u64 * frequency_hz as u64 * counts;
+    let compare = (product + MICROS_PER_SEC / 2) / MICROS_PER_SEC;
+    compare.min(modulo as u64) as u16
+}
+
+/// Returns the pulse, in microseconds, for a PWM compare value
+///
+/// This is the inverse of [`compare`](fn.compare.html), rounded to the nearest
+/// microsecond.
+///
+/// ```
+/// use teensy4_servo::{compare, pulse_us};
+///
+/// assert_eq!(pulse_us(3_516, 50, 46_874), 1_500);
+/// assert_eq!(pulse_us(compare(1_234, 400, 46_874), 400, 46_874), 1_234);
+/// ```
+pub fn pulse_us(compare: u16, frequency_hz: u32, modulo: u16) -> u32 {
+    let denominator = frequency_hz.max(1) as u64 * (modulo as u64 + 1);
+    let product = compare as u64 * MICROS_PER_SEC;
+    ((product + denominator / 2) / denominator) as u32
+}